@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+use polars::prelude::*;
+use crate::ui::ui_file_loader::files::CsvFile;
+use crate::utilities::math::{analytic_geometry, statistics};
+use crate::utilities::math::estimation::BlockGrid;
+
+/// Block of the model, centroid and size in world units.
+#[derive(Clone)]
+pub struct Block{
+    pub center: [f64;3],
+    pub size: [f64;3],
+    /// Attribute values in the same order as [`BlockModel::attributes`]
+    pub values: Vec<f64>,
+}
+
+#[derive(Component, Clone)]
+pub struct BlockModel{
+    pub attributes: Vec<String>,
+    pub blocks: Vec<Block>,
+    /// Bearing of the model y axis in degrees, clockwise from north. Block sizes are along the model axes
+    pub bearing: f64,
+    /// Subtracted from the centroids when building the mesh
+    pub offset: [f64;3],
+}
+
+/// How a block model is drawn.
+#[derive(Component, Clone, Default)]
+pub struct BlockModelDisplay{
+    /// Attribute the blocks are colored by, a single color is used when there is none
+    pub attribute: Option<String>,
+    /// Only the blocks whose attribute falls inside this range are drawn
+    pub filter: Option<[f64;2]>,
+}
+
+/// Placement of a model defined in its own frame: block centroids are read relative to `origin`
+/// along axes rotated by `bearing`. The default frame reads world coordinates.
+#[derive(Clone, Copy, Default)]
+pub struct BlockModelFrame{
+    pub origin: [f64;3],
+    /// Degrees, clockwise from north
+    pub bearing: f64,
+}
+
+impl BlockModelFrame {
+    pub fn to_world(&self, local: [f64;3]) -> [f64;3] {
+        let (sin, cos) = self.bearing.to_radians().sin_cos();
+        [
+            self.origin[0] + local[0] * cos + local[1] * sin,
+            self.origin[1] - local[0] * sin + local[1] * cos,
+            self.origin[2] + local[2],
+        ]
+    }
+}
+
+/// Faces closer than this are considered touching rather than overlapping.
+const OVERLAP_TOLERANCE: f64 = 1e-6;
+
+const COORDINATE_COLUMNS: [&str; 6] = ["x", "y", "z", "dx", "dy", "dz"];
+
+/// Corners of the unit cube faces, counter-clockwise seen from outside, with the face normal.
+const CUBE_FACES: [([f32;3], [[f32;3];4]); 6] = [
+    ([1.0, 0.0, 0.0], [[1.0, -1.0, -1.0], [1.0, 1.0, -1.0], [1.0, 1.0, 1.0], [1.0, -1.0, 1.0]]),
+    ([-1.0, 0.0, 0.0], [[-1.0, -1.0, 1.0], [-1.0, 1.0, 1.0], [-1.0, 1.0, -1.0], [-1.0, -1.0, -1.0]]),
+    ([0.0, 1.0, 0.0], [[-1.0, 1.0, 1.0], [1.0, 1.0, 1.0], [1.0, 1.0, -1.0], [-1.0, 1.0, -1.0]]),
+    ([0.0, -1.0, 0.0], [[-1.0, -1.0, -1.0], [1.0, -1.0, -1.0], [1.0, -1.0, 1.0], [-1.0, -1.0, 1.0]]),
+    ([0.0, 0.0, 1.0], [[-1.0, -1.0, 1.0], [1.0, -1.0, 1.0], [1.0, 1.0, 1.0], [-1.0, 1.0, 1.0]]),
+    ([0.0, 0.0, -1.0], [[1.0, -1.0, -1.0], [-1.0, -1.0, -1.0], [-1.0, 1.0, -1.0], [1.0, 1.0, -1.0]]),
+];
+
+impl BlockModel {
+
+    /// Reads the block centroids (x, y, z) in `frame`, sizes (dx, dy, dz) and every other numeric
+    /// column as an attribute. Sizes may change per row for sub-blocked models. The minimum
+    /// corner is used as offset when `offset` is `None`.
+    pub fn from_csv(csv: &CsvFile, frame: &BlockModelFrame, offset: Option<[f64;3]>) -> Result<Self, Box<dyn Error + Send + Sync>>{
+        let df = csv.dataframe()?;
+
+        let coordinates = COORDINATE_COLUMNS.iter()
+            .map(|name| Self::f64_column(&df, name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let attributes: Vec<String> = df.get_columns().iter()
+            .filter(|s| s.dtype().is_numeric() && !COORDINATE_COLUMNS.contains(&s.name()))
+            .map(|s| s.name().to_string())
+            .collect();
+        let values = attributes.iter()
+            .map(|name| Self::f64_column(&df, name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let blocks: Vec<Block> = (0..df.height())
+            .map(|row| Block{
+                center: frame.to_world([coordinates[0][row], coordinates[1][row], coordinates[2][row]]),
+                size: [coordinates[3][row], coordinates[4][row], coordinates[5][row]],
+                values: values.iter().map(|column| column[row]).collect(),
+            })
+            .collect();
+
+        if blocks.is_empty() {
+            return Err("The block model has no blocks".into());
+        }
+
+        let offset = offset.unwrap_or_else(|| {
+            blocks.iter().fold([f64::MAX; 3], |min, b| {
+                [
+                    min[0].min(b.center[0] - b.size[0] * 0.5),
+                    min[1].min(b.center[1] - b.size[1] * 0.5),
+                    min[2].min(b.center[2] - b.size[2] * 0.5),
+                ]
+            })
+        });
+
+        Ok(Self { attributes, blocks, bearing: frame.bearing, offset })
+    }
+
+    /// Model of the blocks of `grid` that have values, `values` holding the attributes of every
+    /// grid block in order.
+    pub fn from_grid(grid: &BlockGrid, attributes: Vec<String>, values: Vec<Option<Vec<f64>>>, offset: [f64;3]) -> Self {
+        let blocks = values.into_iter()
+            .enumerate()
+            .filter_map(|(block, values)| Some(Block{
+                center: grid.center(block),
+                size: grid.block_size,
+                values: values?,
+            }))
+            .collect();
+
+        Self { attributes, blocks, bearing: 0.0, offset }
+    }
+
+    fn f64_column(df: &DataFrame, name: &str) -> Result<Vec<f64>, Box<dyn Error + Send + Sync>> {
+        let series = df.column(name)
+            .map_err(|_| format!("Missing column '{}' in the block model", name))?
+            .cast(&DataType::Float64)?;
+        Ok(series.f64()?.into_iter().map(|v| v.unwrap_or(f64::NAN)).collect())
+    }
+
+    pub fn attribute_index(&self, attribute: &str) -> Option<usize> {
+        self.attributes.iter().position(|a| a == attribute)
+    }
+
+    /// Minimum and maximum of the finite values of `attribute`.
+    pub fn attribute_range(&self, attribute: &str) -> Option<[f64;2]> {
+        let index = self.attribute_index(attribute)?;
+        let sorted = statistics::sorted_finite(self.blocks.iter().map(|b| b.values[index]));
+        Some([*sorted.first()?, *sorted.last()?])
+    }
+
+    /// Pairs of blocks whose volumes intersect, compared in the model frame. Blocks are bucketed
+    /// on a grid of the largest block size so only neighbouring cells are tested.
+    pub fn overlapping_blocks(&self) -> Vec<(usize, usize)> {
+        let (sin, cos) = self.bearing.to_radians().sin_cos();
+        let bounds: Vec<([f64;3], [f64;3])> = self.blocks.iter()
+            .map(|b| {
+                let [x, y, z] = b.center;
+                let local = [x * cos - y * sin, x * sin + y * cos, z];
+                (
+                    [0, 1, 2].map(|i| local[i] - b.size[i] * 0.5),
+                    [0, 1, 2].map(|i| local[i] + b.size[i] * 0.5),
+                )
+            })
+            .collect();
+
+        let cell = self.blocks.iter().fold([OVERLAP_TOLERANCE; 3], |cell, b| {
+            [0, 1, 2].map(|i| cell[i].max(b.size[i]))
+        });
+        let key = |min: &[f64;3]| [0, 1, 2].map(|i| (min[i] / cell[i]).floor() as i64);
+
+        let mut grid: HashMap<[i64;3], Vec<usize>> = HashMap::new();
+        for (i, (min, _)) in bounds.iter().enumerate() {
+            grid.entry(key(min)).or_default().push(i);
+        }
+
+        let mut overlaps = Vec::new();
+        for (i, (min, max)) in bounds.iter().enumerate() {
+            let [kx, ky, kz] = key(min);
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let Some(cell_blocks) = grid.get(&[kx + dx, ky + dy, kz + dz]) else { continue };
+                        for &j in cell_blocks.iter().filter(|j| **j > i) {
+                            let (other_min, other_max) = &bounds[j];
+                            let intersects = (0..3).all(|axis| {
+                                min[axis] < other_max[axis] - OVERLAP_TOLERANCE
+                                    && other_min[axis] < max[axis] - OVERLAP_TOLERANCE
+                            });
+                            if intersects {
+                                overlaps.push((i, j));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        overlaps
+    }
+
+    /// Color of every block with `display`, scaled between the 25th and 75th percentiles of the
+    /// attribute. `None` for the blocks its filter hides.
+    pub fn colors(&self, display: &BlockModelDisplay) -> Vec<Option<[f32;4]>> {
+        let Some(index) = display.attribute.as_ref().and_then(|a| self.attribute_index(a)) else {
+            return vec![Some([0.8, 0.8, 0.8, 1.0]); self.blocks.len()];
+        };
+
+        let sorted = statistics::sorted_finite(self.blocks.iter().map(|b| b.values[index]));
+        let p25 = statistics::quantile(&sorted, 0.25).unwrap_or(0.0);
+        let p75 = statistics::quantile(&sorted, 0.75).unwrap_or(1.0);
+
+        self.blocks.iter()
+            .map(|block| {
+                let value = block.values[index];
+                if let Some([min, max]) = display.filter {
+                    if !(value >= min && value <= max) {
+                        return None;
+                    }
+                }
+                Some(super::mesh_handlers::color_scale(((value - p25) / (p75 - p25)) as f32))
+            })
+            .collect()
+    }
+
+    /// Merged cubes of the visible blocks with the colors of `display`.
+    pub fn mesh(&self, display: &BlockModelDisplay) -> Mesh{
+        // Scene y is the elevation, a clockwise bearing seen from above is a negative rotation about it
+        let rotation = Quat::from_rotation_y(-self.bearing.to_radians() as f32);
+
+        let mut positions: Vec<[f32;3]> = Vec::new();
+        let mut normals: Vec<[f32;3]> = Vec::new();
+        let mut colors: Vec<[f32;4]> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+
+        for (block, color) in self.blocks.iter().zip(self.colors(display)) {
+            let Some(color) = color else { continue };
+
+            let center = analytic_geometry::world_to_scene(block.center, self.offset);
+            let half = Vec3::new(block.size[0] as f32, block.size[2] as f32, block.size[1] as f32) * 0.5;
+
+            for (normal, corners) in CUBE_FACES.iter() {
+                let first = positions.len() as u32;
+                let normal = rotation * Vec3::from(*normal);
+                for corner in corners {
+                    positions.push((center + rotation * (Vec3::from(*corner) * half)).to_array());
+                    normals.push(normal.to_array());
+                    colors.push(color);
+                }
+                indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+            }
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+}
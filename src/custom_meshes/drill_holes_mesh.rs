@@ -1,6 +1,7 @@
 
 
 
+use std::collections::HashMap;
 use std::ops::Sub;
 use bevy::prelude::*;
 use bevy::prelude::shape::Cylinder;
@@ -13,7 +14,8 @@ use bevy::prelude::shape::Cylinder;
 
 use polars::prelude::*;
 use crate::ui::ui_file_loader::files::{CsvFile};
-use crate::utilities::math::analytic_geometry;
+use crate::utilities::math::{analytic_geometry, statistics};
+use crate::utilities::math::estimation::Sample;
 
 
 /// Saves the files
@@ -29,42 +31,270 @@ pub struct DrillHolesMesh{
     pub offset_z: Option<f32>,
 }
 
+/// Assay interval placed along its desurveyed hole trace, in scene coordinates.
+#[derive(Clone)]
+pub struct DrillInterval{
+    pub hole_id: String,
+    pub from: f32,
+    pub to: f32,
+    pub start: Vec3,
+    pub end: Vec3,
+    /// Rock code of the lithology interval holding the midpoint of the assay
+    pub rock: Option<String>,
+    /// Assay values in the same order as [`DrillHoleIntervals::variables`]
+    pub values: Vec<f64>,
+}
+
+/// Collar and orientation of a hole, the holes being straight.
+#[derive(Clone)]
+pub struct DrillHoleSurvey{
+    pub hole_id: String,
+    /// Collar minus the offset, as the intervals are placed
+    pub collar: [f32;3],
+    pub azimuth: f32,
+    pub dip: f32,
+}
+
+/// Intervals the drill hole layers were built from, kept so they can be exported or analysed.
+#[derive(Component, Clone)]
+pub struct DrillHoleIntervals{
+    pub variables: Vec<String>,
+    pub intervals: Vec<DrillInterval>,
+    /// Subtracted from the collars, restores the world coordinates
+    pub offset: [f64;3],
+    /// Lithology intervals (from, to, rock) of each hole, as loaded
+    pub lithology: HashMap<String, Vec<(f64, f64, String)>>,
+    /// Every hole of the header with a survey, in the order they were loaded
+    pub surveys: Vec<DrillHoleSurvey>,
+}
+
+impl DrillHoleIntervals {
+    pub fn variable_index(&self, variable: &str) -> Option<usize> {
+        self.variables.iter().position(|v| v == variable)
+    }
+
+    /// Replaces the values of `variable`, adding it when it does not exist yet.
+    pub fn set_variable(&mut self, variable: &str, values: impl IntoIterator<Item = f64>) {
+        let index = match self.variable_index(variable) {
+            Some(index) => index,
+            None => {
+                self.variables.push(variable.to_string());
+                for interval in self.intervals.iter_mut() {
+                    interval.values.push(f64::NAN);
+                }
+                self.variables.len() - 1
+            }
+        };
+        for (interval, value) in self.intervals.iter_mut().zip(values) {
+            interval.values[index] = value;
+        }
+    }
+
+    /// Rock codes present in the intervals, sorted.
+    pub fn rocks(&self) -> Vec<String> {
+        let mut rocks: Vec<String> = self.intervals.iter().filter_map(|i| i.rock.clone()).collect();
+        rocks.sort();
+        rocks.dedup();
+        rocks
+    }
+
+    /// Trace of every surveyed hole in the order they were loaded, from its collar down to its
+    /// deepest assay or lithology interval. Holes with neither are left out.
+    pub fn traces(&self) -> Vec<DrillHoleTrace> {
+        let mut depths: HashMap<&str, f64> = HashMap::new();
+        let assays = self.intervals.iter().map(|interval| (interval.hole_id.as_str(), interval.to as f64));
+        let lithology = self.lithology.iter()
+            .flat_map(|(hole_id, rocks)| rocks.iter().map(move |(_, to, _)| (hole_id.as_str(), *to)));
+        for (hole_id, to) in assays.chain(lithology) {
+            let depth = depths.entry(hole_id).or_insert(0.0);
+            *depth = depth.max(to);
+        }
+
+        self.surveys.iter()
+            .filter_map(|survey| {
+                let depth = *depths.get(survey.hole_id.as_str()).filter(|depth| **depth > 0.0)?;
+                let station = |depth: f64| {
+                    let position = analytic_geometry::interpolate_point_on_the_line(survey.collar, survey.azimuth, survey.dip, depth as f32);
+                    (depth, analytic_geometry::scene_to_world(position, self.offset))
+                };
+                Some(DrillHoleTrace { hole_id: survey.hole_id.clone(), stations: vec![station(0.0), station(depth)] })
+            })
+            .collect()
+    }
+
+    /// Holes of the lithology table missing from the header or the survey, which can not be
+    /// placed, sorted.
+    pub fn unsurveyed_lithology(&self) -> Vec<String> {
+        let mut holes: Vec<String> = self.lithology.keys()
+            .filter(|hole_id| !self.surveys.iter().any(|survey| &survey.hole_id == *hole_id))
+            .cloned()
+            .collect();
+        holes.sort();
+        holes
+    }
+
+    /// Rock codes of the lithology table, sorted.
+    pub fn lithology_rocks(&self) -> Vec<String> {
+        let mut rocks: Vec<String> = self.lithology.values().flatten().map(|(_, _, rock)| rock.clone()).collect();
+        rocks.sort();
+        rocks.dedup();
+        rocks
+    }
+
+    /// Contacts going downhole from `upper` to `lower` in consecutive lithology intervals, placed
+    /// on the hole traces halfway across any gap between the two intervals.
+    pub fn contacts(&self, upper: &str, lower: &str) -> Vec<DrillHoleContact> {
+        self.traces().into_iter()
+            .flat_map(|trace| {
+                let mut lithology = self.lithology.get(&trace.hole_id).cloned().unwrap_or_default();
+                lithology.sort_by(|a, b| a.0.total_cmp(&b.0));
+                lithology.windows(2)
+                    .filter(|pair| pair[0].2 == upper && pair[1].2 == lower)
+                    .filter_map(|pair| {
+                        let depth = (pair[0].1 + pair[1].0) * 0.5;
+                        Some(DrillHoleContact {
+                            hole_id: trace.hole_id.clone(),
+                            depth,
+                            position: trace.position_at(depth)?,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Signed distances along the holes to the boundary of the domain made of `rocks`, zero on
+    /// its contacts and at the midpoint of every lithology interval. Negative inside the domain
+    /// and limited to `max_distance`, which holes never crossing the boundary get.
+    pub fn domain_samples(&self, rocks: &[String], max_distance: f64) -> Vec<Sample> {
+        let inside = |rock: &String| rocks.contains(rock);
+        self.traces().into_iter()
+            .flat_map(|trace| {
+                let mut lithology = self.lithology.get(&trace.hole_id).cloned().unwrap_or_default();
+                lithology.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let contacts: Vec<f64> = lithology.windows(2)
+                    .filter(|pair| inside(&pair[0].2) != inside(&pair[1].2))
+                    .map(|pair| (pair[0].1 + pair[1].0) * 0.5)
+                    .collect();
+
+                let midpoints = lithology.iter().map(|(from, to, rock)| {
+                    let depth = (from + to) * 0.5;
+                    let distance = contacts.iter().map(|contact| (contact - depth).abs()).fold(max_distance, f64::min);
+                    (depth, if inside(rock) { -distance } else { distance })
+                });
+                contacts.iter()
+                    .map(|contact| (*contact, 0.0))
+                    .chain(midpoints)
+                    .filter_map(|(depth, value)| Some(Sample { position: trace.position_at(depth)?, value }))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Color of every interval by `variable`, scaled between its 25th and 75th percentiles.
+    pub fn colors(&self, variable: &str) -> Option<Vec<[f32;4]>> {
+        let index = self.variable_index(variable)?;
+
+        let sorted = statistics::sorted_finite(self.intervals.iter().map(|i| i.values[index]));
+        let p25_grade = statistics::quantile(&sorted, 0.25)? as f32;
+        let p75_grade = statistics::quantile(&sorted, 0.75)? as f32;
+
+        Some(self.intervals.iter()
+            .map(|interval| {
+                let grade = interval.values[index] as f32;
+                super::mesh_handlers::color_scale((grade-p25_grade)/(p75_grade-p25_grade))
+            })
+            .collect())
+    }
+
+    /// Interval midpoints in world coordinates with a finite value of `variable`.
+    pub fn samples(&self, variable: &str) -> Option<Vec<Sample>> {
+        let index = self.variable_index(variable)?;
+        Some(self.intervals.iter()
+            .filter(|interval| interval.values[index].is_finite())
+            .map(|interval| Sample{
+                position: analytic_geometry::scene_to_world((interval.start + interval.end) * 0.5, self.offset),
+                value: interval.values[index],
+            })
+            .collect())
+    }
+}
+
+/// Downhole path of a hole through its stations.
+pub struct DrillHoleTrace{
+    pub hole_id: String,
+    /// Depth and world position of the stations, sorted downhole
+    pub stations: Vec<(f64, [f64;3])>,
+}
+
+impl DrillHoleTrace {
+    /// World position at `depth`, extended along the first or last segment outside of the
+    /// stations. `None` with less than two stations.
+    pub fn position_at(&self, depth: f64) -> Option<[f64;3]> {
+        if self.stations.len() < 2 {
+            return None;
+        }
+        let segment = self.stations.windows(2)
+            .find(|pair| depth <= pair[1].0)
+            .unwrap_or(&self.stations[self.stations.len() - 2..]);
+        let ((from, a), (to, b)) = (segment[0], segment[1]);
+        let t = (depth - from) / (to - from);
+        Some([0, 1, 2].map(|i| a[i] + t * (b[i] - a[i])))
+    }
+}
+
+/// Downhole point where the lithology changes from one rock to another.
+pub struct DrillHoleContact{
+    pub hole_id: String,
+    pub depth: f64,
+    /// World coordinates
+    pub position: [f64;3],
+}
+
+/// Marks a drill holes mesh and the assay variable its colors show.
+#[derive(Component, Clone)]
+pub struct DrillHoleLayer{
+    pub variable: String,
+}
+
+/// Marks the mesh highlighting the intervals selected in the scatter plot, child of the layer
+/// the intervals belong to.
+#[derive(Component)]
+pub struct DrillHoleSelection;
+
+/// Variables shown as a layer right after loading the drill holes
+const DEFAULT_LAYERS: [&str; 2] = ["au", "cu"];
+
 impl DrillHolesMesh {
-    pub fn from_csv(drill_holes: DrillHolesMesh) -> Vec<Mesh>{
+    pub fn from_csv(drill_holes: DrillHolesMesh) -> (Vec<(String, Mesh)>, DrillHoleIntervals){
+        let intervals = Self::intervals(drill_holes);
+
+        let meshes = DEFAULT_LAYERS.iter()
+            .filter_map(|variable| {
+                Self::layer_mesh(&intervals, variable).map(|mesh| (variable.to_string(), mesh))
+            })
+            .collect();
+
+        (meshes, intervals)
+    }
+
+    fn intervals(drill_holes: DrillHolesMesh) -> DrillHoleIntervals{
         let assay = &drill_holes.files[0];
         let header = &drill_holes.files[1];
-        let lithography = &drill_holes.files[2];
         let survey = &drill_holes.files[3];
-
-
+        let lithology = Self::lithology(&drill_holes.files[2]).unwrap_or_default();
 
         let df_assay = assay.dataframe().unwrap();
         let mut df_header = header.dataframe().unwrap();
         let df_survey = survey.dataframe().unwrap();
-        let df_lithography = lithography.dataframe().unwrap();
-
-        let mut au_grades_meshes_result: Vec<Mesh> = Vec::new();
-        let mut cu_grades_meshes_result: Vec<Mesh> = Vec::new();
-        let mut transforms_result: Vec<Transform> = Vec::new();
-        let _material_au_result: Vec<[f32;3]> = Vec::new();
-        let _material_cu_result: Vec<[f32;3]> = Vec::new();
-        let _material_lithography: Vec<[f32;3]> = Vec::new();
 
-        let p25_grade_au = df_assay.column("au").unwrap().f64().unwrap()
-            .quantile(0.25, QuantileInterpolOptions::Linear).unwrap().unwrap() as f32;
-        let p75_grade_au = df_assay.column("au").unwrap().f64().unwrap()
-            .quantile(0.75, QuantileInterpolOptions::Linear).unwrap().unwrap() as f32;
-
-        let p25_grade_cu = df_assay.column("cu").unwrap().f64().unwrap()
-            .quantile(0.25, QuantileInterpolOptions::Linear).unwrap().unwrap() as f32;
-        let p75_grade_cu = df_assay.column("cu").unwrap().f64().unwrap()
-            .quantile(0.75, QuantileInterpolOptions::Linear).unwrap().unwrap() as f32;
-
-        let _p25_lithography = df_lithography.column("rock").unwrap().i64().unwrap()
-            .quantile(0.25, QuantileInterpolOptions::Linear).unwrap().unwrap() as f32;
-        let _p75_lithography = df_lithography.column("rock").unwrap().i64().unwrap()
-            .quantile(0.75, QuantileInterpolOptions::Linear).unwrap().unwrap() as f32;
+        let variables: Vec<String> = df_assay.get_columns().iter()
+            .filter(|s| s.dtype().is_numeric() && !["hole-id", "from", "to"].contains(&s.name()))
+            .map(|s| s.name().to_string())
+            .collect();
 
+        let mut intervals: Vec<DrillInterval> = Vec::new();
+        let mut surveys: Vec<DrillHoleSurvey> = Vec::new();
 
         let x_header_colum = df_header.column("x").unwrap().sub(drill_holes.offset_x.unwrap());
         df_header = (*df_header.with_column(x_header_colum).unwrap()).clone();
@@ -91,21 +321,26 @@ impl DrillHolesMesh {
             let azimuth = iters_drills[6].next().unwrap().try_extract::<f32>().unwrap();
             let dip = iters_drills[7].next().unwrap().try_extract::<f32>().unwrap();
 
+            if !surveys.iter().any(|survey| survey.hole_id == hole_id) {
+                surveys.push(DrillHoleSurvey { hole_id: hole_id.clone(), collar: [x, y, z], azimuth, dip });
+            }
+
             let df_filtered_assays = df_assay.filter(&df_assay
                 .column("hole-id").unwrap().utf8().unwrap()
                 .contains_literal(&hole_id).unwrap()).unwrap();
 
             let mut iters_assay = df_filtered_assays
-                .columns(["hole-id","from","to","au","cu"]).unwrap()
+                .columns(["from","to"]).unwrap()
                 .iter().map(|s| s.iter()).collect::<Vec<_>>();
 
-            for _row_assay in 0..df_filtered_assays.height(){
+            let values = variables.iter()
+                .map(|v| df_filtered_assays.column(v).unwrap().cast(&DataType::Float64).unwrap())
+                .collect::<Vec<_>>();
+
+            for row_assay in 0..df_filtered_assays.height(){
 
-                let _hole_id = iters_assay[0].next().unwrap().to_string().replace("\"", "");
-                let from = iters_assay[1].next().unwrap().try_extract::<f32>().unwrap();
-                let to = iters_assay[2].next().unwrap().try_extract::<f32>().unwrap();
-                let au = iters_assay[3].next().unwrap().try_extract::<f32>().unwrap();
-                let cu = iters_assay[4].next().unwrap().try_extract::<f32>().unwrap();
+                let from = iters_assay[0].next().unwrap().try_extract::<f32>().unwrap();
+                let to = iters_assay[1].next().unwrap().try_extract::<f32>().unwrap();
 
                 let grade_from_coord = analytic_geometry::interpolate_point_on_the_line(
                     [x,y,z],
@@ -121,45 +356,104 @@ impl DrillHolesMesh {
                     to
                 );
 
-                let prisma_mesh = Self::generate_triangular_prisma(
-                    &grade_from_coord,
-                    &grade_to_coord,
-                    3.0);
-
+                let middle = (from + to) as f64 * 0.5;
+                let rock = lithology.get(&hole_id).and_then(|rocks| {
+                    rocks.iter()
+                        .find(|(rock_from, rock_to, _)| *rock_from <= middle && middle < *rock_to)
+                        .map(|(_, _, rock)| rock.clone())
+                });
+
+                intervals.push(DrillInterval{
+                    hole_id: hole_id.clone(),
+                    from,
+                    to,
+                    start: grade_from_coord,
+                    end: grade_to_coord,
+                    rock,
+                    values: values.iter()
+                        .map(|s| s.f64().unwrap().get(row_assay).unwrap_or(f64::NAN))
+                        .collect(),
+                });
+            }
+        }
 
-                let material_au_grade = super::mesh_handlers::color_scale((au-p25_grade_au)/(p75_grade_au-p25_grade_au));
-                let material_cu_grade = super::mesh_handlers::color_scale((cu-p25_grade_cu)/(p75_grade_cu-p25_grade_cu));
+        DrillHoleIntervals{
+            variables,
+            intervals,
+            offset: [
+                drill_holes.offset_x.unwrap() as f64,
+                drill_holes.offset_y.unwrap() as f64,
+                drill_holes.offset_z.unwrap() as f64,
+            ],
+            lithology,
+            surveys,
+        }
+    }
 
-                let mut prisma_cu = prisma_mesh.clone();
-                let mut prisma_au = prisma_mesh;
+    /// Lithology intervals (from, to, rock) of each hole. Holes or files without a "rock" column
+    /// are simply missing, the lithology is optional.
+    fn lithology(lithology: &CsvFile) -> PolarsResult<HashMap<String, Vec<(f64, f64, String)>>> {
+        let df = lithology.dataframe()?;
+        let hole_ids = df.column("hole-id")?.cast(&DataType::Utf8)?;
+        let froms = df.column("from")?.cast(&DataType::Float64)?;
+        let tos = df.column("to")?.cast(&DataType::Float64)?;
+        let rocks = df.column("rock")?.cast(&DataType::Utf8)?;
+
+        let mut holes: HashMap<String, Vec<(f64, f64, String)>> = HashMap::new();
+        for (((hole_id, from), to), rock) in hole_ids.utf8()?.into_iter()
+            .zip(froms.f64()?.into_iter())
+            .zip(tos.f64()?.into_iter())
+            .zip(rocks.utf8()?.into_iter())
+        {
+            if let (Some(hole_id), Some(from), Some(to), Some(rock)) = (hole_id, from, to, rock) {
+                holes.entry(hole_id.to_string()).or_default().push((from, to, rock.to_string()));
+            }
+        }
+        Ok(holes)
+    }
 
-                prisma_au.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![material_au_grade; prisma_au.count_vertices()]);
-                prisma_cu.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![material_cu_grade; prisma_cu.count_vertices()]);
+    /// Builds the prisms of every interval colored by `variable`, see [`DrillHoleIntervals::colors`].
+    pub fn layer_mesh(intervals: &DrillHoleIntervals, variable: &str) -> Option<Mesh>{
+        let colors = intervals.colors(variable)?;
 
+        let mut grades_meshes_result: Vec<Mesh> = Vec::new();
+        let mut transforms_result: Vec<Transform> = Vec::new();
 
-                au_grades_meshes_result.push(prisma_au);
-                cu_grades_meshes_result.push(prisma_cu);
-                let transform = (grade_from_coord + grade_to_coord)*0.5;
-                transforms_result.push(Transform::from_xyz(transform.x,transform.y,transform.z));
+        for (interval, material_grade) in intervals.intervals.iter().zip(colors){
+            let mut prisma = Self::generate_triangular_prisma(
+                &interval.start,
+                &interval.end,
+                3.0);
 
-            }
+            prisma.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![material_grade; prisma.count_vertices()]);
 
+            grades_meshes_result.push(prisma);
+            let transform = (interval.start + interval.end)*0.5;
+            transforms_result.push(Transform::from_xyz(transform.x,transform.y,transform.z));
+        }
 
+        Some(super::mesh_handlers::combine_meshes(grades_meshes_result,
+                                                  transforms_result,
+                                                  true, false,
+                                                  false, true))
+    }
 
+    /// Builds slightly wider prisms around the intervals at `indices`, so they stand out from the
+    /// layer they belong to. `None` when no index is valid.
+    pub fn selection_mesh(intervals: &DrillHoleIntervals, indices: &[usize]) -> Option<Mesh>{
+        let selected: Vec<&DrillInterval> = indices.iter().filter_map(|i| intervals.intervals.get(*i)).collect();
+        if selected.is_empty() {
+            return None;
         }
 
-        let au_final_mesh = super::mesh_handlers::combine_meshes(au_grades_meshes_result,
-                                                              transforms_result.clone(),
-                                                              true, false,
-                                                              false, true);
-
-        let cu_final_mesh = super::mesh_handlers::combine_meshes(cu_grades_meshes_result,
-                                                                 transforms_result,
-                                                                 true, false,
-                                                                 false, true);
+        let meshes = selected.iter()
+            .map(|interval| Self::generate_triangular_prisma(&interval.start, &interval.end, 4.0))
+            .collect();
+        let transforms = selected.iter()
+            .map(|interval| Transform::from_translation((interval.start + interval.end)*0.5))
+            .collect();
 
-        //TODO
-        vec![au_final_mesh, cu_final_mesh]
+        Some(super::mesh_handlers::combine_meshes(meshes, transforms, true, false, false, false))
     }
 
     fn generate_triangular_prisma(
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+use crate::ui::ui_file_loader::mesh_files::MeshData;
+use crate::utilities::math::analytic_geometry;
+
+/// Triangulated surface or solid read from an OBJ, STL or PLY file (pit designs, underground
+/// development, geological wireframes).
+#[derive(Component)]
+pub struct ImportedMesh{
+    pub offset_x: f64,
+    pub offset_y: f64,
+    pub offset_z: f64,
+}
+
+impl ImportedMesh {
+    /// Moves `data` to the scene using `offset`, or its own minimum corner when there is none.
+    pub fn from_mesh_data(data: MeshData, offset: Option<[f64;3]>) -> (Mesh, Self){
+        let offset = offset.unwrap_or_else(|| {
+            data.positions.iter().fold([f64::MAX; 3], |min, p| {
+                [min[0].min(p[0]), min[1].min(p[1]), min[2].min(p[2])]
+            })
+        });
+
+        let positions: Vec<Vec3> = data.positions.iter()
+            .map(|p| analytic_geometry::world_to_scene(*p, offset))
+            .collect();
+
+        let mut normals = vec![Vec3::ZERO; positions.len()];
+        for [a, b, c] in data.triangles.iter().map(|t| t.map(|i| i as usize)) {
+            let normal = (positions[b] - positions[a]).cross(positions[c] - positions[a]).normalize_or_zero();
+            normals[a] += normal;
+            normals[b] += normal;
+            normals[c] += normal;
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals.iter().map(|n| n.normalize_or_zero()).collect::<Vec<_>>());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0., 0.]; positions.len()]);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        if let Some(colors) = data.colors {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        }
+        mesh.set_indices(Some(Indices::U32(
+            data.triangles.iter().flatten().copied().collect()
+        )));
+
+        (mesh, Self { offset_x: offset[0], offset_y: offset[1], offset_z: offset[2] })
+    }
+}
@@ -2,6 +2,10 @@ use bevy::math::Vec4Swizzles;
 use bevy::prelude::*;
 use bevy::render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
 
+use crate::ui::ui_file_loader::mesh_files::MeshData;
+use crate::utilities::math::analytic_geometry;
+use crate::utilities::math::surface::SurfaceSampler;
+
 
 pub fn combine_meshes(
     meshes: Vec<Mesh>,
@@ -188,3 +192,213 @@ pub fn color_scale(value: f32) -> [f32; 4] {
 
 
 
+
+/// Positions and triangles of a triangle list mesh, whether it is indexed or not.
+pub fn mesh_triangles(mesh: &Mesh) -> Option<(Vec<Vec3>, Vec<[u32; 3]>)> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return None;
+    }
+
+    let positions: Vec<Vec3> = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => {
+            positions.iter().map(|p| Vec3::from(*p)).collect()
+        }
+        _ => return None,
+    };
+
+    let indices: Vec<u32> = match mesh.indices() {
+        Some(Indices::U32(indices)) => indices.clone(),
+        Some(Indices::U16(indices)) => indices.iter().map(|i| *i as u32).collect(),
+        None => (0..positions.len() as u32).collect(),
+    };
+
+    let triangles = indices
+        .chunks_exact(3)
+        .map(|chunk| [chunk[0], chunk[1], chunk[2]])
+        .collect();
+
+    Some((positions, triangles))
+}
+
+/// Distance along `ray`, in units of its direction, to the closest triangle of `mesh` it goes
+/// through from either side.
+pub fn ray_intersection(mesh: &Mesh, ray: Ray) -> Option<f32> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return None;
+    }
+    let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
+        return None;
+    };
+    match mesh.indices() {
+        Some(Indices::U16(indices)) => closest_triangle(ray, positions, indices.chunks_exact(3).map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])),
+        Some(Indices::U32(indices)) => closest_triangle(ray, positions, indices.chunks_exact(3).map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])),
+        None => closest_triangle(ray, positions, (0..positions.len() / 3).map(|t| [3 * t, 3 * t + 1, 3 * t + 2])),
+    }
+}
+
+fn closest_triangle(ray: Ray, positions: &[[f32; 3]], triangles: impl Iterator<Item = [usize; 3]>) -> Option<f32> {
+    triangles
+        .filter_map(|triangle| {
+            let [a, b, c] = triangle.map(|i| positions.get(i).map(|p| Vec3::from(*p)));
+            ray_triangle_intersection(ray, [a?, b?, c?])
+        })
+        .min_by(|a, b| a.total_cmp(b))
+}
+
+/// Distance along `ray`, in units of its direction, to where it enters the box from `min` to
+/// `max`, zero when it starts inside.
+pub fn ray_box_intersection(ray: Ray, min: Vec3, max: Vec3) -> Option<f32> {
+    let inverse = ray.direction.recip();
+    let (a, b) = ((min - ray.origin) * inverse, (max - ray.origin) * inverse);
+    // A component of the direction at zero gives NaN when the origin is on a face, ignored by min and max
+    let (near, far) = (a.min(b).max_element().max(0.0), a.max(b).min_element());
+    (near <= far).then_some(near)
+}
+
+/// Möller–Trumbore intersection of `ray` with a triangle.
+fn ray_triangle_intersection(ray: Ray, [a, b, c]: [Vec3; 3]) -> Option<f32> {
+    let (ab, ac) = (b - a, c - a);
+    let p = ray.direction.cross(ac);
+    let determinant = ab.dot(p);
+    if determinant.abs() <= f32::MIN_POSITIVE {
+        return None;
+    }
+
+    let to_origin = ray.origin - a;
+    let u = to_origin.dot(p) / determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = to_origin.cross(ab);
+    let v = ray.direction.dot(q) / determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let distance = ac.dot(q) / determinant;
+    (distance >= 0.0).then_some(distance)
+}
+
+/// Triangles and vertex colors of `mesh` moved back to world coordinates, ready to be written
+/// to a mesh file.
+pub fn mesh_to_data(mesh: &Mesh, offset: [f64; 3]) -> Option<MeshData> {
+    let (positions, triangles) = mesh_triangles(mesh)?;
+
+    let colors = match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+        Some(VertexAttributeValues::Float32x4(colors)) => Some(colors.clone()),
+        _ => None,
+    };
+
+    Some(MeshData {
+        positions: positions
+            .into_iter()
+            .map(|p| analytic_geometry::scene_to_world(p, offset))
+            .collect(),
+        triangles,
+        colors,
+    })
+}
+
+/// Elevation sampler over the triangles of `mesh`, in world coordinates.
+pub fn surface_sampler(mesh: &Mesh, offset: [f64; 3]) -> Option<SurfaceSampler> {
+    let data = mesh_to_data(mesh, offset)?;
+    Some(SurfaceSampler::new(data.positions, data.triangles))
+}
+
+/// Vertex attributes of a mesh being clipped, cut points are appended to them.
+struct ClipVertices {
+    positions: Vec<Vec3>,
+    normals: Option<Vec<Vec3>>,
+    colors: Option<Vec<Vec4>>,
+    uvs: Option<Vec<Vec2>>,
+}
+
+impl ClipVertices {
+    /// Appends the vertex at `t` between the vertices `a` and `b`, returning its index.
+    fn interpolate(&mut self, a: u32, b: u32, t: f32) -> u32 {
+        let (a, b) = (a as usize, b as usize);
+        self.positions.push(self.positions[a].lerp(self.positions[b], t));
+        if let Some(normals) = &mut self.normals {
+            normals.push(normals[a].lerp(normals[b], t).normalize_or_zero());
+        }
+        if let Some(colors) = &mut self.colors {
+            colors.push(colors[a].lerp(colors[b], t));
+        }
+        if let Some(uvs) = &mut self.uvs {
+            uvs.push(uvs[a].lerp(uvs[b], t));
+        }
+        (self.positions.len() - 1) as u32
+    }
+}
+
+/// Part of a triangle list `mesh` on the negative side of every plane, given as the coefficients
+/// (a, b, c, d) of `a·x + b·y + c·z + d` in the mesh coordinates. Triangles crossing a plane are
+/// cut, interpolating their positions, normals, colors and uvs.
+pub fn clip_mesh(mesh: &Mesh, planes: &[Vec4]) -> Option<Mesh> {
+    let (positions, triangles) = mesh_triangles(mesh)?;
+
+    let mut vertices = ClipVertices {
+        positions,
+        normals: match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(normals)) => Some(normals.iter().map(|n| Vec3::from(*n)).collect()),
+            _ => None,
+        },
+        colors: match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+            Some(VertexAttributeValues::Float32x4(colors)) => Some(colors.iter().map(|c| Vec4::from(*c)).collect()),
+            _ => None,
+        },
+        uvs: match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(uvs)) => Some(uvs.iter().map(|uv| Vec2::from(*uv)).collect()),
+            _ => None,
+        },
+    };
+
+    let mut indices: Vec<u32> = Vec::new();
+    for triangle in triangles {
+        // Sutherland–Hodgman, one plane at a time
+        let mut polygon = triangle.to_vec();
+        for plane in planes {
+            let distances: Vec<f32> = polygon.iter()
+                .map(|i| plane.xyz().dot(vertices.positions[*i as usize]) + plane.w)
+                .collect();
+            if distances.iter().all(|d| *d <= 0.0) {
+                continue;
+            }
+            if distances.iter().all(|d| *d > 0.0) {
+                polygon.clear();
+                break;
+            }
+
+            let mut clipped = Vec::with_capacity(polygon.len() + 1);
+            for (k, vertex) in polygon.iter().enumerate() {
+                let next = (k + 1) % polygon.len();
+                let (d, d_next) = (distances[k], distances[next]);
+                if d <= 0.0 {
+                    clipped.push(*vertex);
+                }
+                if (d <= 0.0) != (d_next <= 0.0) {
+                    clipped.push(vertices.interpolate(*vertex, polygon[next], d / (d - d_next)));
+                }
+            }
+            polygon = clipped;
+        }
+
+        for k in 1..polygon.len().saturating_sub(1) {
+            indices.extend([polygon[0], polygon[k], polygon[k + 1]]);
+        }
+    }
+
+    let mut clipped = Mesh::new(PrimitiveTopology::TriangleList);
+    clipped.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices.positions.iter().map(|p| p.to_array()).collect::<Vec<_>>());
+    if let Some(normals) = vertices.normals {
+        clipped.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals.iter().map(|n| n.to_array()).collect::<Vec<_>>());
+    }
+    if let Some(colors) = vertices.colors {
+        clipped.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors.iter().map(|c| c.to_array()).collect::<Vec<_>>());
+    }
+    if let Some(uvs) = vertices.uvs {
+        clipped.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs.iter().map(|uv| uv.to_array()).collect::<Vec<_>>());
+    }
+    clipped.set_indices(Some(Indices::U32(indices)));
+    Some(clipped)
+}
@@ -2,3 +2,5 @@
 pub mod topography_mesh;
 pub mod drill_holes_mesh;
 pub mod mesh_handlers;
+pub mod imported_mesh;
+pub mod block_model_mesh;
\ No newline at end of file
@@ -3,6 +3,7 @@ use std::error::Error;
 use std::io::{BufReader};
 
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 
 
 use delaunator::{Point, triangulate};
@@ -10,6 +11,9 @@ use bevy::render::mesh::{PrimitiveTopology};
 
 use csv::ReaderBuilder;
 use crate::ui::ui_file_loader::files::{CsvFile};
+use crate::utilities::math::analytic_geometry;
+use crate::utilities::math::grid::{Grid, GridGeometry};
+use super::mesh_handlers;
 
 
 #[derive(Component)]
@@ -39,11 +43,14 @@ impl TopographyMesh {
 
     fn create_mesh(vec: Vec<[f64;3]>) -> Mesh{
         let points = vec.iter().map(|v| Point { x: v[0], y: v[1] }).collect::<Vec<Point>>();
-        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
         let result = triangulate(&points);
 
-        let triangles = result.triangles;
-        let vector_values = vec.iter().map(|v| Vec3::new(v[0] as f32, v[2] as f32, v[1] as f32)).collect::<Vec<_>>();
+        Self::build_mesh(vec, result.triangles)
+    }
+
+    fn build_mesh(vec: Vec<[f64;3]>, triangles: Vec<usize>) -> Mesh{
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        let vector_values = vec.iter().map(|v| analytic_geometry::world_to_scene(*v, [0.0; 3])).collect::<Vec<_>>();
         let normals = Self::calculate_normals(&vector_values, &triangles);
 
         mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0., 0.]; vector_values.len()]);
@@ -69,6 +76,138 @@ impl TopographyMesh {
         (mesh, Self { offset_x:min_x, offset_y: min_y, offset_z: min_z })
     }
 
+    /// Builds the surface from an existing triangulation (e.g. 3DFACE entities) keeping its triangles
+    /// as they are. Shared corners are merged so the normals are smoothed across triangles.
+    pub fn from_faces(faces: &[[[f64;3];3]]) -> (Mesh, Self){
+        let mut vec: Vec<[f64;3]> = Vec::new();
+        let mut triangles: Vec<usize> = Vec::with_capacity(faces.len() * 3);
+        let mut indices: HashMap<[u64;3], usize> = HashMap::new();
+
+        for face in faces {
+            for corner in face {
+                let key = corner.map(|c| c.to_bits());
+                let index = *indices.entry(key).or_insert_with(|| {
+                    vec.push(*corner);
+                    vec.len() - 1
+                });
+                triangles.push(index);
+            }
+        }
+
+        let min_x = vec.iter().map(|v| v[0]).fold(f64::MAX, f64::min);
+        let min_y = vec.iter().map(|v| v[1]).fold(f64::MAX, f64::min);
+        let min_z = vec.iter().map(|v| v[2]).fold(f64::MAX, f64::min);
+
+        for v in vec.iter_mut() {
+            v[0] -= min_x;
+            v[1] -= min_y;
+            v[2] -= min_z;
+        };
+        let mesh = Self::build_mesh(vec, triangles);
+
+        (mesh, Self { offset_x:min_x, offset_y: min_y, offset_z: min_z })
+    }
+
+    /// Builds the surface from scattered points plus breaklines. The breaklines are densified
+    /// so the triangulation follows them even though it isn't constrained.
+    pub fn from_points_with_breaklines(mut vec: Vec<[f64;3]>, breaklines: &[Vec<[f64;3]>]) -> (Mesh, Self){
+        let spacing = Self::breakline_spacing(&vec, breaklines);
+
+        for breakline in breaklines {
+            for segment in breakline.windows(2) {
+                let (a, b) = (segment[0], segment[1]);
+                let length = (b[0] - a[0]).hypot(b[1] - a[1]);
+                let steps = (length / spacing).ceil().max(1.0) as usize;
+                for step in 0..steps {
+                    let t = step as f64 / steps as f64;
+                    vec.push([
+                        a[0] + t * (b[0] - a[0]),
+                        a[1] + t * (b[1] - a[1]),
+                        a[2] + t * (b[2] - a[2]),
+                    ]);
+                }
+            }
+            if let Some(last) = breakline.last() {
+                vec.push(*last);
+            }
+        }
+
+        Self::from_points(vec)
+    }
+
+    /// Mean distance between points if they were spread evenly over their bounding box.
+    fn breakline_spacing(points: &[[f64;3]], breaklines: &[Vec<[f64;3]>]) -> f64 {
+        let mut min = [f64::MAX, f64::MAX];
+        let mut max = [f64::MIN, f64::MIN];
+        let mut count = 0;
+        for p in points.iter().chain(breaklines.iter().flatten()) {
+            min = [min[0].min(p[0]), min[1].min(p[1])];
+            max = [max[0].max(p[0]), max[1].max(p[1])];
+            count += 1;
+        }
+        let area = (max[0] - min[0]) * (max[1] - min[1]);
+        let spacing = (area / count.max(1) as f64).sqrt();
+        if spacing.is_finite() && spacing > 0.0 { spacing } else { 1.0 }
+    }
+
+    pub fn offset(&self) -> [f64;3] {
+        [self.offset_x, self.offset_y, self.offset_z]
+    }
+
+    /// Lower and upper corners in plan of the surface built in `mesh`, in world coordinates.
+    pub fn plan_bounds(&self, mesh: &Mesh) -> Option<([f64;2], [f64;2])> {
+        mesh_handlers::surface_sampler(mesh, self.offset()).map(|surface| surface.bounds())
+    }
+
+    /// Elevations of the surface built in `mesh` at the cell centers of a grid, undefined
+    /// outside of the triangulation.
+    pub fn resample_to_grid(&self, mesh: &Mesh, geometry: GridGeometry) -> Option<Grid> {
+        let surface = mesh_handlers::surface_sampler(mesh, self.offset())?;
+        Some(Grid::from_fn(geometry, |x, y| surface.elevation(x, y)))
+    }
+
+    /// Builds the surface through the cell centers of a grid, two triangles for every square of
+    /// defined centers. The vertices follow the order of the defined values. `None` when no
+    /// square is defined.
+    pub fn from_grid(grid: &Grid) -> Option<(Mesh, Self)> {
+        let geometry = grid.geometry;
+        let (min_z, _) = grid.range()?;
+        let [min_x, min_y] = geometry.origin;
+
+        let mut vec: Vec<[f64;3]> = Vec::new();
+        let vertices: Vec<Option<usize>> = grid.values.iter()
+            .enumerate()
+            .map(|(i, value)| value.map(|z| {
+                let [x, y] = geometry.center(i % geometry.columns, i / geometry.columns);
+                vec.push([x - min_x, y - min_y, z - min_z]);
+                vec.len() - 1
+            }))
+            .collect();
+
+        let mut triangles: Vec<usize> = Vec::new();
+        for row in 0..geometry.rows.saturating_sub(1) {
+            for column in 0..geometry.columns.saturating_sub(1) {
+                let vertex = |c: usize, r: usize| vertices[r * geometry.columns + c];
+                let (Some(a), Some(b), Some(c), Some(d)) = (
+                    vertex(column, row),
+                    vertex(column + 1, row),
+                    vertex(column + 1, row + 1),
+                    vertex(column, row + 1),
+                ) else {
+                    continue;
+                };
+                // Counter-clockwise seen from above, so the normals point up
+                triangles.extend([a, b, c, a, c, d]);
+            }
+        }
+        if triangles.is_empty() {
+            return None;
+        }
+
+        let mesh = Self::build_mesh(vec, triangles);
+        Some((mesh, Self { offset_x: min_x, offset_y: min_y, offset_z: min_z }))
+    }
+
     pub fn from_csv(csv: &CsvFile) -> Result<(Mesh, Self), Box<dyn Error>>{
 
         let file = csv.get_file().unwrap();
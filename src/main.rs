@@ -5,16 +5,27 @@ mod ui;
 mod systems;
 mod custom_meshes;
 mod utilities;
+mod project;
 
 
 
 use systems::*;
+use project::{DxfLayerSelection, Project, Variograms};
+use utilities::math::estimation::Anisotropy;
+use utilities::math::variogram::{StructureKind, VariogramModel, VariogramStructure};
 use ui::ui_setup::EditorPlugin;
 
 fn main() {
     App::new()
         .insert_resource(ClearColor(Color::rgb(28.0/255.0, 28.0/255.0, 36.0/255.0)))
         .insert_resource(Msaa::Sample4)
+        .init_resource::<Project>()
+        .register_type::<DxfLayerSelection>()
+        .register_type::<Variograms>()
+        .register_type::<VariogramModel>()
+        .register_type::<VariogramStructure>()
+        .register_type::<StructureKind>()
+        .register_type::<Anisotropy>()
 
         .add_plugins(DefaultPlugins.set(WindowPlugin{
             primary_window: Some(Window {
@@ -28,5 +39,7 @@ fn main() {
         .add_plugin(EntityCountDiagnosticsPlugin)
         .add_plugin(InfiniteGridPlugin)
         .add_startup_system(setup_system)
+        .add_system(apply_vertical_exaggeration)
+        .add_system(show_hidden_datasets)
         .run();
 }
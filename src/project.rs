@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::utilities::math::analytic_geometry;
+use crate::utilities::math::variogram::VariogramModel;
+
+/// Layer roles a dxf file was imported with, kept on the topography it produced so they are saved
+/// with the scene and offered again when the same file is imported.
+#[derive(Component, Reflect, Clone, Default)]
+#[reflect(Component)]
+pub struct DxfLayerSelection {
+    /// Path of the imported file
+    pub path: String,
+    pub point_layers: Vec<String>,
+    pub breakline_layers: Vec<String>,
+}
+
+/// Variogram models fitted in the variography window, keyed by variable. Kept on the drill holes
+/// they were fitted on so they are saved with the scene.
+#[derive(Component, Reflect, Clone, Default)]
+#[reflect(Component)]
+pub struct Variograms(pub HashMap<String, VariogramModel>);
+
+/// Settings that belong to the project being edited rather than to a single window.
+#[derive(Resource)]
+pub struct Project {
+    /// World coordinates of the scene origin, fixed by the first dataset loaded
+    pub offset: Option<[f64;3]>,
+    /// Factor the elevations are multiplied by in the viewport, the data keeping its true values
+    pub vertical_exaggeration: f32,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self {
+            offset: None,
+            vertical_exaggeration: 1.0,
+        }
+    }
+}
+
+impl Project {
+    /// World coordinates of a point of the viewport, in true elevation.
+    pub fn displayed_to_world(&self, point: Vec3) -> [f64;3] {
+        let point = Vec3::new(point.x, point.y / self.vertical_exaggeration, point.z);
+        analytic_geometry::scene_to_world(point, self.offset.unwrap_or_default())
+    }
+
+    /// Inverse of [`Project::displayed_to_world`].
+    pub fn world_to_displayed(&self, point: [f64;3]) -> Vec3 {
+        let point = analytic_geometry::world_to_scene(point, self.offset.unwrap_or_default());
+        Vec3::new(point.x, point.y * self.vertical_exaggeration, point.z)
+    }
+}
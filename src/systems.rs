@@ -1,8 +1,18 @@
-use bevy::{prelude::*, pbr::{CascadeShadowConfigBuilder}};
+use bevy::{prelude::*, pbr::{CascadeShadowConfigBuilder}, render::view::RenderLayers};
 use bevy_infinite_grid::{InfiniteGrid, InfiniteGridBundle};
 
+use crate::project::Project;
+use crate::ui::ui_windows::clipping::{ClipBox, ClipPlane};
 use crate::ui::ui_windows::hierarchy::HideInEditor;
 
+/// Vertical exaggeration the transform of a top-level dataset or clip is scaled by.
+#[derive(Component)]
+pub struct Exaggerated(pub f32);
+
+/// Dataset hidden while the entity standing in for it exists, e.g. a map colored from it.
+#[derive(Component)]
+pub struct HiddenBy(pub Entity);
+
 pub fn setup_system(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -62,4 +72,65 @@ pub fn setup_system(
         &[child_1, child_2]
     );
 
-}
\ No newline at end of file
+}
+/// Scales the elevations of every top-level dataset and clip about the scene origin by the
+/// project vertical exaggeration. Only the transforms change, so the children follow and the
+/// meshes keep their true coordinates. Datasets moved under a parent get their transform back,
+/// their parent being exaggerated already.
+pub fn apply_vertical_exaggeration(
+    mut commands: Commands,
+    project: Res<Project>,
+    mut datasets: Query<
+        (Entity, &mut Transform, Option<&Exaggerated>, Option<&ClipPlane>),
+        (
+            With<Handle<Mesh>>,
+            Without<Parent>,
+            Without<Camera>,
+            Or<(Without<RenderLayers>, With<ClipBox>, With<ClipPlane>)>,
+        ),
+    >,
+    mut reparented: Query<(Entity, &mut Transform, &Exaggerated, Option<&ClipPlane>), With<Parent>>,
+) {
+    for (entity, mut transform, exaggerated, clip_plane) in reparented.iter_mut() {
+        exaggerate(&mut transform, 1.0 / exaggerated.0, clip_plane.is_some());
+        commands.entity(entity).remove::<Exaggerated>();
+    }
+
+    let factor = project.vertical_exaggeration;
+    for (entity, mut transform, exaggerated, clip_plane) in datasets.iter_mut() {
+        let applied = exaggerated.map_or(1.0, |exaggerated| exaggerated.0);
+        if applied == factor {
+            continue;
+        }
+        exaggerate(&mut transform, factor / applied, clip_plane.is_some());
+        commands.entity(entity).insert(Exaggerated(factor));
+    }
+}
+
+/// Multiplies the elevations of `transform` by `ratio`. A plane keeps its size and turns so it
+/// stays on the same points.
+fn exaggerate(transform: &mut Transform, ratio: f32, is_plane: bool) {
+    transform.translation.y *= ratio;
+    if is_plane {
+        // Normals scale by the inverse of the points
+        let normal = transform.rotation * Vec3::Y;
+        let scaled = (normal * Vec3::new(1.0, 1.0 / ratio, 1.0)).normalize();
+        transform.rotation = Quat::from_rotation_arc(normal, scaled) * transform.rotation;
+    } else {
+        transform.scale.y *= ratio;
+    }
+}
+
+/// Shows the datasets again once the entity that hid them is gone.
+pub fn show_hidden_datasets(
+    mut commands: Commands,
+    mut hidden: Query<(Entity, &HiddenBy, &mut Visibility)>,
+    existing: Query<()>,
+) {
+    for (entity, hidden_by, mut visibility) in hidden.iter_mut() {
+        if existing.get(hidden_by.0).is_err() {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<HiddenBy>();
+        }
+    }
+}
@@ -6,8 +6,13 @@ use bevy::prelude::*;
 
 
 
-use dxf::Drawing;
-use dxf::entities::EntityType;
+use dxf::{Drawing, Point, XData, XDataItem};
+use dxf::entities::{Entity as DxfEntity, EntityType, Face3D, Line, Polyline, Vertex};
+use dxf::tables::AppId;
+
+use crate::custom_meshes::drill_holes_mesh::DrillHoleIntervals;
+use crate::custom_meshes::mesh_handlers;
+use crate::utilities::math::{analytic_geometry, curves};
 
 use polars::prelude::*;
 
@@ -34,9 +39,38 @@ pub trait FileProperties{
 
 }
 
-#[derive(Component, Clone)]
+/// Geometry read from a dxf file, split by the role its layer plays in the surface.
+#[derive(Default)]
+pub struct DxfGeometry{
+    pub points: Vec<[f64;3]>,
+    pub breaklines: Vec<Vec<[f64;3]>>,
+    /// Triangles read from 3DFACE entities, already forming a TIN
+    pub faces: Vec<[[f64;3];3]>,
+    /// Entity types found in the selected layers that couldn't be used, with their count
+    pub skipped: Vec<(String, usize)>,
+}
+
+impl DxfGeometry {
+    /// Counts `count` more entities of `name` as skipped.
+    pub fn skip(&mut self, name: &str, count: usize) {
+        match self.skipped.iter_mut().find(|(skipped, _)| skipped == name) {
+            Some((_, skipped)) => *skipped += count,
+            None => self.skipped.push((name.to_string(), count)),
+        }
+    }
+}
+
+const ARC_SEGMENTS_PER_TURN: usize = 72;
+const XDATA_APPLICATION: &str = "DECOROUS";
+const SPLINE_SEGMENTS_PER_SPAN: usize = 8;
+
+#[derive(Component, Clone, Default)]
 pub struct DxfFile{
-    pub path: String
+    pub path: String,
+    /// Layers whose vertices are used as surface points. Every layer is used when empty.
+    pub point_layers: Vec<String>,
+    /// Layers whose polylines are used as breaklines.
+    pub breakline_layers: Vec<String>,
 }
 
 impl FileProperties for DxfFile{
@@ -46,40 +80,272 @@ impl FileProperties for DxfFile{
 }
 
 impl DxfFile {
-    pub fn get_points(&self) -> Vec<[f64;3]>{
-        let mut _points : Vec<[f64;3]> = Vec::new();
-        let path = self.path.clone();
-        let drawing = Drawing::load_file(&path).unwrap();
+
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+            ..Default::default()
+        }
+    }
+
+    /// Returns every layer of the drawing with the number of entities it holds.
+    pub fn layers(&self) -> Result<Vec<(String, usize)>, Box<dyn Error + Send + Sync>> {
+        let drawing = Drawing::load_file(&self.path)?;
+        let mut layers: Vec<(String, usize)> = drawing.layers()
+            .map(|layer| (layer.name.clone(), 0))
+            .collect();
+
         for e in drawing.entities() {
-            match e.specific {
-                EntityType::Line(ref _line) => {
-                    let p1 = _line.p1.clone();
-                    _points.push([p1.x, p1.y, p1.z]);
-
-                    let p2 = _line.p2.clone();
-                    _points.push([p2.x, p2.y, p2.z]);
-                },
-                EntityType::LwPolyline(ref _lw_polyline) => {
-                    let vertices = &_lw_polyline.vertices;
-                    let z = _lw_polyline.elevation;
-                    for point in vertices{
-                        _points.push([point.x, point.y, z]);
-                    }
-                },
-                EntityType::Polyline(ref p_line) => {
-                    let vertices = p_line.vertices();
-                    for ver in vertices{
-                        let p = ver.location.clone();
-                        _points.push([p.x, p.y, p.z]);
-                    }
-                },
-                _ => (),
+            match layers.iter_mut().find(|(name, _)| *name == e.common.layer) {
+                Some((_, count)) => *count += 1,
+                None => layers.push((e.common.layer.clone(), 1)),
             }
+        }
+        Ok(layers)
+    }
+
+    fn is_point_layer(&self, layer: &str) -> bool {
+        if self.point_layers.is_empty() && self.breakline_layers.is_empty() {
+            return true;
+        }
+        self.point_layers.iter().any(|name| name == layer)
+    }
+
+    fn is_breakline_layer(&self, layer: &str) -> bool {
+        self.breakline_layers.iter().any(|name| name == layer)
+    }
 
+    fn entity_vertices(specific: &EntityType) -> Vec<[f64;3]> {
+        let mut _points : Vec<[f64;3]> = Vec::new();
+        match specific {
+            EntityType::Line(ref _line) => {
+                let p1 = _line.p1.clone();
+                _points.push([p1.x, p1.y, p1.z]);
+
+                let p2 = _line.p2.clone();
+                _points.push([p2.x, p2.y, p2.z]);
+            },
+            EntityType::LwPolyline(ref _lw_polyline) => {
+                let vertices = &_lw_polyline.vertices;
+                let z = _lw_polyline.elevation;
+                for point in vertices{
+                    _points.push([point.x, point.y, z]);
+                }
+            },
+            EntityType::Polyline(ref p_line) => {
+                let vertices = p_line.vertices();
+                for ver in vertices{
+                    let p = ver.location.clone();
+                    _points.push([p.x, p.y, p.z]);
+                }
+            },
+            EntityType::ModelPoint(ref point) => {
+                let p = &point.location;
+                _points.push([p.x, p.y, p.z]);
+            },
+            EntityType::Insert(ref insert) => {
+                // Survey points are usually delivered as a block inserted at the shot location
+                let p = &insert.location;
+                _points.push([p.x, p.y, p.z]);
+            },
+            EntityType::Arc(ref arc) => {
+                let c = &arc.center;
+                _points.extend(curves::tessellate_arc(
+                    [c.x, c.y, c.z],
+                    arc.radius,
+                    arc.start_angle,
+                    arc.end_angle,
+                    ARC_SEGMENTS_PER_TURN,
+                ));
+            },
+            EntityType::Spline(ref spline) => {
+                let control_points = spline.control_points.iter()
+                    .map(|p| [p.x, p.y, p.z])
+                    .collect::<Vec<_>>();
+                if control_points.is_empty() {
+                    _points.extend(spline.fit_points.iter().map(|p| [p.x, p.y, p.z]));
+                } else {
+                    _points.extend(curves::tessellate_b_spline(
+                        spline.degree_of_curve.max(0) as usize,
+                        &spline.knot_values,
+                        &control_points,
+                        &spline.weight_values,
+                        control_points.len() * SPLINE_SEGMENTS_PER_SPAN,
+                    ));
+                }
+            },
+            _ => (),
         }
         _points
     }
 
+    fn entity_faces(specific: &EntityType) -> Vec<[[f64;3];3]> {
+        match specific {
+            EntityType::Face3D(ref face) => {
+                let corners = [
+                    &face.first_corner,
+                    &face.second_corner,
+                    &face.third_corner,
+                    &face.fourth_corner,
+                ].map(|p| [p.x, p.y, p.z]);
+
+                let mut faces = vec![[corners[0], corners[1], corners[2]]];
+                // A 3DFACE repeats its third corner when it is a triangle
+                if corners[3] != corners[2] {
+                    faces.push([corners[0], corners[2], corners[3]]);
+                }
+                faces
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// DXF name of the entity types that hold no surface vertices, e.g. `TEXT`.
+    fn entity_type_name(specific: &EntityType) -> &'static str {
+        match specific {
+            EntityType::Circle(_) => "CIRCLE",
+            EntityType::Ellipse(_) => "ELLIPSE",
+            EntityType::Text(_) => "TEXT",
+            EntityType::MText(_) => "MTEXT",
+            EntityType::Attribute(_) => "ATTRIB",
+            EntityType::AttributeDefinition(_) => "ATTDEF",
+            EntityType::Leader(_) => "LEADER",
+            EntityType::Solid(_) => "SOLID",
+            EntityType::Solid3D(_) => "3DSOLID",
+            EntityType::Body(_) => "BODY",
+            EntityType::Region(_) => "REGION",
+            EntityType::Image(_) => "IMAGE",
+            EntityType::RotatedDimension(_)
+            | EntityType::RadialDimension(_)
+            | EntityType::DiameterDimension(_)
+            | EntityType::AngularThreePointDimension(_)
+            | EntityType::OrdinateDimension(_) => "DIMENSION",
+            _ => "Other",
+        }
+    }
+
+    pub fn get_geometry(&self) -> Result<DxfGeometry, Box<dyn Error + Send + Sync>> {
+        let mut geometry = DxfGeometry::default();
+        let drawing = Drawing::load_file(&self.path)?;
+        for e in drawing.entities() {
+            let layer = e.common.layer.as_str();
+            let is_breakline = self.is_breakline_layer(layer);
+            if !is_breakline && !self.is_point_layer(layer) {
+                continue;
+            }
+
+            let faces = Self::entity_faces(&e.specific);
+            if !faces.is_empty() {
+                geometry.faces.extend(faces);
+                continue;
+            }
+
+            let vertices = Self::entity_vertices(&e.specific);
+            if vertices.is_empty() {
+                geometry.skip(Self::entity_type_name(&e.specific), 1);
+                continue;
+            }
+
+            if is_breakline {
+                geometry.breaklines.push(vertices);
+            } else {
+                geometry.points.extend(vertices);
+            }
+        }
+        Ok(geometry)
+    }
+
+
+    fn dxf_point(point: Vec3, offset: [f64;3]) -> Point {
+        let [x, y, z] = analytic_geometry::scene_to_world(point, offset);
+        Point::new(x, y, z)
+    }
+
+    /// Writes the triangles of `mesh` as 3DFACE entities, moved back to world coordinates.
+    pub fn write_mesh(&self, mesh: &Mesh, offset: [f64;3], layer: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (positions, triangles) = mesh_handlers::mesh_triangles(mesh)
+            .ok_or("Only triangle meshes can be exported")?;
+
+        let mut drawing = Drawing::new();
+        for [a, b, c] in triangles {
+            let a = Self::dxf_point(positions[a as usize], offset);
+            let b = Self::dxf_point(positions[b as usize], offset);
+            let c = Self::dxf_point(positions[c as usize], offset);
+
+            let mut entity = DxfEntity::new(EntityType::Face3D(Face3D::new(a, b, c.clone(), c)));
+            entity.common.layer = layer.to_string();
+            drawing.add_entity(entity);
+        }
+
+        drawing.save_file(&self.path)?;
+        Ok(())
+    }
+
+    /// Writes a 3D polyline per hole trace and a line per interval carrying the hole id, depths
+    /// and assays as XDATA.
+    pub fn write_drill_holes(&self, drill_holes: &DrillHoleIntervals) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut drawing = Drawing::new();
+        drawing.add_app_id(AppId{
+            name: XDATA_APPLICATION.to_string(),
+            ..Default::default()
+        });
+
+        let mut hole_ids: Vec<&str> = Vec::new();
+        for interval in drill_holes.intervals.iter() {
+            if !hole_ids.contains(&interval.hole_id.as_str()) {
+                hole_ids.push(&interval.hole_id);
+            }
+        }
+
+        for hole_id in hole_ids {
+            let mut intervals = drill_holes.intervals.iter()
+                .filter(|i| i.hole_id == hole_id)
+                .collect::<Vec<_>>();
+            intervals.sort_by(|a, b| a.from.partial_cmp(&b.from).unwrap());
+
+            let mut trace = Polyline::default();
+            trace.set_is_3d_polyline(true);
+            trace.add_vertex(&mut drawing, Vertex::new(Self::dxf_point(intervals[0].start, drill_holes.offset)));
+            for interval in intervals.iter() {
+                trace.add_vertex(&mut drawing, Vertex::new(Self::dxf_point(interval.end, drill_holes.offset)));
+            }
+            let mut entity = DxfEntity::new(EntityType::Polyline(trace));
+            entity.common.layer = "DH_TRACES".to_string();
+            entity.common.x_data.push(XData{
+                application_name: XDATA_APPLICATION.to_string(),
+                items: vec![XDataItem::Str(hole_id.to_string())],
+            });
+            drawing.add_entity(entity);
+
+            for interval in intervals {
+                let line = Line::new(
+                    Self::dxf_point(interval.start, drill_holes.offset),
+                    Self::dxf_point(interval.end, drill_holes.offset),
+                );
+                let mut items = vec![
+                    XDataItem::Str(interval.hole_id.clone()),
+                    XDataItem::Real(interval.from as f64),
+                    XDataItem::Real(interval.to as f64),
+                ];
+                for (variable, value) in drill_holes.variables.iter().zip(interval.values.iter()) {
+                    items.push(XDataItem::Str(variable.clone()));
+                    items.push(XDataItem::Real(*value));
+                }
+
+                let mut entity = DxfEntity::new(EntityType::Line(line));
+                entity.common.layer = "DH_INTERVALS".to_string();
+                entity.common.x_data.push(XData{
+                    application_name: XDATA_APPLICATION.to_string(),
+                    items,
+                });
+                drawing.add_entity(entity);
+            }
+        }
+
+        drawing.save_file(&self.path)?;
+        Ok(())
+    }
+
 }
 
 #[derive(Component, Clone)]
@@ -131,7 +397,21 @@ impl CsvFile {
         Ok(df)
     }
 
+    /// Writes `rows` under `header`, overwriting the file.
+    pub fn write_table(&self, header: &[&str], rows: impl IntoIterator<Item = Vec<String>>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(self.sep)
+            .from_path(&self.path)?;
 
+        if self.header {
+            writer.write_record(header)?;
+        }
+        for row in rows {
+            writer.write_record(&row)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
 
 }
 
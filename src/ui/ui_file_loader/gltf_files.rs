@@ -0,0 +1,267 @@
+use std::error::Error;
+use std::fmt::Write as _;
+
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use bevy::render::view::RenderLayers;
+
+use crate::custom_meshes::mesh_handlers;
+use crate::project::Project;
+use crate::ui::ui_windows::export::entity_mesh;
+
+use super::files::FileProperties;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// glTF 2.0 binary file.
+#[derive(Component, Clone)]
+pub struct GltfFile{
+    pub path: String,
+}
+
+impl FileProperties for GltfFile {
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+}
+
+/// Accumulates the json objects and the binary buffer while walking the hierarchy.
+#[derive(Default)]
+struct GltfBuilder{
+    nodes: Vec<String>,
+    meshes: Vec<String>,
+    materials: Vec<String>,
+    accessors: Vec<String>,
+    buffer_views: Vec<String>,
+    buffer: Vec<u8>,
+}
+
+impl GltfBuilder {
+    fn add_view(&mut self, bytes: &[u8], target: u32) -> usize {
+        let offset = self.buffer.len();
+        self.buffer.extend_from_slice(bytes);
+        self.buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+            offset, bytes.len(), target
+        ));
+        self.buffer_views.len() - 1
+    }
+
+    fn add_floats<const N: usize>(&mut self, values: &[[f32; N]], kind: &str, bounds: bool) -> usize {
+        let bytes: Vec<u8> = values.iter().flatten().flat_map(|v| v.to_le_bytes()).collect();
+        let view = self.add_view(&bytes, ARRAY_BUFFER);
+
+        let mut accessor = format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"{}""#,
+            view, FLOAT, values.len(), kind
+        );
+        if bounds {
+            let mut min = [f32::MAX; N];
+            let mut max = [f32::MIN; N];
+            // NaN and infinities are not valid json, they are left out of the bounds
+            for value in values {
+                for i in (0..N).filter(|i| value[*i].is_finite()) {
+                    min[i] = min[i].min(value[i]);
+                    max[i] = max[i].max(value[i]);
+                }
+            }
+            for i in (0..N).filter(|i| min[*i] > max[*i]) {
+                min[i] = 0.0;
+                max[i] = 0.0;
+            }
+            let _ = write!(accessor, r#","min":{:?},"max":{:?}"#, min, max);
+        }
+        accessor.push('}');
+
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn add_indices(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let view = self.add_view(&bytes, ELEMENT_ARRAY_BUFFER);
+        self.accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"SCALAR"}}"#,
+            view, UNSIGNED_INT, indices.len()
+        ));
+        self.accessors.len() - 1
+    }
+
+    fn add_material(&mut self, material: &StandardMaterial) -> usize {
+        let [r, g, b, a] = material.base_color.as_linear_rgba_f32();
+        let alpha_mode = match material.alpha_mode {
+            AlphaMode::Opaque => "OPAQUE",
+            AlphaMode::Mask(_) => "MASK",
+            _ => "BLEND",
+        };
+        self.materials.push(format!(
+            r#"{{"pbrMetallicRoughness":{{"baseColorFactor":[{},{},{},{}],"metallicFactor":{},"roughnessFactor":{}}},"alphaMode":"{}","doubleSided":{}}}"#,
+            r, g, b, a,
+            material.metallic,
+            material.perceptual_roughness,
+            alpha_mode,
+            material.cull_mode.is_none(),
+        ));
+        self.materials.len() - 1
+    }
+
+    fn add_mesh(&mut self, mesh: &Mesh, material: Option<usize>) -> Option<usize> {
+        let (positions, triangles) = mesh_handlers::mesh_triangles(mesh)?;
+        if triangles.is_empty() {
+            return None;
+        }
+
+        let positions: Vec<[f32; 3]> = positions.iter().map(|p| p.to_array()).collect();
+        let mut attributes = format!(r#""POSITION":{}"#, self.add_floats(&positions, "VEC3", true));
+
+        if let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            if normals.len() == positions.len() {
+                let _ = write!(attributes, r#","NORMAL":{}"#, self.add_floats(normals, "VEC3", false));
+            }
+        }
+        if let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+            if colors.len() == positions.len() {
+                let _ = write!(attributes, r#","COLOR_0":{}"#, self.add_floats(colors, "VEC4", false));
+            }
+        }
+
+        let indices: Vec<u32> = triangles.iter().flatten().copied().collect();
+        let mut primitive = format!(
+            r#"{{"attributes":{{{}}},"indices":{},"mode":4"#,
+            attributes,
+            self.add_indices(&indices)
+        );
+        if let Some(material) = material {
+            let _ = write!(primitive, r#","material":{}"#, material);
+        }
+        primitive.push('}');
+
+        self.meshes.push(format!(r#"{{"primitives":[{}]}}"#, primitive));
+        Some(self.meshes.len() - 1)
+    }
+
+    /// Adds `entity` and its exportable children, returning the node index.
+    fn add_node(&mut self, world: &World, entity: Entity) -> Option<usize> {
+        if !is_exported(world, entity) {
+            return None;
+        }
+
+        // The whole mesh, not what is left of it inside the clips
+        let mesh = entity_mesh(world, entity);
+        let material = world.get::<Handle<StandardMaterial>>(entity)
+            .and_then(|handle| world.resource::<Assets<StandardMaterial>>().get(handle))
+            .map(|material| self.add_material(material));
+        let mesh = mesh.and_then(|mesh| self.add_mesh(mesh, material));
+
+        let children: Vec<usize> = world.get::<Children>(entity)
+            .map(|children| children.iter().filter_map(|child| self.add_node(world, *child)).collect())
+            .unwrap_or_default();
+
+        let transform = world.get::<Transform>(entity).copied().unwrap_or_default();
+        let name = world.get::<Name>(entity).map_or(format!("{:?}", entity), |name| name.to_string());
+
+        let mut node = format!(
+            r#"{{"name":{},"translation":{:?},"rotation":{:?},"scale":{:?}"#,
+            json_string(&name),
+            transform.translation.to_array(),
+            transform.rotation.to_array(),
+            transform.scale.to_array(),
+        );
+        if let Some(mesh) = mesh {
+            let _ = write!(node, r#","mesh":{}"#, mesh);
+        }
+        if !children.is_empty() {
+            let _ = write!(node, r#","children":{:?}"#, children);
+        }
+        node.push('}');
+
+        self.nodes.push(node);
+        Some(self.nodes.len() - 1)
+    }
+}
+
+/// Skips hidden entities and the editor-only ones such as gizmo markers.
+fn is_exported(world: &World, entity: Entity) -> bool {
+    if matches!(world.get::<Visibility>(entity), Some(Visibility::Hidden)) {
+        return false;
+    }
+    if let Some(layers) = world.get::<RenderLayers>(entity) {
+        if !layers.intersects(&RenderLayers::default()) {
+            return false;
+        }
+    }
+    world.get::<Camera>(entity).is_none()
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn glb_chunk(bytes: &mut Vec<u8>, kind: u32, mut data: Vec<u8>, padding: u8) {
+    while data.len() % 4 != 0 {
+        data.push(padding);
+    }
+    bytes.extend((data.len() as u32).to_le_bytes());
+    bytes.extend(kind.to_le_bytes());
+    bytes.extend(data);
+}
+
+impl GltfFile {
+    /// Writes `roots` and their children as a GLB. Vertices stay in scene coordinates, the
+    /// project offset is stored in the scene extras so the world coordinates can be restored.
+    pub fn write_scene(&self, world: &World, roots: &[Entity]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut builder = GltfBuilder::default();
+        let root_nodes: Vec<usize> = roots.iter().filter_map(|root| builder.add_node(world, *root)).collect();
+
+        if root_nodes.is_empty() {
+            return Err("Nothing to export".into());
+        }
+
+        let offset = world.resource::<Project>().offset.unwrap_or_default();
+        let json = format!(
+            r#"{{"asset":{{"version":"2.0","generator":"Decorous"}},"scene":0,"scenes":[{{"nodes":{:?},"extras":{{"offset":{:?}}}}}],"nodes":[{}],"meshes":[{}],"materials":[{}],"accessors":[{}],"bufferViews":[{}],"buffers":[{{"byteLength":{}}}]}}"#,
+            root_nodes,
+            offset,
+            builder.nodes.join(","),
+            builder.meshes.join(","),
+            builder.materials.join(","),
+            builder.accessors.join(","),
+            builder.buffer_views.join(","),
+            builder.buffer.len(),
+        );
+
+        let mut chunks = Vec::new();
+        glb_chunk(&mut chunks, CHUNK_JSON, json.into_bytes(), b' ');
+        glb_chunk(&mut chunks, CHUNK_BIN, builder.buffer, 0);
+
+        let mut bytes = Vec::with_capacity(12 + chunks.len());
+        bytes.extend(GLB_MAGIC.to_le_bytes());
+        bytes.extend(GLB_VERSION.to_le_bytes());
+        bytes.extend((12 + chunks.len() as u32).to_le_bytes());
+        bytes.extend(chunks);
+
+        std::fs::write(&self.path, bytes)?;
+        Ok(())
+    }
+}
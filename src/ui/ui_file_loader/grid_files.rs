@@ -0,0 +1,44 @@
+use std::error::Error;
+use std::fmt::Write as _;
+
+use crate::utilities::math::grid::Grid;
+
+use super::files::FileProperties;
+
+/// Written in place of the undefined cells
+const NO_DATA: f64 = -9999.0;
+
+/// Grid in the ESRI ASCII raster format, read by most GIS and mine planning packages.
+pub struct GridFile{
+    pub path: String,
+}
+
+impl FileProperties for GridFile {
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+}
+
+impl GridFile {
+    pub fn write(&self, grid: &Grid) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let geometry = grid.geometry;
+        let mut text = String::new();
+        let _ = writeln!(text, "ncols {}", geometry.columns);
+        let _ = writeln!(text, "nrows {}", geometry.rows);
+        let _ = writeln!(text, "xllcorner {}", geometry.origin[0]);
+        let _ = writeln!(text, "yllcorner {}", geometry.origin[1]);
+        let _ = writeln!(text, "cellsize {}", geometry.cell);
+        let _ = writeln!(text, "NODATA_value {}", NO_DATA);
+
+        // The rows are written from the north
+        for row in (0..geometry.rows).rev() {
+            let values: Vec<String> = (0..geometry.columns)
+                .map(|column| grid.value(column, row).unwrap_or(NO_DATA).to_string())
+                .collect();
+            let _ = writeln!(text, "{}", values.join(" "));
+        }
+
+        std::fs::write(&self.path, text)?;
+        Ok(())
+    }
+}
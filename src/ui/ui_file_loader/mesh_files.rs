@@ -0,0 +1,429 @@
+use std::error::Error;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use super::files::FileProperties;
+
+/// Triangles in world coordinates (x, y, elevation), the way they are stored in the mesh files.
+#[derive(Default)]
+pub struct MeshData{
+    pub positions: Vec<[f64;3]>,
+    pub triangles: Vec<[u32;3]>,
+    /// Per vertex colors, only kept by PLY and OBJ
+    pub colors: Option<Vec<[f32;4]>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MeshFormat{
+    Obj,
+    Stl,
+    Ply,
+}
+
+impl MeshFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            MeshFormat::Obj => "obj",
+            MeshFormat::Stl => "stl",
+            MeshFormat::Ply => "ply",
+        }
+    }
+
+    pub fn all() -> [MeshFormat; 3] {
+        [MeshFormat::Obj, MeshFormat::Stl, MeshFormat::Ply]
+    }
+}
+
+#[derive(Component, Clone)]
+pub struct MeshFile{
+    pub path: String,
+}
+
+impl FileProperties for MeshFile {
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+}
+
+type MeshResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+impl MeshFile {
+
+    pub fn format(&self) -> MeshResult<MeshFormat> {
+        let extension = Path::new(&self.path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+
+        MeshFormat::all()
+            .into_iter()
+            .find(|format| format.extension() == extension)
+            .ok_or_else(|| format!("Unsupported mesh file: {}", self.path).into())
+    }
+
+    pub fn read(&self) -> MeshResult<MeshData> {
+        let bytes = std::fs::read(&self.path)?;
+        let data = match self.format()? {
+            MeshFormat::Obj => read_obj(std::str::from_utf8(&bytes)?)?,
+            MeshFormat::Stl => read_stl(&bytes)?,
+            MeshFormat::Ply => read_ply(&bytes)?,
+        };
+
+        if data.triangles.is_empty() {
+            return Err("The file has no triangles".into());
+        }
+        let count = data.positions.len();
+        if data.triangles.iter().flatten().any(|index| *index as usize >= count) {
+            return Err(format!("A face refers to a missing vertex, the file has {} vertices", count).into());
+        }
+        Ok(data)
+    }
+
+    pub fn write(&self, data: &MeshData) -> MeshResult<()> {
+        let bytes = match self.format()? {
+            MeshFormat::Obj => write_obj(data).into_bytes(),
+            MeshFormat::Stl => write_stl(data),
+            MeshFormat::Ply => write_ply(data),
+        };
+        std::fs::write(&self.path, bytes)?;
+        Ok(())
+    }
+}
+
+fn read_obj(text: &str) -> MeshResult<MeshData> {
+    let mut data = MeshData::default();
+    let mut colors: Vec<[f32;4]> = Vec::new();
+
+    for line in text.lines() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let values = tokens.map(|t| t.parse::<f64>()).collect::<Result<Vec<_>, _>>()?;
+                if values.len() < 3 {
+                    return Err(format!("Invalid vertex: {}", line).into());
+                }
+                data.positions.push([values[0], values[1], values[2]]);
+                // Vertex colors are a common extension: `v x y z r g b`
+                if values.len() >= 6 {
+                    colors.push([values[3] as f32, values[4] as f32, values[5] as f32, 1.0]);
+                }
+            }
+            Some("f") => {
+                let count = data.positions.len() as i64;
+                let face = tokens
+                    .map(|t| -> MeshResult<u32> {
+                        let index = t.split('/').next().unwrap_or_default().parse::<i64>()?;
+                        // Negative indices are relative to the last vertex read, 0 is not an index
+                        let index = match index {
+                            0 => return Err(format!("Invalid face: {}", line).into()),
+                            index if index < 0 => count + index,
+                            index => index - 1,
+                        };
+                        u32::try_from(index).map_err(|_| format!("Invalid face: {}", line).into())
+                    })
+                    .collect::<MeshResult<Vec<u32>>>()?;
+
+                for i in 1..face.len().saturating_sub(1) {
+                    data.triangles.push([face[0], face[i], face[i + 1]]);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if !colors.is_empty() && colors.len() == data.positions.len() {
+        data.colors = Some(colors);
+    }
+    Ok(data)
+}
+
+fn write_obj(data: &MeshData) -> String {
+    let mut text = String::from("# Exported by Decorous\n");
+    for (i, p) in data.positions.iter().enumerate() {
+        match &data.colors {
+            Some(colors) => {
+                let c = colors[i];
+                let _ = writeln!(text, "v {} {} {} {} {} {}", p[0], p[1], p[2], c[0], c[1], c[2]);
+            }
+            None => {
+                let _ = writeln!(text, "v {} {} {}", p[0], p[1], p[2]);
+            }
+        }
+    }
+    for t in data.triangles.iter() {
+        let _ = writeln!(text, "f {} {} {}", t[0] + 1, t[1] + 1, t[2] + 1);
+    }
+    text
+}
+
+/// Merges the repeated corners of STL triangles.
+fn add_corner(data: &mut MeshData, indices: &mut HashMap<[u64;3], u32>, corner: [f64;3]) -> u32 {
+    *indices.entry(corner.map(|c| c.to_bits())).or_insert_with(|| {
+        data.positions.push(corner);
+        data.positions.len() as u32 - 1
+    })
+}
+
+fn read_stl(bytes: &[u8]) -> MeshResult<MeshData> {
+    let mut data = MeshData::default();
+    let mut indices: HashMap<[u64;3], u32> = HashMap::new();
+
+    let is_binary = bytes.len() >= 84 && {
+        let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+        bytes.len() == 84 + count * 50
+    };
+
+    if is_binary {
+        for facet in bytes[84..].chunks_exact(50) {
+            let mut triangle = [0; 3];
+            for (corner, index) in triangle.iter_mut().enumerate() {
+                let offset = 12 + corner * 12;
+                let p = [0, 1, 2].map(|axis| {
+                    let start = offset + axis * 4;
+                    f32::from_le_bytes([facet[start], facet[start + 1], facet[start + 2], facet[start + 3]]) as f64
+                });
+                *index = add_corner(&mut data, &mut indices, p);
+            }
+            data.triangles.push(triangle);
+        }
+    } else {
+        let text = std::str::from_utf8(bytes)?;
+        let mut corners: Vec<u32> = Vec::new();
+        for line in text.lines() {
+            let mut tokens = line.split_whitespace();
+            if tokens.next() == Some("vertex") {
+                let p = tokens.map(|t| t.parse::<f64>()).collect::<Result<Vec<_>, _>>()?;
+                if p.len() < 3 {
+                    return Err(format!("Invalid vertex: {}", line).into());
+                }
+                corners.push(add_corner(&mut data, &mut indices, [p[0], p[1], p[2]]));
+                if corners.len() == 3 {
+                    data.triangles.push([corners[0], corners[1], corners[2]]);
+                    corners.clear();
+                }
+            }
+        }
+    }
+    Ok(data)
+}
+
+/// Binary STL. The format only stores single precision floats.
+fn write_stl(data: &MeshData) -> Vec<u8> {
+    let mut bytes = vec![0u8; 80];
+    bytes.extend((data.triangles.len() as u32).to_le_bytes());
+
+    for t in data.triangles.iter() {
+        let [a, b, c] = t.map(|i| {
+            let p = data.positions[i as usize];
+            Vec3::new(p[0] as f32, p[1] as f32, p[2] as f32)
+        });
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        for v in [normal, a, b, c] {
+            for value in v.to_array() {
+                bytes.extend(value.to_le_bytes());
+            }
+        }
+        bytes.extend(0u16.to_le_bytes());
+    }
+    bytes
+}
+
+struct PlyProperty{
+    name: String,
+    kind: String,
+    /// Type of the length prefix for list properties
+    list_count_kind: Option<String>,
+}
+
+struct PlyElement{
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+enum PlySource<'a>{
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary{
+        bytes: &'a [u8],
+        position: usize,
+        big_endian: bool,
+    },
+}
+
+impl PlySource<'_> {
+    fn read(&mut self, kind: &str) -> MeshResult<f64> {
+        match self {
+            PlySource::Ascii(tokens) => {
+                let token = tokens.next().ok_or("Unexpected end of ply file")?;
+                Ok(token.parse::<f64>()?)
+            }
+            PlySource::Binary { bytes, position, big_endian } => {
+                let size = match kind {
+                    "char" | "int8" | "uchar" | "uint8" => 1,
+                    "short" | "int16" | "ushort" | "uint16" => 2,
+                    "int" | "int32" | "uint" | "uint32" | "float" | "float32" => 4,
+                    "double" | "float64" => 8,
+                    _ => return Err(format!("Unknown ply type: {}", kind).into()),
+                };
+                let slice = bytes.get(*position..*position + size).ok_or("Unexpected end of ply file")?;
+                *position += size;
+
+                let mut buffer = [0u8; 8];
+                buffer[..size].copy_from_slice(slice);
+                if *big_endian {
+                    buffer[..size].reverse();
+                }
+
+                Ok(match kind {
+                    "char" | "int8" => buffer[0] as i8 as f64,
+                    "uchar" | "uint8" => buffer[0] as f64,
+                    "short" | "int16" => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    "ushort" | "uint16" => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    "int" | "int32" => i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+                    "uint" | "uint32" => u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+                    "float" | "float32" => f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+                    _ => f64::from_le_bytes(buffer),
+                })
+            }
+        }
+    }
+}
+
+fn read_ply(bytes: &[u8]) -> MeshResult<MeshData> {
+    const END_HEADER: &[u8] = b"end_header";
+    let end = bytes.windows(END_HEADER.len())
+        .position(|w| w == END_HEADER)
+        .ok_or("Missing ply header")?;
+    let body_start = bytes[end..].iter().position(|b| *b == b'\n').map_or(bytes.len(), |p| end + p + 1);
+    let header = std::str::from_utf8(&bytes[..end])?;
+
+    let mut format = "";
+    let mut elements: Vec<PlyElement> = Vec::new();
+    for line in header.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["format", name, ..] => format = *name,
+            ["element", name, count] => elements.push(PlyElement{
+                name: name.to_string(),
+                count: count.parse()?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_kind, kind, name] => {
+                if let Some(element) = elements.last_mut() {
+                    element.properties.push(PlyProperty{
+                        name: name.to_string(),
+                        kind: kind.to_string(),
+                        list_count_kind: Some(count_kind.to_string()),
+                    });
+                }
+            }
+            ["property", kind, name] => {
+                if let Some(element) = elements.last_mut() {
+                    element.properties.push(PlyProperty{
+                        name: name.to_string(),
+                        kind: kind.to_string(),
+                        list_count_kind: None,
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let mut source = match format {
+        "ascii" => PlySource::Ascii(std::str::from_utf8(&bytes[body_start..])?.split_whitespace()),
+        "binary_little_endian" => PlySource::Binary{ bytes, position: body_start, big_endian: false },
+        "binary_big_endian" => PlySource::Binary{ bytes, position: body_start, big_endian: true },
+        _ => return Err(format!("Unknown ply format: {}", format).into()),
+    };
+
+    let mut data = MeshData::default();
+    let mut colors: Vec<[f32;4]> = Vec::new();
+
+    for element in elements.iter() {
+        for _ in 0..element.count {
+            let mut position = [0.0; 3];
+            let mut color = [1.0; 4];
+            let mut has_color = false;
+
+            for property in element.properties.iter() {
+                if let Some(count_kind) = &property.list_count_kind {
+                    let count = source.read(count_kind)? as usize;
+                    let is_face = element.name == "face" && (property.name == "vertex_indices" || property.name == "vertex_index");
+                    let mut values = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        let value = source.read(&property.kind)?;
+                        if is_face && value < 0.0 {
+                            return Err(format!("Invalid face index: {}", value).into());
+                        }
+                        values.push(value as u32);
+                    }
+                    if is_face {
+                        for i in 1..count.saturating_sub(1) {
+                            data.triangles.push([values[0], values[i], values[i + 1]]);
+                        }
+                    }
+                    continue;
+                }
+
+                let value = source.read(&property.kind)?;
+                let channel_scale = if property.kind.contains("char") || property.kind.contains("int8") { 255.0 } else { 1.0 };
+                match property.name.as_str() {
+                    "x" => position[0] = value,
+                    "y" => position[1] = value,
+                    "z" => position[2] = value,
+                    "red" | "r" => { color[0] = (value / channel_scale) as f32; has_color = true; }
+                    "green" | "g" => { color[1] = (value / channel_scale) as f32; has_color = true; }
+                    "blue" | "b" => { color[2] = (value / channel_scale) as f32; has_color = true; }
+                    "alpha" | "a" => color[3] = (value / channel_scale) as f32,
+                    _ => (),
+                }
+            }
+
+            if element.name == "vertex" {
+                data.positions.push(position);
+                if has_color {
+                    colors.push(color);
+                }
+            }
+        }
+    }
+
+    if !colors.is_empty() && colors.len() == data.positions.len() {
+        data.colors = Some(colors);
+    }
+    Ok(data)
+}
+
+/// Binary little endian PLY with double precision positions, so the world coordinates survive.
+fn write_ply(data: &MeshData) -> Vec<u8> {
+    let mut header = String::from("ply\nformat binary_little_endian 1.0\ncomment Exported by Decorous\n");
+    let _ = writeln!(header, "element vertex {}", data.positions.len());
+    header.push_str("property double x\nproperty double y\nproperty double z\n");
+    if data.colors.is_some() {
+        header.push_str("property uchar red\nproperty uchar green\nproperty uchar blue\nproperty uchar alpha\n");
+    }
+    let _ = writeln!(header, "element face {}", data.triangles.len());
+    header.push_str("property list uchar uint vertex_indices\nend_header\n");
+
+    let mut bytes = header.into_bytes();
+    for (i, p) in data.positions.iter().enumerate() {
+        for value in p {
+            bytes.extend(value.to_le_bytes());
+        }
+        if let Some(colors) = &data.colors {
+            bytes.extend(colors[i].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+        }
+    }
+    for t in data.triangles.iter() {
+        bytes.push(3);
+        for index in t {
+            bytes.extend(index.to_le_bytes());
+        }
+    }
+    bytes
+}
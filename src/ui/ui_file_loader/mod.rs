@@ -1 +1,4 @@
-pub mod files;
\ No newline at end of file
+pub mod files;
+pub mod gltf_files;
+pub mod grid_files;
+pub mod mesh_files;
\ No newline at end of file
@@ -15,7 +15,9 @@ pub use crate::ui::ui_core::{editor, editor_window, AddEditorWindow};
 
 pub use crate::ui::ui_windows as default_windows;
 
+use crate::ui::ui_windows::load_block_model::LoadBlockModel;
 use crate::ui::ui_windows::load_drills::LoadDrills;
+use crate::ui::ui_windows::load_dxf::LoadDxf;
 use crate::ui::ui_windows::nodes_creator::NodesCreator;
 
 /// Commonly used types and extension traits
@@ -97,15 +99,31 @@ impl Plugin for EditorPlugin {
         {
             use crate::ui::ui_windows::add::AddWindow;
             use crate::ui::ui_windows::assets::AssetsWindow;
+            use crate::ui::ui_windows::block_model::BlockModelWindow;
             use crate::ui::ui_windows::cameras::CameraWindow;
+            use crate::ui::ui_windows::capping::CappingWindow;
+            use crate::ui::ui_windows::clipping::ClippingWindow;
+            use crate::ui::ui_windows::cut_fill::CutFillWindow;
+            use crate::ui::ui_windows::isopach::IsopachWindow;
+            use crate::ui::ui_windows::intersections::IntersectionsWindow;
+            use crate::ui::ui_windows::contact_surface::ContactSurfaceWindow;
+            use crate::ui::ui_windows::implicit_model::ImplicitModelWindow;
             use crate::ui::ui_windows::debug_settings::DebugSettingsWindow;
             use crate::ui::ui_windows::diagnostics::DiagnosticsWindow;
+            use crate::ui::ui_windows::estimation::EstimationWindow;
+            use crate::ui::ui_windows::export::ExportWindow;
             use crate::ui::ui_windows::gizmos::GizmoWindow;
+            use crate::ui::ui_windows::grade_tonnage::GradeTonnageWindow;
             use crate::ui::ui_windows::hierarchy::HierarchyWindow;
             use crate::ui::ui_windows::inspector::InspectorWindow;
+            use crate::ui::ui_windows::measure::MeasureWindow;
             use crate::ui::ui_windows::renderer::RendererWindow;
             use crate::ui::ui_windows::resources::ResourcesWindow;
+            use crate::ui::ui_windows::scatter::ScatterWindow;
             use crate::ui::ui_windows::scenes::SceneWindow;
+            use crate::ui::ui_windows::section::SectionWindow;
+            use crate::ui::ui_windows::statistics::StatisticsWindow;
+            use crate::ui::ui_windows::variography::VariographyWindow;
             use crate::ui::ui_windows::new_project::NewProject;
             use crate::ui::ui_windows::hierarchy::picking::PickingWindow;
 
@@ -119,10 +137,28 @@ impl Plugin for EditorPlugin {
             app.add_editor_window::<CameraWindow>();
             app.add_editor_window::<ResourcesWindow>();
             app.add_editor_window::<SceneWindow>();
+            app.add_editor_window::<ExportWindow>();
             app.add_editor_window::<GizmoWindow>();
+            app.add_editor_window::<BlockModelWindow>();
+            app.add_editor_window::<EstimationWindow>();
+            app.add_editor_window::<VariographyWindow>();
+            app.add_editor_window::<GradeTonnageWindow>();
+            app.add_editor_window::<StatisticsWindow>();
+            app.add_editor_window::<CappingWindow>();
+            app.add_editor_window::<ScatterWindow>();
+            app.add_editor_window::<SectionWindow>();
+            app.add_editor_window::<ClippingWindow>();
+            app.add_editor_window::<MeasureWindow>();
+            app.add_editor_window::<CutFillWindow>();
+            app.add_editor_window::<IsopachWindow>();
+            app.add_editor_window::<IntersectionsWindow>();
+            app.add_editor_window::<ContactSurfaceWindow>();
+            app.add_editor_window::<ImplicitModelWindow>();
             app.add_editor_window::<controls::ControlsWindow>();
             app.add_editor_window::<NewProject>();
             app.add_editor_window::<LoadDrills>();
+            app.add_editor_window::<LoadDxf>();
+            app.add_editor_window::<LoadBlockModel>();
             app.add_editor_window::<NodesCreator>();
             app.add_editor_window::<PickingWindow>();
 
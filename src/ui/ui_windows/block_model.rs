@@ -0,0 +1,110 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::block_model_mesh::{BlockModel, BlockModelDisplay};
+
+use super::clipping::ClipOriginal;
+use super::hierarchy::HierarchyWindow;
+
+#[derive(Default)]
+pub struct BlockModelWindowState{
+    entity: Option<Entity>,
+    attribute: Option<String>,
+    filter_enabled: bool,
+    filter: [f64;2],
+    apply_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+}
+
+pub struct BlockModelWindow;
+
+impl EditorWindow for BlockModelWindow {
+    type State = BlockModelWindowState;
+    const NAME: &'static str = "Block Model";
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let selected: Vec<Entity> = cx.state::<HierarchyWindow>().unwrap().selected.iter().collect();
+        let state = cx.state_mut::<BlockModelWindow>().unwrap();
+
+        let entity = match selected.as_slice() {
+            [entity] if world.get::<BlockModel>(*entity).is_some() => *entity,
+            _ => {
+                ui.label("Select a block model in the hierarchy");
+                return;
+            }
+        };
+
+        if state.entity != Some(entity) {
+            let display = world.get::<BlockModelDisplay>(entity).cloned().unwrap_or_default();
+            state.entity = Some(entity);
+            state.attribute = display.attribute;
+            state.filter_enabled = display.filter.is_some();
+            state.filter = display.filter.unwrap_or_default();
+            state.apply_result = None;
+        }
+
+        let block_model = world.get::<BlockModel>(entity).unwrap();
+        ui.label(format!("{} blocks", block_model.blocks.len()));
+        ui.separator();
+
+        egui::ComboBox::from_label("Color by")
+            .selected_text(state.attribute.clone().unwrap_or_else(|| "None".to_string()))
+            .show_ui(ui, |ui|{
+                ui.selectable_value(&mut state.attribute, None, "None");
+                for attribute in block_model.attributes.iter() {
+                    ui.selectable_value(&mut state.attribute, Some(attribute.clone()), attribute);
+                }
+            });
+
+        ui.add_enabled_ui(state.attribute.is_some(), |ui|{
+            ui.horizontal(|ui|{
+                if ui.checkbox(&mut state.filter_enabled, "Show only from").changed() && state.filter_enabled {
+                    if let Some(range) = state.attribute.as_ref().and_then(|a| block_model.attribute_range(a)) {
+                        state.filter = range;
+                    }
+                }
+                ui.add_enabled(state.filter_enabled, egui::DragValue::new(&mut state.filter[0]).speed(0.01));
+                ui.label("to");
+                ui.add_enabled(state.filter_enabled, egui::DragValue::new(&mut state.filter[1]).speed(0.01));
+            });
+        });
+
+        if ui.button("Apply").clicked() {
+            state.apply_result = Some(apply_display(world, entity, state));
+        }
+
+        if let Some(Err(error)) = &state.apply_result {
+            ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+        }
+    }
+}
+
+fn apply_display(
+    world: &mut World,
+    entity: Entity,
+    state: &BlockModelWindowState
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if state.filter_enabled && state.filter[0] > state.filter[1] {
+        return Err("The minimum of the range is greater than the maximum".into());
+    }
+
+    let display = BlockModelDisplay{
+        attribute: state.attribute.clone(),
+        filter: if state.filter_enabled && state.attribute.is_some() { Some(state.filter) } else { None },
+    };
+
+    let mesh = world.get::<BlockModel>(entity).ok_or("The entity is not a block model")?.mesh(&display);
+    // Put the unclipped mesh back so the new one is clipped again
+    if let Some(ClipOriginal(original)) = world.entity_mut(entity).take::<ClipOriginal>() {
+        world.entity_mut(entity).insert(original);
+    }
+    let handle = world.get::<Handle<Mesh>>(entity).ok_or("The entity has no mesh")?.clone();
+    world.resource_mut::<Assets<Mesh>>().set_untracked(handle, mesh);
+    world.entity_mut(entity).insert(display);
+
+    Ok(())
+}
@@ -3,7 +3,7 @@
 use bevy::{
     input::mouse::{MouseScrollUnit, MouseWheel},
     prelude::*,
-    render::camera::OrthographicProjection,
+    render::camera::Projection,
 };
 
 use crate::ui::ui_core::Editor;
@@ -25,7 +25,7 @@ impl Plugin for PanCamPlugin {
 
 // Zoom doesn't work on bevy 0.5 due to: https://github.com/bevyengine/bevy/pull/2015
 fn camera_zoom(
-    mut query: Query<(&PanCamControls, &mut OrthographicProjection)>,
+    mut query: Query<(&PanCamControls, &mut Projection)>,
     mut scroll_events: EventReader<MouseWheel>,
 ) {
     let pixels_per_line = 100.; // Maybe make configurable?
@@ -41,8 +41,13 @@ fn camera_zoom(
         return;
     }
 
-    for (_cam, mut projection) in query.iter_mut() {
-        projection.scale = (projection.scale * (1. + -scroll * 0.001)).max(0.00001);
+    for (cam, mut projection) in query.iter_mut() {
+        if !cam.enabled {
+            continue;
+        }
+        if let Projection::Orthographic(projection) = projection.as_mut() {
+            projection.scale = (projection.scale * (1. + -scroll * 0.001)).max(0.00001);
+        }
     }
 }
 
@@ -50,7 +55,7 @@ fn camera_movement(
     editor: Res<Editor>,
     window: Query<&Window>,
     mouse_buttons: Res<Input<MouseButton>>,
-    mut query: Query<(&PanCamControls, &mut Transform, &OrthographicProjection)>,
+    mut query: Query<(&PanCamControls, &Camera, &mut Transform, &Projection)>,
     mut last_pos: Local<Option<Vec2>>,
 ) {
     let Ok(window) = window.get(editor.window()) else { return };
@@ -62,22 +67,26 @@ fn camera_movement(
     };
     let delta = current_pos - last_pos.unwrap_or(current_pos);
 
-    for (cam, mut transform, projection) in query.iter_mut() {
+    for (cam, camera, mut transform, projection) in query.iter_mut() {
         if !cam.enabled {
             continue;
         }
+        let Projection::Orthographic(projection) = projection else { continue };
 
         if cam
             .grab_buttons
             .iter()
             .any(|btn| mouse_buttons.pressed(*btn))
         {
-            let scaling = Vec2::new(
-                window.width() / projection.area.width(),
-                window.height() / projection.area.height(),
-            ) * projection.scale;
+            // World units per logical pixel of the viewport
+            let viewport_size = camera
+                .logical_viewport_size()
+                .unwrap_or(Vec2::new(window.width(), window.height()));
+            let scaling = projection.area.size() / viewport_size.max(Vec2::ONE);
 
-            transform.translation -= (delta * scaling).extend(0.);
+            // Pan in the plane of the view, whatever the camera orientation
+            let delta = transform.rotation * (delta * scaling).extend(0.);
+            transform.translation -= delta;
         }
     }
     *last_pos = Some(current_pos);
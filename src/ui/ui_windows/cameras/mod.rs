@@ -1,8 +1,9 @@
+pub mod camera_2d_panzoom;
 pub mod camera_3d_free;
 pub mod camera_3d_panorbit;
 use super::scenes::NotInScene;
 
-use bevy::render::camera::RenderTarget;
+use bevy::render::camera::{Projection, RenderTarget};
 use bevy::render::view::RenderLayers;
 use bevy::utils::HashSet;
 use bevy::window::WindowRef;
@@ -13,11 +14,21 @@ use crate::ui::ui_core::{
     Editor, EditorEvent,
 };
 use bevy_egui::egui;
+use egui::{Align2, Color32, FontId, Stroke};
 use bevy_mod_picking::prelude::RaycastPickCamera;
 // use bevy_mod_picking::prelude::PickRaycastSource;
 
+use crate::custom_meshes::topography_mesh::TopographyMesh;
+use crate::project::Project;
+use crate::utilities::math::surface::SurfaceSampler;
+
+use super::clipping::ClipOriginal;
+use super::export::entity_surface;
+use super::hierarchy::picking::raycast_surfaces;
+
 use super::hierarchy::{HideInEditor, HierarchyWindow};
 
+use self::camera_2d_panzoom::PanCamControls;
 use self::camera_3d_panorbit::PanOrbitCamera;
 
 
@@ -39,6 +50,19 @@ struct EditorCamera3dFree;
 #[derive(Component)]
 struct EditorCamera3dPanOrbit;
 
+// Marker component for the orthographic camera of the plan and section views
+#[derive(Component)]
+struct EditorCameraOrthographic;
+
+/// Distance in front of and behind the orthographic camera within which the scene is drawn
+const ORTHOGRAPHIC_DEPTH: f32 = 100_000.0;
+
+/// Spacing in points the grid lines of the plan and section views are at least apart
+const GRID_SPACING: f32 = 100.0;
+
+/// Longest scale bar in points
+const SCALE_BAR_LENGTH: f32 = 150.0;
+
 pub struct CameraWindow;
 
 #[derive(Clone, Copy, PartialEq)]
@@ -47,6 +71,9 @@ pub enum EditorCamKind {
     D3Free,
     #[default]
     D3PanOrbit,
+    Plan,
+    SectionNS,
+    SectionEW,
 }
 
 impl EditorCamKind {
@@ -54,15 +81,48 @@ impl EditorCamKind {
         match self {
             EditorCamKind::D3Free => "3D (Free)",
             EditorCamKind::D3PanOrbit => "3D (Pan/Orbit)",
+            EditorCamKind::Plan => "Plan",
+            EditorCamKind::SectionNS => "Section (N-S)",
+            EditorCamKind::SectionEW => "Section (E-W)",
         }
     }
 
-    fn all() -> [EditorCamKind; 2] {
+    fn all() -> [EditorCamKind; 5] {
         [
             EditorCamKind::D3Free,
             EditorCamKind::D3PanOrbit,
+            EditorCamKind::Plan,
+            EditorCamKind::SectionNS,
+            EditorCamKind::SectionEW,
         ]
     }
+
+    pub fn is_orthographic(self) -> bool {
+        matches!(self, EditorCamKind::Plan | EditorCamKind::SectionNS | EditorCamKind::SectionEW)
+    }
+
+    /// Viewing direction and up vector of the orthographic views. The scene has north along -Z
+    /// and the elevation along +Y, so the plan is north up with the east on the right and the
+    /// sections look west and north to keep the coordinates increasing to the right.
+    fn orthographic_orientation(self) -> Option<(Vec3, Vec3)> {
+        match self {
+            EditorCamKind::Plan => Some((Vec3::NEG_Y, Vec3::NEG_Z)),
+            EditorCamKind::SectionNS => Some((Vec3::NEG_X, Vec3::Y)),
+            EditorCamKind::SectionEW => Some((Vec3::NEG_Z, Vec3::Y)),
+            EditorCamKind::D3Free | EditorCamKind::D3PanOrbit => None,
+        }
+    }
+
+    /// World coordinate indices and names along the horizontal and vertical screen axes of the
+    /// orthographic views.
+    fn screen_axes(self) -> Option<[(usize, &'static str); 2]> {
+        match self {
+            EditorCamKind::Plan => Some([(0, "E"), (1, "N")]),
+            EditorCamKind::SectionNS => Some([(1, "N"), (2, "Z")]),
+            EditorCamKind::SectionEW => Some([(0, "E"), (2, "Z")]),
+            EditorCamKind::D3Free | EditorCamKind::D3PanOrbit => None,
+        }
+    }
 }
 
 
@@ -72,6 +132,12 @@ pub struct CameraWindowState {
     // make sure to keep the `ActiveEditorCamera` marker component in sync with this field
     editor_cam: EditorCamKind,
     pub show_ui: bool,
+    /// Last ray cast under the pointer and the surface it hit, cast again when the ray changes
+    cursor_hit: Option<(Ray, Option<(Entity, Vec3)>)>,
+    /// Topography the cursor elevation is read from, picked in the toolbar
+    reference_topography: Option<Entity>,
+    /// Elevation sampler of the reference topography and the mesh it was built from
+    topography: Option<(Entity, Handle<Mesh>, Option<SurfaceSampler>)>,
 }
 
 impl CameraWindowState {
@@ -107,6 +173,47 @@ impl EditorWindow for CameraWindow {
             }
         });
         ui.checkbox(&mut state.show_ui, "UI");
+
+        let topographies: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), With<TopographyMesh>>()
+            .iter(world)
+            .map(|(topography, name)| (topography, name.to_string()))
+            .collect();
+        let reference = state.reference_topography
+            .and_then(|reference| topographies.iter().find(|(topography, _)| *topography == reference));
+        state.reference_topography = reference.map(|(topography, _)| *topography);
+        let text = reference.map_or("No topography", |(_, name)| name.as_str());
+        ui.menu_button(text, |ui| {
+            if ui.button("No topography").clicked() {
+                state.reference_topography = None;
+                ui.close_menu();
+            }
+            for (topography, name) in &topographies {
+                if ui.button(name).clicked() {
+                    state.reference_topography = Some(*topography);
+                    ui.close_menu();
+                }
+            }
+        }).response.on_hover_text("Topography the elevation under the cursor is read from");
+
+        let mut exaggeration = world.resource::<Project>().vertical_exaggeration;
+        let response = ui.add(
+            egui::DragValue::new(&mut exaggeration)
+                .clamp_range(0.1..=100.0)
+                .speed(0.05)
+                .prefix("VE ×"),
+        );
+        if response.on_hover_text("Vertical exaggeration").changed() {
+            world.resource_mut::<Project>().vertical_exaggeration = exaggeration;
+        }
+    }
+
+    fn viewport_ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let state = cx.state_mut::<CameraWindow>().unwrap();
+        if state.editor_cam.is_orthographic() {
+            orthographic_overlay(world, state.editor_cam, ui);
+        }
+        cursor_status_bar(world, state, ui);
     }
 
     fn app_setup(app: &mut App) {
@@ -114,10 +221,12 @@ impl EditorWindow for CameraWindow {
 
         app.add_plugin(camera_3d_free::FlycamPlugin)
             .add_plugin(camera_3d_panorbit::PanOrbitCameraPlugin)
+            .add_plugin(camera_2d_panzoom::PanCamPlugin)
             .add_system(
                 set_editor_cam_active
                     .before(camera_3d_panorbit::CameraSystem::Movement)
-                    .before(camera_3d_free::CameraSystem::Movement),
+                    .before(camera_3d_free::CameraSystem::Movement)
+                    .before(camera_2d_panzoom::CameraSystem::Movement),
             )
             .add_system(toggle_editor_cam.in_base_set(CoreSet::PreUpdate))
             .add_system(focus_selected.in_base_set(CoreSet::PreUpdate))
@@ -143,7 +252,10 @@ fn set_active_editor_camera_marker(world: &mut World, editor_cam: EditorCamKind)
         "there should be only one `ActiveEditorCamera`"
     );
 
+    // Center the orthographic views on what the pan/orbit camera was looking at
+    let mut focus = None;
     if let Some(previously_active) = previously_active {
+        focus = world.get::<PanOrbitCamera>(previously_active).map(|camera| camera.focus);
         world
             .entity_mut(previously_active)
             .remove::<ActiveEditorCamera>();
@@ -158,8 +270,20 @@ fn set_active_editor_camera_marker(world: &mut World, editor_cam: EditorCamKind)
             let mut state = world.query_filtered::<Entity, With<EditorCamera3dPanOrbit>>();
             state.iter(world).next().unwrap()
         }
+        EditorCamKind::Plan | EditorCamKind::SectionNS | EditorCamKind::SectionEW => {
+            let mut state = world.query_filtered::<Entity, With<EditorCameraOrthographic>>();
+            state.iter(world).next().unwrap()
+        }
     };
     world.entity_mut(entity).insert(ActiveEditorCamera);
+
+    if let Some((direction, up)) = editor_cam.orthographic_orientation() {
+        let mut transform = world.get_mut::<Transform>(entity).unwrap();
+        if let Some(focus) = focus {
+            transform.translation = focus;
+        }
+        transform.look_to(direction, up);
+    }
 }
 
 fn cameras_ui(ui: &mut egui::Ui, world: &mut World) {
@@ -191,6 +315,240 @@ fn cameras_ui(ui: &mut egui::Ui, world: &mut World) {
     }
 }
 
+/// Position in the egui `viewport` of a scene point seen by `camera`.
+pub fn scene_to_viewport(
+    camera: &Camera,
+    transform: &GlobalTransform,
+    viewport: egui::Rect,
+    point: Vec3,
+) -> Option<egui::Pos2> {
+    let size = camera.logical_viewport_size()?;
+    let position = camera.world_to_viewport(transform, point)?;
+    Some(egui::pos2(
+        viewport.left() + position.x / size.x * viewport.width(),
+        viewport.bottom() - position.y / size.y * viewport.height(),
+    ))
+}
+
+/// Ray of `camera` through a position of the egui `viewport`.
+pub fn viewport_ray(
+    camera: &Camera,
+    transform: &GlobalTransform,
+    viewport: egui::Rect,
+    position: egui::Pos2,
+) -> Option<Ray> {
+    let size = camera.logical_viewport_size()?;
+    let position = Vec2::new(
+        (position.x - viewport.left()) / viewport.width() * size.x,
+        (viewport.bottom() - position.y) / viewport.height() * size.y,
+    );
+    camera.viewport_to_world(transform, position)
+}
+
+/// Ray of the active editor camera through a position of the egui `viewport`.
+pub fn active_camera_ray(world: &mut World, viewport: egui::Rect, position: egui::Pos2) -> Option<Ray> {
+    let mut cameras = world.query_filtered::<(&Camera, &GlobalTransform), With<ActiveEditorCamera>>();
+    let (camera, transform) = cameras.iter(world).next()?;
+    viewport_ray(camera, transform, viewport, position)
+}
+
+/// Pointer position over the viewport of `ui` below its toolbar, `None` while the pointer is
+/// elsewhere or over a window on top of the viewport.
+pub fn viewport_pointer(ui: &egui::Ui) -> Option<egui::Pos2> {
+    let position = ui.input(|i| i.pointer.hover_pos())?;
+    let mut viewport = ui.clip_rect();
+    viewport.min.y += ui.spacing().interact_size.y;
+    (viewport.contains(position) && ui.ctx().layer_id_at(position) == Some(ui.layer_id())).then_some(position)
+}
+
+/// Grid labelled in real-world coordinates, north arrow and scale bar over the plan and section
+/// views.
+fn orthographic_overlay(world: &mut World, editor_cam: EditorCamKind, ui: &mut egui::Ui) {
+    let Some([(horizontal, horizontal_name), (vertical, vertical_name)]) = editor_cam.screen_axes() else { return };
+    let mut cameras = world.query_filtered::<(&Camera, &GlobalTransform), With<ActiveEditorCamera>>();
+    let Some((camera, transform)) = cameras.iter(world).next() else { return };
+    let project = world.resource::<Project>();
+
+    let viewport = ui.clip_rect();
+    let edge = |position: egui::Pos2| {
+        viewport_ray(camera, transform, viewport, position)
+            .map(|ray| project.displayed_to_world(ray.origin))
+    };
+    let (Some(left), Some(right), Some(top), Some(bottom)) = (
+        edge(viewport.left_center()),
+        edge(viewport.right_center()),
+        edge(viewport.center_top()),
+        edge(viewport.center_bottom()),
+    ) else {
+        return;
+    };
+
+    let width = right[horizontal] - left[horizontal];
+    let height = bottom[vertical] - top[vertical];
+    if width.abs() < f64::EPSILON || height.abs() < f64::EPSILON {
+        return;
+    }
+    // The orthographic views map the world coordinates linearly to the screen
+    let screen_x = |value: f64| viewport.left() + ((value - left[horizontal]) / width) as f32 * viewport.width();
+    let screen_y = |value: f64| viewport.top() + ((value - top[vertical]) / height) as f32 * viewport.height();
+    // The vertical exaggeration stretches the sections vertically
+    let units_per_point = width.abs() / viewport.width() as f64;
+    let vertical_units_per_point = height.abs() / viewport.height() as f64;
+
+    let painter = ui.painter_at(viewport);
+    let grid_stroke = Stroke::new(1.0, Color32::from_white_alpha(40));
+    let text_color = Color32::from_white_alpha(200);
+    let font = FontId::proportional(12.0);
+    // Below the viewport toolbar
+    let top_margin = viewport.top() + ui.spacing().interact_size.y + 4.0;
+
+    let step = nice_step_above(units_per_point * GRID_SPACING as f64);
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    for value in grid_values(left[horizontal], right[horizontal], step) {
+        let x = screen_x(value);
+        painter.line_segment([egui::pos2(x, viewport.top()), egui::pos2(x, viewport.bottom())], grid_stroke);
+        painter.text(
+            egui::pos2(x + 2.0, top_margin),
+            Align2::LEFT_TOP,
+            format!("{} {:.*}", horizontal_name, decimals, value),
+            font.clone(),
+            text_color,
+        );
+    }
+    let step = nice_step_above(vertical_units_per_point * GRID_SPACING as f64);
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    for value in grid_values(top[vertical], bottom[vertical], step) {
+        let y = screen_y(value);
+        painter.line_segment([egui::pos2(viewport.left(), y), egui::pos2(viewport.right(), y)], grid_stroke);
+        painter.text(
+            egui::pos2(viewport.left() + 4.0, y - 2.0),
+            Align2::LEFT_BOTTOM,
+            format!("{} {:.*}", vertical_name, decimals, value),
+            font.clone(),
+            text_color,
+        );
+    }
+
+    let arrow_stroke = Stroke::new(2.0, Color32::WHITE);
+    let corner = egui::pos2(viewport.right() - 40.0, top_margin + 50.0);
+    match editor_cam {
+        EditorCamKind::Plan => {
+            let north = if top[vertical] > bottom[vertical] { -1.0 } else { 1.0 };
+            let arrow = egui::vec2(0.0, north * 30.0);
+            painter.arrow(corner - arrow * 0.5, arrow, arrow_stroke);
+            painter.text(corner + arrow * 0.5, Align2::CENTER_BOTTOM, "N", FontId::proportional(16.0), Color32::WHITE);
+        }
+        EditorCamKind::SectionNS => {
+            painter.text(corner, Align2::RIGHT_CENTER, "Looking west", font.clone(), text_color);
+        }
+        EditorCamKind::SectionEW => {
+            painter.text(corner, Align2::RIGHT_CENTER, "Looking north", font.clone(), text_color);
+        }
+        EditorCamKind::D3Free | EditorCamKind::D3PanOrbit => (),
+    }
+
+    let length = nice_step_below(units_per_point * SCALE_BAR_LENGTH as f64);
+    // Above the cursor status bar
+    let start = egui::pos2(viewport.left() + 16.0, viewport.bottom() - ui.spacing().interact_size.y - 20.0);
+    let end = start + egui::vec2((length / units_per_point) as f32, 0.0);
+    let tick = egui::vec2(0.0, 5.0);
+    painter.line_segment([start, end], arrow_stroke);
+    painter.line_segment([start - tick, start + tick], arrow_stroke);
+    painter.line_segment([end - tick, end + tick], arrow_stroke);
+    painter.text(
+        egui::pos2((start.x + end.x) * 0.5, start.y - 6.0),
+        Align2::CENTER_BOTTOM,
+        format!("{} m", length),
+        font,
+        Color32::WHITE,
+    );
+}
+
+/// Real-world coordinates of the surface under the pointer and elevation of the reference
+/// topography at the same place, in a bar along the bottom of the viewport.
+fn cursor_status_bar(world: &mut World, state: &mut CameraWindowState, ui: &egui::Ui) {
+    let viewport = ui.clip_rect();
+    let ray = viewport_pointer(ui).and_then(|position| active_camera_ray(world, viewport, position));
+
+    let hit = match (ray, state.cursor_hit) {
+        (None, _) => None,
+        (Some(ray), Some((last_ray, hit))) if ray == last_ray => hit,
+        (Some(ray), _) => {
+            let hit = raycast_surfaces(world, ray);
+            state.cursor_hit = Some((ray, hit));
+            hit
+        }
+    };
+
+    let text = match hit {
+        Some((entity, point)) => {
+            let [x, y, z] = world.resource::<Project>().displayed_to_world(point);
+            let mut text = format!("X {:.2}    Y {:.2}    Z {:.2}", x, y, z);
+
+            update_topography_sampler(world, state);
+            if let Some(elevation) = state.topography.as_ref().and_then(|(_, _, surface)| surface.as_ref()?.elevation(x, y)) {
+                text += &format!("    Topography {:.2}", elevation);
+            }
+            if let Some(name) = world.get::<Name>(entity) {
+                text += &format!("    {}", name);
+            }
+            text
+        }
+        None => "No surface under the cursor".to_string(),
+    };
+
+    let height = ui.spacing().interact_size.y;
+    let bar = egui::Rect::from_min_max(egui::pos2(viewport.left(), viewport.bottom() - height), viewport.max);
+    let painter = ui.painter_at(viewport);
+    painter.rect_filled(bar, 0.0, Color32::from_black_alpha(160));
+    painter.text(
+        bar.left_center() + egui::vec2(6.0, 0.0),
+        Align2::LEFT_CENTER,
+        text,
+        FontId::proportional(13.0),
+        Color32::WHITE,
+    );
+}
+
+/// Builds the elevation sampler again when the reference topography is changed or replaced.
+fn update_topography_sampler(world: &mut World, state: &mut CameraWindowState) {
+    let surface = state.reference_topography.and_then(|entity| {
+        let handle = world.get::<ClipOriginal>(entity).map(|original| &original.0)
+            .or_else(|| world.get::<Handle<Mesh>>(entity))?;
+        Some((entity, handle.clone()))
+    });
+
+    let unchanged = match (&surface, &state.topography) {
+        (Some((entity, handle)), Some((cached, cached_handle, _))) => entity == cached && handle == cached_handle,
+        (None, None) => true,
+        _ => false,
+    };
+    if unchanged {
+        return;
+    }
+
+    state.topography = surface.map(|(entity, handle)| (entity, handle, entity_surface(world, entity).ok()));
+}
+
+/// Multiples of `step` between `a` and `b`.
+fn grid_values(a: f64, b: f64, step: f64) -> impl Iterator<Item = f64> {
+    let first = (a.min(b) / step).ceil() as i64;
+    let last = (a.max(b) / step).floor() as i64;
+    (first..=last).map(move |i| i as f64 * step)
+}
+
+/// Smallest of 1, 2 and 5 times a power of ten not below `value`.
+fn nice_step_above(value: f64) -> f64 {
+    let magnitude = 10f64.powf(value.log10().floor());
+    [1.0, 2.0, 5.0].iter().map(|m| m * magnitude).find(|step| *step >= value).unwrap_or(10.0 * magnitude)
+}
+
+/// Largest of 1, 2 and 5 times a power of ten not above `value`.
+fn nice_step_below(value: f64) -> f64 {
+    let magnitude = 10f64.powf(value.log10().floor());
+    [5.0, 2.0, 1.0].iter().map(|m| m * magnitude).find(|step| *step <= value).unwrap_or(magnitude)
+}
+
 fn spawn_editor_cameras(mut commands: Commands, editor: Res<Editor>) {
     #[derive(Component, Default)]
     struct Ec2d;
@@ -258,6 +616,39 @@ fn spawn_editor_cameras(mut commands: Commands, editor: Res<Editor>) {
         ActiveEditorCamera
     )).insert(GridShadowCamera);
 
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                order: editor_cam_priority + 2,
+                is_active: false,
+                target,
+                ..default()
+            },
+            projection: Projection::Orthographic(OrthographicProjection {
+                near: -ORTHOGRAPHIC_DEPTH,
+                far: ORTHOGRAPHIC_DEPTH,
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, 0.0, 0.0).looking_to(Vec3::NEG_Y, Vec3::NEG_Z),
+            ..Camera3dBundle::default()
+        },
+        UiCameraConfig {
+            show_ui: show_ui_by_default,
+        },
+        Ec3d,
+        PanCamControls {
+            grab_buttons: vec![MouseButton::Right, MouseButton::Middle],
+            ..default()
+        },
+        EditorCamera,
+        EditorCameraOrthographic,
+        HideInEditor,
+        Name::new("Editor Camera Orthographic"),
+        NotInScene,
+        render_layers,
+        RaycastPickCamera::default(),
+    ));
+
 }
 
 fn set_editor_cam_active(
@@ -265,7 +656,8 @@ fn set_editor_cam_active(
 
     mut editor_cameras: ParamSet<(
         Query<(&mut Camera, &mut camera_3d_free::FlycamControls)>,
-        Query<(&mut Camera, &mut camera_3d_panorbit::PanOrbitCamera)>
+        Query<(&mut Camera, &mut camera_3d_panorbit::PanOrbitCamera)>,
+        Query<(&mut Camera, &mut PanCamControls), With<EditorCameraOrthographic>>,
     )>,
 
     mut ui_camera_settings: Query<&mut UiCameraConfig, With<EditorCamera>>,
@@ -293,6 +685,13 @@ fn set_editor_cam_active(
         editor_cam_3d_panorbit.0.is_active = active;
         editor_cam_3d_panorbit.1.enabled = active && editor.viewport_interaction_active();
     }
+    {
+        let mut q = editor_cameras.p2();
+        let mut editor_cam_orthographic = q.single_mut();
+        let active = editor_cam.is_orthographic() && editor.active();
+        editor_cam_orthographic.0.is_active = active;
+        editor_cam_orthographic.1.enabled = active && editor.viewport_interaction_active();
+    }
 
 }
 
@@ -340,7 +739,7 @@ fn focus_selected(
         (
             &mut Transform,
             Option<&mut PanOrbitCamera>,
-            Option<&mut OrthographicProjection>,
+            Option<&mut Projection>,
         ),
         With<ActiveEditorCamera>,
     >,
@@ -403,11 +802,10 @@ fn focus_selected(
             RADIUS_MULTIPLIER
         };
 
-        let (mut camera_tf, pan_orbit_cam, ortho) = active_cam.single_mut();
+        let (mut camera_tf, pan_orbit_cam, mut projection) = active_cam.single_mut();
 
-        if let Some(mut ortho) = ortho {
-            camera_tf.translation.x = focus_loc.x;
-            camera_tf.translation.y = focus_loc.y;
+        if let Some(Projection::Orthographic(ortho)) = projection.as_deref_mut() {
+            camera_tf.translation = focus_loc;
 
             ortho.scale = radius / window.width().min(window.height()).max(1.0);
         } else {
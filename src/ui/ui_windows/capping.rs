@@ -0,0 +1,253 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::drill_holes_mesh::{DrillHoleIntervals, DrillHolesMesh};
+use crate::utilities::math::statistics;
+
+use super::hierarchy::HierarchyWindow;
+use super::load_drills::spawn_drill_hole_layer;
+use super::statistics::{log_probability_plot, weighted_values};
+
+/// Cap of one lithology, `rock` is `None` for the intervals of every other lithology.
+struct CapRow{
+    rock: Option<String>,
+    enabled: bool,
+    cap: f64,
+    /// 99th percentile of the domain, the cap follows it until it is edited
+    default_cap: f64,
+    /// Sorted length-weighted values of the intervals the cap applies to
+    domain: Vec<(f64, f64)>,
+}
+
+impl CapRow {
+    fn new(rock: Option<String>, domain: Vec<(f64, f64)>) -> Self {
+        let cap = statistics::weighted_quantile(&domain, 0.99).unwrap_or_default();
+        Self { enabled: rock.is_none(), rock, cap, default_cap: cap, domain }
+    }
+
+    fn set_domain(&mut self, domain: Vec<(f64, f64)>) {
+        if self.cap == self.default_cap {
+            self.default_cap = statistics::weighted_quantile(&domain, 0.99).unwrap_or_default();
+            self.cap = self.default_cap;
+        }
+        self.domain = domain;
+    }
+}
+
+pub struct CappingWindowState{
+    source: Option<(Entity, String)>,
+    variable: Option<String>,
+    /// Sorted length-weighted values of the variable over every interval
+    sorted: Vec<(f64, f64)>,
+    rows: Vec<CapRow>,
+    /// Rocks with an enabled row, left out of the "All others" domain
+    excluded: Vec<String>,
+    show_layer: bool,
+    apply_result: Option<Result<String, Box<dyn Error + Send + Sync>>>,
+}
+
+impl Default for CappingWindowState {
+    fn default() -> Self {
+        Self {
+            source: None,
+            variable: None,
+            sorted: Vec::new(),
+            rows: Vec::new(),
+            excluded: Vec::new(),
+            show_layer: true,
+            apply_result: None,
+        }
+    }
+}
+
+pub struct CappingWindow;
+
+impl EditorWindow for CappingWindow {
+    type State = CappingWindowState;
+    const NAME: &'static str = "Capping";
+    const DEFAULT_SIZE: (f32, f32) = (700.0, 700.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let selected: Vec<Entity> = cx.state::<HierarchyWindow>().unwrap().selected.iter().collect();
+        let state = cx.state_mut::<CappingWindow>().unwrap();
+
+        let entity = match selected.as_slice() {
+            [entity] if world.get::<DrillHoleIntervals>(*entity).is_some() => *entity,
+            _ => {
+                ui.label("Select a drill holes layer in the hierarchy");
+                return;
+            }
+        };
+        let intervals = world.get::<DrillHoleIntervals>(entity).unwrap();
+        let variables = intervals.variables.clone();
+        let rocks = intervals.rocks();
+
+        if !matches!(&state.variable, Some(variable) if variables.contains(variable)) {
+            state.variable = variables.first().cloned();
+        }
+
+        egui::ComboBox::from_label("Variable")
+            .selected_text(state.variable.clone().unwrap_or_default())
+            .show_ui(ui, |ui|{
+                for variable in variables.iter() {
+                    ui.selectable_value(&mut state.variable, Some(variable.clone()), variable);
+                }
+            });
+
+        let variable = state.variable.clone().unwrap_or_default();
+
+        if state.source.as_ref() != Some(&(entity, variable.clone())) {
+            state.source = Some((entity, variable.clone()));
+            state.apply_result = None;
+            state.sorted = weighted_values(world, entity, &variable, true, None);
+            // Only the "All others" row is enabled, so its domain is every interval
+            state.rows = std::iter::once(None).chain(rocks.iter().cloned().map(Some))
+                .map(|rock| {
+                    let domain = weighted_values(world, entity, &variable, true, rock.as_deref());
+                    CapRow::new(rock, domain)
+                })
+                .collect();
+            state.excluded.clear();
+        }
+
+        let excluded: Vec<String> = state.rows.iter().filter(|row| row.enabled).filter_map(|row| row.rock.clone()).collect();
+        if excluded != state.excluded {
+            let domain = other_rocks_values(world, entity, &variable, &excluded);
+            if let Some(others) = state.rows.iter_mut().find(|row| row.rock.is_none()) {
+                others.set_domain(domain);
+            }
+            state.excluded = excluded;
+        }
+
+        ui.columns(2, |columns|{
+            decile_table(&mut columns[0], &state.sorted);
+            let caps: Vec<f64> = state.rows.iter().filter(|row| row.enabled).map(|row| row.cap).collect();
+            log_probability_plot(&mut columns[1], "capping log probability", &state.sorted, &caps);
+        });
+
+        ui.separator();
+        egui::Grid::new("capping values").striped(true).show(ui, |ui|{
+            ui.label(RichText::new("Lithology").strong());
+            ui.label(RichText::new("Cap").strong());
+            ui.label(RichText::new("Capped").strong());
+            ui.end_row();
+
+            for row in state.rows.iter_mut() {
+                ui.checkbox(&mut row.enabled, row.rock.clone().unwrap_or_else(|| "All others".to_string()));
+                ui.add_enabled(row.enabled, egui::DragValue::new(&mut row.cap).clamp_range(0.0..=f64::MAX).speed(0.01));
+
+                let capped = row.domain.iter().filter(|(value, _)| *value > row.cap).count();
+                ui.label(if row.enabled { capped.to_string() } else { "-".to_string() });
+                ui.end_row();
+            }
+        });
+
+        ui.horizontal(|ui|{
+            ui.checkbox(&mut state.show_layer, "Show as a drill holes layer");
+            if ui.button("Apply").clicked() {
+                state.apply_result = Some(apply_caps(world, entity, state));
+            }
+        });
+
+        if let Some(status) = &state.apply_result {
+            match status {
+                Ok(capped_variable) => {
+                    ui.label(RichText::new(format!("Created {}", capped_variable)).color(egui::Color32::GREEN));
+                }
+                Err(error) => {
+                    ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+                }
+            }
+        }
+    }
+}
+
+/// Sorted length-weighted values of `variable` in the intervals whose rock is not `excluded`.
+fn other_rocks_values(world: &World, entity: Entity, variable: &str, excluded: &[String]) -> Vec<(f64, f64)> {
+    let Some(intervals) = world.get::<DrillHoleIntervals>(entity) else { return Vec::new() };
+    let Some(index) = intervals.variable_index(variable) else { return Vec::new() };
+    statistics::sorted_weighted(intervals.intervals.iter()
+        .filter(|interval| !matches!(&interval.rock, Some(rock) if excluded.contains(rock)))
+        .map(|interval| (interval.values[index], (interval.to - interval.from) as f64)))
+}
+
+fn decile_table(ui: &mut egui::Ui, sorted: &[(f64, f64)]) {
+    egui::ScrollArea::vertical()
+        .id_source("capping deciles")
+        .max_height(250.0)
+        .show(ui, |ui|{
+            egui::Grid::new("capping decile analysis").striped(true).show(ui, |ui|{
+                for header in ["Class", "Min", "Max", "Mean", "Metal %"] {
+                    ui.label(RichText::new(header).strong());
+                }
+                ui.end_row();
+
+                for row in statistics::decile_analysis(sorted) {
+                    ui.label(row.label);
+                    ui.label(format!("{:.3}", row.min));
+                    ui.label(format!("{:.3}", row.max));
+                    ui.label(format!("{:.3}", row.mean));
+                    ui.label(format!("{:.1}", row.metal_percent));
+                    ui.end_row();
+                }
+            });
+        });
+}
+
+/// Adds the capped variable to the selected layer and to the layers loaded with it, so every
+/// layer and the estimators can use it.
+fn apply_caps(
+    world: &mut World,
+    entity: Entity,
+    state: &CappingWindowState
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let variable = state.variable.clone().ok_or("Select the variable to cap")?;
+    let capped_variable = format!("{}_cap", variable);
+
+    let intervals = world.get::<DrillHoleIntervals>(entity).ok_or("The entity has no drill holes")?;
+    let index = intervals.variable_index(&variable).ok_or("The variable is not in the drill holes")?;
+    let default_cap = state.rows.iter().find(|row| row.rock.is_none() && row.enabled).map(|row| row.cap);
+
+    let values: Vec<f64> = intervals.intervals.iter()
+        .map(|interval| {
+            let cap = state.rows.iter()
+                .find(|row| row.enabled && row.rock.is_some() && row.rock == interval.rock)
+                .map(|row| row.cap)
+                .or(default_cap);
+            let value = interval.values[index];
+            // `f64::min` would turn the missing assays into the cap
+            match cap {
+                Some(cap) if !value.is_nan() => value.min(cap),
+                _ => value,
+            }
+        })
+        .collect();
+
+    let parent = world.get::<Parent>(entity).map(|parent| parent.get());
+    let count = intervals.intervals.len();
+    let layers: Vec<Entity> = world
+        .query::<(Entity, &DrillHoleIntervals, Option<&Parent>)>()
+        .iter(world)
+        .filter(|(layer, intervals, layer_parent)| {
+            *layer == entity || (parent.is_some() && layer_parent.map(|p| p.get()) == parent && intervals.intervals.len() == count)
+        })
+        .map(|(layer, _, _)| layer)
+        .collect();
+
+    for layer in layers {
+        world.get_mut::<DrillHoleIntervals>(layer).unwrap().set_variable(&capped_variable, values.iter().copied());
+    }
+
+    if state.show_layer {
+        let intervals = world.get::<DrillHoleIntervals>(entity).unwrap().clone();
+        let mesh = DrillHolesMesh::layer_mesh(&intervals, &capped_variable).ok_or("The capped variable has no values")?;
+        spawn_drill_hole_layer(world, parent, mesh, &intervals, capped_variable.clone());
+    }
+
+    Ok(capped_variable)
+}
@@ -0,0 +1,368 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::asset::HandleId;
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::transform::TransformSystem;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::mesh_handlers;
+use crate::project::Project;
+use crate::systems::Exaggerated;
+use crate::utilities::math::analytic_geometry;
+
+use super::cameras::EDITOR_RENDER_LAYER;
+use super::hierarchy::picking::NoEditorPicking;
+use super::hierarchy::HierarchyWindow;
+
+/// Hides the geometry outside of the unit cube moved, rotated and scaled by the entity transform.
+#[derive(Component)]
+pub struct ClipBox{
+    pub enabled: bool,
+}
+
+/// Hides the geometry on the side of the plane its local Y axis points to, the plane going
+/// through the entity translation.
+#[derive(Component)]
+pub struct ClipPlane{
+    pub enabled: bool,
+}
+
+/// Unclipped mesh of an entity showing a clipped copy, restored when no clip is enabled.
+#[derive(Component)]
+pub struct ClipOriginal(pub Handle<Mesh>);
+
+pub struct ClippingWindow;
+
+impl EditorWindow for ClippingWindow {
+    type State = ();
+    const NAME: &'static str = "Clipping";
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let clips: Vec<(Entity, String, bool)> = world
+            .query::<(Entity, &Name, Option<&ClipBox>, Option<&ClipPlane>)>()
+            .iter(world)
+            .filter_map(|(entity, name, clip_box, clip_plane)| {
+                let enabled = clip_box.map(|c| c.enabled).or(clip_plane.map(|c| c.enabled))?;
+                Some((entity, name.to_string(), enabled))
+            })
+            .collect();
+        let offset = world.resource::<Project>().offset.unwrap_or_default();
+        // Clips live in the viewport where the elevations are exaggerated, shown in true units
+        let exaggeration = Vec3::new(1.0, world.resource::<Project>().vertical_exaggeration, 1.0);
+
+        let mut spawned = None;
+        ui.horizontal(|ui|{
+            if ui.button("Add clip box").clicked() {
+                spawned = Some(spawn_clip_box(world));
+            }
+            if ui.button("Add clip plane").clicked() {
+                spawned = Some(spawn_clip_plane(world));
+            }
+        });
+        ui.label(RichText::new("Select a clip to move, rotate or scale it with the gizmo").weak());
+        ui.separator();
+
+        let mut selected = spawned;
+        let mut despawned = None;
+        for (entity, name, mut enabled) in clips {
+            ui.horizontal(|ui|{
+                if ui.checkbox(&mut enabled, name).changed() {
+                    if let Some(mut clip_box) = world.get_mut::<ClipBox>(entity) {
+                        clip_box.enabled = enabled;
+                    }
+                    if let Some(mut clip_plane) = world.get_mut::<ClipPlane>(entity) {
+                        clip_plane.enabled = enabled;
+                    }
+                }
+                if ui.button("Select").clicked() {
+                    selected = Some(entity);
+                }
+                if ui.small_button("\u{1F5D1}").clicked() {
+                    despawned = Some(entity);
+                }
+            });
+
+            let is_box = world.get::<ClipBox>(entity).is_some();
+            let Some(mut transform) = world.get_mut::<Transform>(entity) else { continue };
+            let mut center = analytic_geometry::scene_to_world(transform.translation / exaggeration, offset);
+
+            egui::Grid::new(("clip", entity)).show(ui, |ui|{
+                ui.label(if is_box { "Center" } else { "Point" });
+                let mut moved = false;
+                for (value, axis) in center.iter_mut().zip(["X: ", "Y: ", "Z: "]) {
+                    moved |= ui.add(egui::DragValue::new(value).prefix(axis)).changed();
+                }
+                if moved {
+                    transform.translation = analytic_geometry::world_to_scene(center, offset) * exaggeration;
+                }
+                ui.end_row();
+
+                if is_box {
+                    ui.label("Size");
+                    // The scene Y axis is the elevation
+                    for (index, axis) in [(0, "X: "), (2, "Y: "), (1, "Z: ")] {
+                        let mut size = transform.scale[index] / exaggeration[index];
+                        if ui.add(egui::DragValue::new(&mut size).clamp_range(0.01..=f32::MAX).prefix(axis)).changed() {
+                            transform.scale[index] = size * exaggeration[index];
+                        }
+                    }
+                } else {
+                    ui.label("Orientation");
+                    ui.horizontal(|ui|{
+                        if ui.button("Horizontal").clicked() {
+                            transform.rotation = Quat::IDENTITY;
+                        }
+                        if ui.button("North–south").clicked() {
+                            transform.rotation = Quat::from_rotation_z(-std::f32::consts::FRAC_PI_2);
+                        }
+                        if ui.button("East–west").clicked() {
+                            transform.rotation = Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
+                        }
+                        if ui.button("Flip").clicked() {
+                            transform.rotation *= Quat::from_rotation_x(std::f32::consts::PI);
+                        }
+                    });
+                }
+                ui.end_row();
+            });
+            ui.separator();
+        }
+
+        if let Some(entity) = despawned {
+            bevy::hierarchy::despawn_with_children_recursive(world, entity);
+        }
+        if let Some(entity) = selected {
+            cx.state_mut::<HierarchyWindow>().unwrap().selected.select_replace(entity);
+        }
+    }
+
+    fn app_setup(app: &mut App) {
+        app.add_system(
+            clip_meshes
+                .in_base_set(CoreSet::PostUpdate)
+                .after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Scene bounds of the meshes that can be clipped, `None` when there are none.
+fn clippable_bounds(world: &mut World) -> Option<(Vec3, Vec3)> {
+    let bounds: Vec<(GlobalTransform, Handle<Mesh>)> = world
+        .query_filtered::<(&GlobalTransform, &Handle<Mesh>), (Without<ClipBox>, Without<ClipPlane>, Without<RenderLayers>)>()
+        .iter(world)
+        .map(|(transform, handle)| (*transform, handle.clone()))
+        .collect();
+    let meshes = world.resource::<Assets<Mesh>>();
+
+    bounds.iter()
+        .filter_map(|(transform, handle)| {
+            let aabb = meshes.get(handle)?.compute_aabb()?;
+            let (min, max) = (Vec3::from(aabb.min()), Vec3::from(aabb.max()));
+            (0..8)
+                .map(|corner| transform.transform_point(Vec3::select(
+                    BVec3::new(corner & 1 == 0, corner & 2 == 0, corner & 4 == 0),
+                    min,
+                    max,
+                )))
+                .map(|corner| (corner, corner))
+                .reduce(|(min, max), (corner, _)| (min.min(corner), max.max(corner)))
+        })
+        .reduce(|(min, max), (other_min, other_max)| (min.min(other_min), max.max(other_max)))
+}
+
+fn clip_material(world: &mut World) -> Handle<StandardMaterial> {
+    world.resource_mut::<Assets<StandardMaterial>>().add(StandardMaterial {
+        base_color: Color::rgba(1.0, 0.6, 0.2, 0.15),
+        alpha_mode: AlphaMode::Blend,
+        double_sided: true,
+        cull_mode: None,
+        unlit: true,
+        ..Default::default()
+    })
+}
+
+/// Box over the middle half of the data in plan and its whole height.
+fn spawn_clip_box(world: &mut World) -> Entity {
+    let (min, max) = clippable_bounds(world).unwrap_or((Vec3::splat(-50.0), Vec3::splat(50.0)));
+    let size = (max - min) * Vec3::new(0.5, 1.0, 0.5);
+
+    let mesh = world.resource_mut::<Assets<Mesh>>().add(Mesh::from(shape::Cube { size: 1.0 }));
+    let material = clip_material(world);
+    let exaggeration = world.resource::<Project>().vertical_exaggeration;
+    world.spawn((PbrBundle {
+        mesh,
+        material,
+        transform: Transform::from_translation((min + max) * 0.5).with_scale(size.max(Vec3::splat(0.01))),
+        ..Default::default()
+    },
+    Name::new("Clip box"),
+    ClipBox{ enabled: true },
+    // Spawned over the data as displayed
+    Exaggerated(exaggeration),
+    RenderLayers::layer(EDITOR_RENDER_LAYER),
+    NoEditorPicking,
+    )).id()
+}
+
+/// Horizontal plane through the middle of the data, hiding its upper half.
+fn spawn_clip_plane(world: &mut World) -> Entity {
+    let (min, max) = clippable_bounds(world).unwrap_or((Vec3::splat(-50.0), Vec3::splat(50.0)));
+    let size = (max - min).max_element().max(1.0);
+
+    let mesh = world.resource_mut::<Assets<Mesh>>().add(Mesh::from(shape::Plane { size: 1.0, subdivisions: 0 }));
+    let material = clip_material(world);
+    let exaggeration = world.resource::<Project>().vertical_exaggeration;
+    world.spawn((PbrBundle {
+        mesh,
+        material,
+        transform: Transform::from_translation((min + max) * 0.5).with_scale(Vec3::splat(size)),
+        ..Default::default()
+    },
+    Name::new("Clip plane"),
+    ClipPlane{ enabled: true },
+    // Spawned over the data as displayed
+    Exaggerated(exaggeration),
+    RenderLayers::layer(EDITOR_RENDER_LAYER),
+    NoEditorPicking,
+    )).id()
+}
+
+/// Planes of the enabled clip boxes and planes in scene coordinates, by clip entity. The
+/// geometry is kept where a·x + b·y + c·z + d <= 0 for every plane of a clip.
+fn clip_regions(
+    clip_boxes: &Query<(Entity, &GlobalTransform, &ClipBox)>,
+    clip_planes: &Query<(Entity, &GlobalTransform, &ClipPlane)>,
+) -> Vec<(Entity, Vec<Vec4>)> {
+    clip_boxes.iter()
+        .filter(|(_, _, clip_box)| clip_box.enabled)
+        .map(|(entity, transform, _)| {
+            let inverse = transform.compute_matrix().inverse();
+            let planes = (0..3)
+                .flat_map(|axis| {
+                    let row = inverse.row(axis);
+                    [row - Vec4::W * 0.5, -row - Vec4::W * 0.5]
+                })
+                .collect();
+            (entity, planes)
+        })
+        .chain(clip_planes.iter()
+            .filter(|(_, _, clip_plane)| clip_plane.enabled)
+            .map(|(entity, transform, _)| {
+                let (_, rotation, translation) = transform.to_scale_rotation_translation();
+                let normal = rotation * Vec3::Y;
+                (entity, vec![normal.extend(-normal.dot(translation))])
+            }))
+        .collect()
+}
+
+/// Whether the box with scene `corners` is kept whole (`Some(true)`), removed whole
+/// (`Some(false)`) or cut (`None`) by the clip made of `planes`. Some removed boxes are reported
+/// as cut.
+fn clip_side(planes: &[Vec4], corners: &[Vec3; 8]) -> Option<bool> {
+    let outside = |plane: &Vec4, corner: &Vec3| plane.truncate().dot(*corner) + plane.w > 0.0;
+    if planes.iter().all(|plane| corners.iter().all(|corner| !outside(plane, corner))) {
+        Some(true)
+    } else if planes.iter().any(|plane| corners.iter().all(|corner| outside(plane, corner))) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Replaces the mesh of every entity by its part inside the enabled clips, keeping the original
+/// in [`ClipOriginal`]. Meshes are clipped again when a clip that cuts them, before or after,
+/// moves, and when they move or are added or replaced. They are restored when no clip is enabled.
+fn clip_meshes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    clip_boxes: Query<(Entity, &GlobalTransform, &ClipBox)>,
+    clip_planes: Query<(Entity, &GlobalTransform, &ClipPlane)>,
+    mut targets: ParamSet<(
+        Query<Entity, (Changed<Handle<Mesh>>, Without<ClipOriginal>, Without<ClipBox>, Without<ClipPlane>, Without<RenderLayers>)>,
+        Query<Entity, (Changed<GlobalTransform>, With<Handle<Mesh>>, Without<ClipBox>, Without<ClipPlane>, Without<RenderLayers>)>,
+        Query<
+            (Entity, &GlobalTransform, &mut Handle<Mesh>, Option<&ClipOriginal>),
+            (Without<ClipBox>, Without<ClipPlane>, Without<RenderLayers>),
+        >,
+    )>,
+    mut applied: Local<Vec<(Entity, Vec<Vec4>)>>,
+    mut local_bounds: Local<HashMap<HandleId, Option<(Vec3, Vec3)>>>,
+) {
+    let regions = clip_regions(&clip_boxes, &clip_planes);
+
+    // Planes of every clip that moved, was added or was removed, before and after
+    let planes_of = |regions: &[(Entity, Vec<Vec4>)], entity: Entity| -> Vec<Vec4> {
+        regions.iter().find(|(clip, _)| *clip == entity).map(|(_, planes)| planes.clone()).unwrap_or_default()
+    };
+    let changed: Vec<(Vec<Vec4>, Vec<Vec4>)> = applied.iter()
+        .map(|(entity, _)| *entity)
+        .chain(regions.iter().map(|(entity, _)| *entity))
+        .collect::<HashSet<Entity>>()
+        .into_iter()
+        .map(|entity| (planes_of(applied.as_slice(), entity), planes_of(&regions, entity)))
+        .filter(|(before, after)| before != after)
+        .collect();
+
+    let mut dirty: HashSet<Entity> = targets.p0().iter().collect();
+    dirty.extend(targets.p1().iter());
+    if changed.is_empty() && dirty.is_empty() {
+        return;
+    }
+
+    let planes: Vec<Vec4> = regions.iter().flat_map(|(_, planes)| planes.iter().copied()).collect();
+    let mut used: HashSet<HandleId> = HashSet::new();
+    for (entity, transform, mut handle, original) in targets.p2().iter_mut() {
+        let original = original.map_or_else(|| handle.clone(), |original| original.0.clone());
+        used.insert(original.id());
+
+        if !dirty.contains(&entity) {
+            let bounds = *local_bounds.entry(original.id()).or_insert_with(|| {
+                let aabb = meshes.get(&original)?.compute_aabb()?;
+                Some((Vec3::from(aabb.min()), Vec3::from(aabb.max())))
+            });
+            let Some((min, max)) = bounds else { continue };
+            let corners: [Vec3; 8] = std::array::from_fn(|corner| transform.transform_point(Vec3::select(
+                BVec3::new(corner & 1 == 0, corner & 2 == 0, corner & 4 == 0),
+                min,
+                max,
+            )));
+            let affected = changed.iter().any(|(before, after)| {
+                let side = clip_side(before, &corners);
+                side.is_none() || side != clip_side(after, &corners)
+            });
+            if !affected {
+                continue;
+            }
+        }
+
+        // A plane applied to the mesh coordinates p is applied to the scene coordinates M·p
+        let matrix = transform.compute_matrix().transpose();
+        let local: Vec<Vec4> = planes.iter().map(|plane| matrix * *plane).collect();
+
+        let clipped = if planes.is_empty() {
+            None
+        } else {
+            meshes.get(&original).and_then(|mesh| mesh_handlers::clip_mesh(mesh, &local))
+        };
+
+        match clipped {
+            Some(clipped) => {
+                *handle = meshes.add(clipped);
+                commands.entity(entity).insert(ClipOriginal(original));
+            }
+            None => {
+                if *handle != original {
+                    *handle = original;
+                }
+                commands.entity(entity).remove::<ClipOriginal>();
+            }
+        }
+    }
+
+    local_bounds.retain(|id, _| used.contains(id));
+    *applied = regions;
+}
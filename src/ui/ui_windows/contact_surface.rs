@@ -0,0 +1,244 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::drill_holes_mesh::{DrillHoleContact, DrillHoleIntervals, DrillHoleLayer};
+use crate::custom_meshes::topography_mesh::TopographyMesh;
+use crate::utilities::math::estimation::Sample;
+use crate::utilities::math::grid::{Grid, GridGeometry};
+use crate::utilities::math::rbf::{Kernel, Rbf};
+
+use super::nodes_creator::{spawn_mesh_node, topography_material};
+use super::widgets::entity_combo;
+
+/// Largest number of contacts interpolated with the RBF, its system is solved densely
+const MAX_RBF_CONTACTS: usize = 1_000;
+/// Largest number of grid cells of an RBF surface times its contacts, every cell sums the kernel
+/// of every contact on the UI thread
+const MAX_EVALUATIONS: f64 = 100_000_000.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ContactInterpolation{
+    /// Triangulates the contacts
+    Delaunay,
+    /// Thin plate spline sampled on a grid over the contacts
+    Rbf,
+}
+
+pub struct ContactSurfaceWindowState{
+    drill_holes: Option<Entity>,
+    upper: Option<String>,
+    lower: Option<String>,
+    interpolation: ContactInterpolation,
+    cell: f64,
+    smoothing: f64,
+    contacts: Vec<DrillHoleContact>,
+    /// Holes with lithology that could not be placed without a collar or survey
+    unsurveyed: Vec<String>,
+    extract_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+    surface_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+}
+
+impl Default for ContactSurfaceWindowState {
+    fn default() -> Self {
+        Self {
+            drill_holes: None,
+            upper: None,
+            lower: None,
+            interpolation: ContactInterpolation::Delaunay,
+            cell: 10.0,
+            smoothing: 0.0,
+            contacts: Vec::new(),
+            unsurveyed: Vec::new(),
+            extract_result: None,
+            surface_result: None,
+        }
+    }
+}
+
+pub struct ContactSurfaceWindow;
+
+impl EditorWindow for ContactSurfaceWindow {
+    type State = ContactSurfaceWindowState;
+    const NAME: &'static str = "Contact surface";
+    const DEFAULT_SIZE: (f32, f32) = (450.0, 450.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let state = cx.state_mut::<ContactSurfaceWindow>().unwrap();
+
+        let drill_holes: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), (With<DrillHoleIntervals>, With<DrillHoleLayer>)>()
+            .iter(world)
+            .map(|(entity, name)| (entity, name.to_string()))
+            .collect();
+        if drill_holes.is_empty() {
+            ui.label("Load drill holes");
+            return;
+        }
+
+        let rocks = state.drill_holes
+            .and_then(|entity| world.get::<DrillHoleIntervals>(entity))
+            .map(|intervals| intervals.lithology_rocks())
+            .unwrap_or_default();
+
+        egui::Grid::new("contact parameters").show(ui, |ui|{
+            ui.label("Drill holes");
+            entity_combo(ui, "contact drill holes", &mut state.drill_holes, &drill_holes);
+            ui.end_row();
+
+            ui.label("Upper rock");
+            rock_combo(ui, "contact upper rock", &mut state.upper, &rocks);
+            ui.end_row();
+
+            ui.label("Lower rock");
+            rock_combo(ui, "contact lower rock", &mut state.lower, &rocks);
+            ui.end_row();
+
+            ui.label("Interpolation");
+            ui.horizontal(|ui|{
+                ui.selectable_value(&mut state.interpolation, ContactInterpolation::Delaunay, "Delaunay TIN");
+                ui.selectable_value(&mut state.interpolation, ContactInterpolation::Rbf, "Smooth RBF");
+            });
+            ui.end_row();
+
+            if state.interpolation == ContactInterpolation::Rbf {
+                ui.label("Cell size");
+                ui.add(egui::DragValue::new(&mut state.cell).clamp_range(0.1..=f64::MAX).speed(0.1).suffix(" m"));
+                ui.end_row();
+
+                ui.label("Smoothing");
+                ui.add(egui::DragValue::new(&mut state.smoothing).clamp_range(0.0..=f64::MAX).speed(0.1))
+                    .on_hover_text("Lets the surface pass near the contacts instead of through them");
+                ui.end_row();
+            }
+        });
+        if rocks.is_empty() && state.drill_holes.is_some() {
+            ui.label(RichText::new("The drill holes have no lithology").weak());
+        }
+
+        ui.horizontal(|ui|{
+            if ui.button("Extract contacts").clicked() {
+                state.extract_result = Some(extract(world, state));
+                state.surface_result = None;
+            }
+            if ui.add_enabled(state.contacts.len() >= 3, egui::Button::new("Build surface")).clicked() {
+                state.surface_result = Some(build_surface(world, state));
+            }
+        });
+
+        for status in [&state.extract_result, &state.surface_result].into_iter().flatten() {
+            if let Err(error) = status {
+                ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+            }
+        }
+        if !state.unsurveyed.is_empty() {
+            ui.label(RichText::new(format!(
+                "{} holes have no collar or survey and were skipped: {}",
+                state.unsurveyed.len(),
+                state.unsurveyed.join(", "),
+            )).color(egui::Color32::YELLOW));
+        }
+
+        if state.contacts.is_empty() {
+            return;
+        }
+        ui.separator();
+        ui.label(format!("{} contacts", state.contacts.len()));
+        egui::ScrollArea::vertical().id_source("contacts").show(ui, |ui|{
+            egui::Grid::new("contacts").striped(true).show(ui, |ui|{
+                for header in ["Hole", "X", "Y", "Z", "Depth"] {
+                    ui.label(RichText::new(header).strong());
+                }
+                ui.end_row();
+                for contact in &state.contacts {
+                    let [x, y, z] = contact.position;
+                    ui.label(&contact.hole_id);
+                    ui.label(format!("{:.2}", x));
+                    ui.label(format!("{:.2}", y));
+                    ui.label(format!("{:.2}", z));
+                    ui.label(format!("{:.2}", contact.depth));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+}
+
+fn rock_combo(ui: &mut egui::Ui, id: &str, rock: &mut Option<String>, rocks: &[String]) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(rock.clone().unwrap_or_default())
+        .show_ui(ui, |ui|{
+            for option in rocks {
+                ui.selectable_value(rock, Some(option.clone()), option);
+            }
+        });
+}
+
+fn extract(world: &mut World, state: &mut ContactSurfaceWindowState) -> Result<(), Box<dyn Error + Send + Sync>> {
+    state.contacts.clear();
+    state.unsurveyed.clear();
+    let drill_holes = state.drill_holes.ok_or("Select the drill holes")?;
+    let upper = state.upper.as_deref().ok_or("Select the upper rock")?;
+    let lower = state.lower.as_deref().ok_or("Select the lower rock")?;
+    if upper == lower {
+        return Err("Select two different rocks".into());
+    }
+
+    let intervals = world.get::<DrillHoleIntervals>(drill_holes).ok_or("The entity has no drill holes")?;
+    state.contacts = intervals.contacts(upper, lower);
+    state.unsurveyed = intervals.unsurveyed_lithology();
+    if state.contacts.is_empty() {
+        return Err(format!("No hole goes from {} to {}", upper, lower).into());
+    }
+    Ok(())
+}
+
+/// Spawns a topography surface through the contacts, named after them.
+fn build_surface(world: &mut World, state: &ContactSurfaceWindowState) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let points: Vec<[f64;3]> = state.contacts.iter().map(|contact| contact.position).collect();
+    let (mesh, topography) = match state.interpolation {
+        ContactInterpolation::Delaunay => {
+            let (mesh, topography) = TopographyMesh::from_points(points);
+            if !matches!(mesh.indices(), Some(indices) if !indices.is_empty()) {
+                return Err("The contacts are aligned".into());
+            }
+            (mesh, topography)
+        }
+        ContactInterpolation::Rbf => rbf_surface(&points, state.cell, state.smoothing)?,
+    };
+
+    let name = format!(
+        "Contact {} - {}",
+        state.upper.clone().unwrap_or_default(),
+        state.lower.clone().unwrap_or_default(),
+    );
+    let offset = topography.offset();
+    spawn_mesh_node(world, mesh, offset, topography_material(), (topography, Name::new(name)));
+    Ok(())
+}
+
+/// Thin plate spline through the contacts, sampled on a grid over their extent.
+fn rbf_surface(points: &[[f64;3]], cell: f64, smoothing: f64) -> Result<(Mesh, TopographyMesh), Box<dyn Error + Send + Sync>> {
+    if points.len() > MAX_RBF_CONTACTS {
+        return Err(format!("The RBF interpolates up to {} contacts, use the Delaunay TIN", MAX_RBF_CONTACTS).into());
+    }
+    let min = points.iter().fold([f64::MAX; 2], |min, p| [min[0].min(p[0]), min[1].min(p[1])]);
+    let max = points.iter().fold([f64::MIN; 2], |max, p| [max[0].max(p[0]), max[1].max(p[1])]);
+    if (max[0] - min[0]) * (max[1] - min[1]) / (cell * cell) * points.len() as f64 > MAX_EVALUATIONS {
+        return Err("The cell size is too small for this many contacts".into());
+    }
+
+    let samples: Vec<Sample> = points.iter()
+        .map(|p| Sample { position: [p[0], p[1], 0.0], value: p[2] })
+        .collect();
+    let rbf = Rbf::fit(&samples, Kernel::ThinPlate, 2, smoothing)
+        .ok_or("The contacts repeat in plan, increase the smoothing")?;
+
+    let geometry = GridGeometry::covering(min, max, cell);
+    let grid = Grid::from_fn(geometry, |x, y| Some(rbf.value([x, y, 0.0])));
+    TopographyMesh::from_grid(&grid).ok_or_else(|| "The contacts span less than two cells, reduce the cell size".into())
+}
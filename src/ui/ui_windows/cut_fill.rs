@@ -0,0 +1,248 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::topography_mesh::TopographyMesh;
+use crate::systems::HiddenBy;
+use crate::ui::ui_file_loader::files::CsvFile;
+use crate::utilities::math::analytic_geometry;
+use crate::utilities::math::surface::SurfaceSampler;
+use crate::utilities::math::volume::{self, CutFill};
+
+use super::export::{entity_mesh, entity_offset, entity_surface};
+use super::measure::MeasureWindow;
+use super::nodes_creator::spawn_mesh_node;
+use super::widgets::entity_combo;
+
+/// Largest number of grid cells sampled, each kept for the export. The cell size has to grow
+/// for larger surfaces
+const MAX_CELLS: f64 = 2_000_000.0;
+
+/// Compared surface colored by its difference with the reference surface.
+#[derive(Component)]
+pub struct CutFillMap;
+
+pub struct CutFillWindowState{
+    reference: Option<Entity>,
+    compared: Option<Entity>,
+    cell: f64,
+    /// Only count the cells inside the closed polygon of the measure tool
+    within_polygon: bool,
+    show_map: bool,
+    result: Option<CutFill>,
+    compute_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+    export_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+}
+
+impl Default for CutFillWindowState {
+    fn default() -> Self {
+        Self {
+            reference: None,
+            compared: None,
+            cell: 1.0,
+            within_polygon: false,
+            show_map: true,
+            result: None,
+            compute_result: None,
+            export_result: None,
+        }
+    }
+}
+
+pub struct CutFillWindow;
+
+impl EditorWindow for CutFillWindow {
+    type State = CutFillWindowState;
+    const NAME: &'static str = "Cut / Fill";
+    const DEFAULT_SIZE: (f32, f32) = (450.0, 400.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let polygon = cx.state::<MeasureWindow>().unwrap().polygon();
+        let state = cx.state_mut::<CutFillWindow>().unwrap();
+
+        let topographies: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), With<TopographyMesh>>()
+            .iter(world)
+            .map(|(topography, name)| (topography, name.to_string()))
+            .collect();
+        if topographies.len() < 2 {
+            ui.label("Load two topography surfaces");
+            return;
+        }
+
+        egui::Grid::new("cut fill parameters").show(ui, |ui|{
+            ui.label("Reference (before)");
+            entity_combo(ui, "cut fill reference", &mut state.reference, &topographies);
+            ui.end_row();
+
+            ui.label("Compared (after)");
+            entity_combo(ui, "cut fill compared", &mut state.compared, &topographies);
+            ui.end_row();
+
+            ui.label("Cell size");
+            ui.add(egui::DragValue::new(&mut state.cell).clamp_range(0.01..=f64::MAX).speed(0.1).suffix(" m"));
+            ui.end_row();
+
+            ui.label("Boundary");
+            ui.add_enabled(polygon.is_some(), egui::Checkbox::new(&mut state.within_polygon, "Measured polygon"))
+                .on_disabled_hover_text("Close a polygon with the Measure tool");
+            ui.end_row();
+        });
+        ui.checkbox(&mut state.show_map, "Show the difference map on the compared surface");
+
+        ui.horizontal(|ui|{
+            if ui.button("Compute").clicked() {
+                state.compute_result = Some(compute(world, state, polygon));
+                state.export_result = None;
+            }
+            if ui.add_enabled(state.result.is_some(), egui::Button::new("Export CSV")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("Cut and fill (csv)", &["csv"]).save_file() {
+                    if let Some(result) = &state.result {
+                        state.export_result = Some(export_cells(result, path.with_extension("csv").display().to_string()));
+                    }
+                }
+            }
+        });
+
+        for status in [&state.compute_result, &state.export_result].into_iter().flatten() {
+            if let Err(error) = status {
+                ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+            }
+        }
+        if let Some(Ok(())) = &state.export_result {
+            ui.label(RichText::new("Export Success!").color(egui::Color32::GREEN));
+        }
+
+        if let Some(result) = &state.result {
+            ui.separator();
+            egui::Grid::new("cut fill volumes").striped(true).show(ui, |ui|{
+                ui.label("Cut");
+                ui.label(format!("{:.1} m³", result.cut));
+                ui.end_row();
+                ui.label("Fill");
+                ui.label(format!("{:.1} m³", result.fill));
+                ui.end_row();
+                ui.label("Net (fill - cut)");
+                ui.label(format!("{:.1} m³", result.net()));
+                ui.end_row();
+                ui.label("Area");
+                ui.label(format!("{:.1} m²", result.area));
+                ui.end_row();
+            });
+        }
+    }
+}
+
+fn compute(
+    world: &mut World,
+    state: &mut CutFillWindowState,
+    polygon: Option<Vec<[f64;2]>>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    state.result = None;
+    let reference = state.reference.ok_or("Select the reference surface")?;
+    let compared = state.compared.ok_or("Select the compared surface")?;
+    if reference == compared {
+        return Err("Select two different surfaces".into());
+    }
+    let polygon = if state.within_polygon {
+        Some(polygon.ok_or("Close a polygon with the Measure tool")?)
+    } else {
+        None
+    };
+
+    let reference_surface = entity_surface(world, reference)?;
+    let compared_surface = entity_surface(world, compared)?;
+
+    let (min, max) = volume::overlap(&reference_surface, &compared_surface, polygon.as_deref())
+        .ok_or("The surfaces do not overlap")?;
+    if (max[0] - min[0]) * (max[1] - min[1]) / (state.cell * state.cell) > MAX_CELLS {
+        return Err("The cell size is too small for the surfaces".into());
+    }
+
+    let result = volume::cut_fill(&reference_surface, &compared_surface, state.cell, polygon.as_deref())
+        .ok_or("The surfaces do not overlap")?;
+
+    if state.show_map {
+        show_difference_map(world, compared, &reference_surface, polygon.as_deref())?;
+    }
+    state.result = Some(result);
+    Ok(())
+}
+
+/// Replaces the previous map by a copy of the compared surface colored red where it is below
+/// the reference and blue where it is above, hiding the compared surface while the map exists.
+fn show_difference_map(
+    world: &mut World,
+    compared: Entity,
+    reference: &SurfaceSampler,
+    polygon: Option<&[[f64;2]]>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let offset = entity_offset(world, compared);
+    let mut mesh = entity_mesh(world, compared).ok_or("The compared surface has no mesh")?.clone();
+    let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
+        return Err("The compared surface is not a triangle mesh".into());
+    };
+
+    let differences: Vec<Option<f64>> = positions.iter()
+        .map(|position| {
+            let [x, y, z] = analytic_geometry::scene_to_world(Vec3::from(*position), offset);
+            if matches!(polygon, Some(polygon) if !analytic_geometry::point_in_polygon([x, y], polygon)) {
+                return None;
+            }
+            reference.elevation(x, y).map(|elevation| z - elevation)
+        })
+        .collect();
+    let largest = differences.iter().flatten().fold(f64::EPSILON, |largest, d| largest.max(d.abs()));
+    let colors: Vec<[f32;4]> = differences.iter()
+        .map(|difference| match difference {
+            None => [0.5, 0.5, 0.5, 1.0],
+            Some(d) => {
+                let t = (d.abs() / largest) as f32;
+                if *d > 0.0 { [1.0 - t, 1.0 - t, 1.0, 1.0] } else { [1.0, 1.0 - t, 1.0 - t, 1.0] }
+            }
+        })
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+
+    let previous: Vec<Entity> = world
+        .query_filtered::<Entity, With<CutFillMap>>()
+        .iter(world)
+        .collect();
+    for entity in previous {
+        bevy::hierarchy::despawn_with_children_recursive(world, entity);
+    }
+
+    let name = world.get::<Name>(compared).map_or("surface".to_string(), |name| name.to_string());
+    let map = spawn_mesh_node(world, mesh, offset, StandardMaterial {
+        double_sided: true,
+        cull_mode: None,
+        ..Default::default()
+    }, (Name::new(format!("Cut/fill ({})", name)), CutFillMap));
+    world.entity_mut(compared).insert((Visibility::Hidden, HiddenBy(map)));
+    Ok(())
+}
+
+fn export_cells(result: &CutFill, path: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let csv = CsvFile{
+        path,
+        header: true,
+        sep: b',',
+    };
+    csv.write_table(
+        &["x", "y", "reference", "compared", "difference"],
+        result.cells.iter().map(|cell| {
+            vec![
+                cell.x.to_string(),
+                cell.y.to_string(),
+                cell.reference.to_string(),
+                cell.compared.to_string(),
+                (cell.compared - cell.reference).to_string(),
+            ]
+        }),
+    )
+}
@@ -0,0 +1,280 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::block_model_mesh::BlockModel;
+use crate::custom_meshes::drill_holes_mesh::DrillHoleIntervals;
+use crate::project::{Project, Variograms};
+use crate::utilities::math::estimation::{self, Anisotropy, BlockGrid, SearchParameters};
+use crate::utilities::math::variogram::{StructureKind, VariogramModel, VariogramStructure};
+
+use super::hierarchy::HierarchyWindow;
+use super::load_block_model::spawn_block_model;
+
+/// Largest number of blocks estimated, every block searches the samples on the UI thread
+const MAX_BLOCKS: usize = 2_000_000;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EstimationMethod{
+    InverseDistance,
+    OrdinaryKriging,
+}
+
+pub struct EstimationWindowState{
+    variable: Option<String>,
+    grid: BlockGrid,
+    search: SearchParameters,
+    method: EstimationMethod,
+    power: f64,
+    variogram: VariogramModel,
+    estimate_result: Option<Result<usize, Box<dyn Error + Send + Sync>>>,
+}
+
+impl Default for EstimationWindowState {
+    fn default() -> Self {
+        Self {
+            variable: None,
+            grid: BlockGrid::default(),
+            search: SearchParameters::default(),
+            method: EstimationMethod::InverseDistance,
+            power: 2.0,
+            variogram: VariogramModel::default(),
+            estimate_result: None,
+        }
+    }
+}
+
+pub struct EstimationWindow;
+
+impl EditorWindow for EstimationWindow {
+    type State = EstimationWindowState;
+    const NAME: &'static str = "Estimation";
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let selected: Vec<Entity> = cx.state::<HierarchyWindow>().unwrap().selected.iter().collect();
+        let state = cx.state_mut::<EstimationWindow>().unwrap();
+
+        let entity = match selected.as_slice() {
+            [entity] if world.get::<DrillHoleIntervals>(*entity).is_some() => *entity,
+            _ => {
+                ui.label("Select a drill holes layer in the hierarchy");
+                return;
+            }
+        };
+        let intervals = world.get::<DrillHoleIntervals>(entity).unwrap();
+
+        if state.variable.as_ref().and_then(|v| intervals.variable_index(v)).is_none() {
+            state.variable = intervals.variables.first().cloned();
+        }
+
+        egui::ComboBox::from_label("Variable")
+            .selected_text(state.variable.clone().unwrap_or_default())
+            .show_ui(ui, |ui|{
+                for variable in intervals.variables.iter() {
+                    ui.selectable_value(&mut state.variable, Some(variable.clone()), variable);
+                }
+            });
+
+        egui::CollapsingHeader::new("Block grid")
+            .default_open(true)
+            .show(ui, |ui|{
+                grid_ui(ui, &mut state.grid);
+                if ui.button("Fit to drill holes").clicked() {
+                    let samples = state.variable.as_ref().and_then(|v| intervals.samples(v)).unwrap_or_default();
+                    if let Some(grid) = BlockGrid::fit(&samples, state.grid.block_size) {
+                        state.grid = grid;
+                    }
+                }
+            });
+
+        egui::CollapsingHeader::new("Search")
+            .default_open(true)
+            .show(ui, |ui|{
+                search_ui(ui, &mut state.search);
+            });
+
+        egui::CollapsingHeader::new("Method")
+            .default_open(true)
+            .show(ui, |ui|{
+                ui.horizontal(|ui|{
+                    ui.radio_value(&mut state.method, EstimationMethod::InverseDistance, "Inverse distance");
+                    ui.radio_value(&mut state.method, EstimationMethod::OrdinaryKriging, "Ordinary kriging");
+                });
+                match state.method {
+                    EstimationMethod::InverseDistance => {
+                        ui.add(egui::DragValue::new(&mut state.power).clamp_range(0.0..=10.0).speed(0.1).prefix("Power: "));
+                    }
+                    EstimationMethod::OrdinaryKriging => {
+                        let saved = state.variable.as_ref().and_then(|v| world.get::<Variograms>(entity)?.0.get(v));
+                        if ui.add_enabled(saved.is_some(), egui::Button::new("Use saved variogram")).clicked() {
+                            state.variogram = saved.unwrap().clone();
+                        }
+                        variogram_ui(ui, &mut state.variogram);
+                    }
+                }
+            });
+
+        if ui.button("Estimate").clicked() {
+            state.estimate_result = Some(estimate(world, entity, state));
+        }
+
+        if let Some(status) = &state.estimate_result {
+            match status {
+                Ok(blocks) => {
+                    ui.label(RichText::new(format!("{} blocks estimated", blocks)).color(egui::Color32::GREEN));
+                }
+                Err(error) => {
+                    ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+                }
+            }
+        }
+    }
+}
+
+fn grid_ui(ui: &mut egui::Ui, grid: &mut BlockGrid) {
+    egui::Grid::new("estimation grid").show(ui, |ui|{
+        ui.label("Origin");
+        for origin in grid.origin.iter_mut() {
+            ui.add(egui::DragValue::new(origin));
+        }
+        ui.end_row();
+
+        ui.label("Block size");
+        for size in grid.block_size.iter_mut() {
+            ui.add(egui::DragValue::new(size).clamp_range(0.01..=f64::MAX).speed(0.1));
+        }
+        ui.end_row();
+
+        ui.label("Blocks");
+        for count in grid.count.iter_mut() {
+            ui.add(egui::DragValue::new(count).clamp_range(1..=10000));
+        }
+        ui.end_row();
+    });
+}
+
+pub fn anisotropy_ui(ui: &mut egui::Ui, id: &str, anisotropy: &mut Anisotropy) {
+    egui::Grid::new(id).show(ui, |ui|{
+        ui.label("Azimuth / dip / rake");
+        ui.add(egui::DragValue::new(&mut anisotropy.azimuth).clamp_range(-360.0..=360.0).suffix("\u{B0}"));
+        ui.add(egui::DragValue::new(&mut anisotropy.dip).clamp_range(-90.0..=90.0).suffix("\u{B0}"));
+        ui.add(egui::DragValue::new(&mut anisotropy.rake).clamp_range(-180.0..=180.0).suffix("\u{B0}"));
+        ui.end_row();
+
+        ui.label("Ranges");
+        for range in anisotropy.ranges.iter_mut() {
+            ui.add(egui::DragValue::new(range).clamp_range(0.01..=f64::MAX));
+        }
+        ui.end_row();
+    });
+}
+
+/// Nugget and nested structures of `variogram`, with buttons to add and remove structures.
+pub fn variogram_ui(ui: &mut egui::Ui, variogram: &mut VariogramModel) {
+    ui.add(egui::DragValue::new(&mut variogram.nugget).clamp_range(0.0..=f64::MAX).speed(0.01).prefix("Nugget: "));
+
+    let mut removed = None;
+    for (i, structure) in variogram.structures.iter_mut().enumerate() {
+        ui.separator();
+        ui.horizontal(|ui|{
+            egui::ComboBox::from_id_source(("variogram structure", i))
+                .selected_text(format!("{:?}", structure.kind))
+                .show_ui(ui, |ui|{
+                    for kind in StructureKind::all() {
+                        ui.selectable_value(&mut structure.kind, kind, format!("{:?}", kind));
+                    }
+                });
+            ui.add(egui::DragValue::new(&mut structure.sill).clamp_range(0.0..=f64::MAX).speed(0.01).prefix("Sill: "));
+            if ui.small_button("\u{1F5D1}").clicked() {
+                removed = Some(i);
+            }
+        });
+        anisotropy_ui(ui, &format!("variogram structure {}", i), &mut structure.anisotropy);
+    }
+    if let Some(i) = removed {
+        variogram.structures.remove(i);
+    }
+
+    if ui.button("Add structure").clicked() {
+        variogram.structures.push(VariogramStructure{
+            kind: StructureKind::Spherical,
+            sill: 1.0,
+            anisotropy: Anisotropy::default(),
+        });
+    }
+}
+
+fn search_ui(ui: &mut egui::Ui, search: &mut SearchParameters) {
+    anisotropy_ui(ui, "search ellipsoid", &mut search.ellipsoid);
+    egui::Grid::new("search samples").show(ui, |ui|{
+        ui.label("Samples min / max");
+        ui.add(egui::DragValue::new(&mut search.min_samples).clamp_range(1..=search.max_samples));
+        ui.add(egui::DragValue::new(&mut search.max_samples).clamp_range(search.min_samples..=1000));
+        ui.end_row();
+
+        ui.label("Max per octant");
+        ui.add(egui::DragValue::new(&mut search.max_per_octant).clamp_range(0..=1000))
+            .on_hover_text("0 for no limit");
+        ui.end_row();
+
+        ui.label("Min octants");
+        ui.add(egui::DragValue::new(&mut search.min_octants).clamp_range(0..=8));
+        ui.end_row();
+    });
+}
+
+fn estimate(
+    world: &mut World,
+    entity: Entity,
+    state: &EstimationWindowState
+) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let variable = state.variable.clone().ok_or("Select the variable to estimate")?;
+    if state.grid.block_count() > MAX_BLOCKS {
+        return Err(format!("The grid has more than {} blocks, use larger blocks", MAX_BLOCKS).into());
+    }
+    let intervals = world.get::<DrillHoleIntervals>(entity).ok_or("The entity has no drill holes")?;
+    let samples = intervals.samples(&variable).ok_or("The variable is not in the drill holes")?;
+    if samples.is_empty() {
+        return Err("The variable has no values".into());
+    }
+    let offset = world.resource::<Project>().offset.unwrap_or(intervals.offset);
+
+    let (name, attributes, values): (String, Vec<String>, Vec<Option<Vec<f64>>>) = match state.method {
+        EstimationMethod::InverseDistance => {
+            let estimates = estimation::inverse_distance(&samples, &state.grid, &state.search, state.power);
+            (
+                format!("IDW ({})", variable),
+                vec![variable.clone(), "samples".to_string()],
+                estimates.into_iter()
+                    .map(|estimate| estimate.map(|e| vec![e.value, e.samples as f64]))
+                    .collect(),
+            )
+        }
+        EstimationMethod::OrdinaryKriging => {
+            if state.variogram.structures.is_empty() && state.variogram.nugget <= 0.0 {
+                return Err("The variogram has no sill".into());
+            }
+            let estimates = estimation::ordinary_kriging(&samples, &state.grid, &state.search, &state.variogram);
+            (
+                format!("OK ({})", variable),
+                vec![variable.clone(), "variance".to_string(), "samples".to_string()],
+                estimates.into_iter()
+                    .map(|estimate| estimate.map(|e| vec![e.value, e.variance, e.samples as f64]))
+                    .collect(),
+            )
+        }
+    };
+
+    let block_model = BlockModel::from_grid(&state.grid, attributes, values, offset);
+    let blocks = block_model.blocks.len();
+    if blocks == 0 {
+        return Err("No block has enough samples in its search ellipsoid".into());
+    }
+
+    spawn_block_model(world, block_model, Name::new(name), ());
+    Ok(blocks)
+}
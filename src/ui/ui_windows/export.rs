@@ -0,0 +1,132 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::drill_holes_mesh::DrillHoleIntervals;
+use crate::custom_meshes::imported_mesh::ImportedMesh;
+use crate::custom_meshes::mesh_handlers;
+use crate::custom_meshes::topography_mesh::TopographyMesh;
+use crate::project::Project;
+use crate::ui::ui_file_loader::files::DxfFile;
+use crate::ui::ui_file_loader::mesh_files::{MeshFile, MeshFormat};
+use crate::utilities::math::surface::SurfaceSampler;
+
+use super::clipping::ClipOriginal;
+use super::hierarchy::HierarchyWindow;
+
+#[derive(Default)]
+pub struct ExportWindowState{
+    export_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+}
+
+pub struct ExportWindow;
+
+impl EditorWindow for ExportWindow {
+    type State = ExportWindowState;
+    const NAME: &'static str = "Export";
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::File;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let selected: Vec<Entity> = cx.state::<HierarchyWindow>().unwrap().selected.iter().collect();
+        let state = cx.state_mut::<ExportWindow>().unwrap();
+
+        if selected.len() != 1 {
+            ui.label("Select the entity to export in the hierarchy");
+            return;
+        }
+        let entity = selected[0];
+
+        let name = world.get::<Name>(entity).map_or("Entity".to_string(), |name| name.to_string());
+        ui.label(RichText::new(name).strong());
+        ui.separator();
+
+        let is_topography = world.get::<TopographyMesh>(entity).is_some();
+        let is_drill_holes = world.get::<DrillHoleIntervals>(entity).is_some();
+        let has_mesh = world.get::<Handle<Mesh>>(entity).is_some();
+
+        ui.horizontal(|ui|{
+            if ui.add_enabled(is_topography || is_drill_holes, egui::Button::new("DXF")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("CAD files (dxf)", &["dxf"]).save_file() {
+                    state.export_result = Some(export_dxf(world, entity, path.display().to_string()));
+                }
+            }
+
+            for format in MeshFormat::all() {
+                let extension = format.extension();
+                if ui.add_enabled(has_mesh, egui::Button::new(extension.to_uppercase())).clicked() {
+                    if let Some(path) = rfd::FileDialog::new().add_filter(extension, &[extension]).save_file() {
+                        let path = path.with_extension(extension);
+                        state.export_result = Some(export_mesh(world, entity, path.display().to_string()));
+                    }
+                }
+            }
+        });
+
+        if let Some(status) = &state.export_result {
+            match status {
+                Ok(()) => {
+                    ui.label(RichText::new("Export Success!").color(egui::Color32::GREEN));
+                }
+                Err(error) => {
+                    ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+                }
+            }
+        }
+    }
+}
+
+fn export_dxf(world: &mut World, entity: Entity, path: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let dxf = DxfFile::new(path);
+
+    if let Some(drill_holes) = world.get::<DrillHoleIntervals>(entity) {
+        return dxf.write_drill_holes(drill_holes);
+    }
+
+    if world.get::<TopographyMesh>(entity).is_none() {
+        return Err("The entity is not a topography or drill holes".into());
+    }
+    let offset = entity_offset(world, entity);
+    let mesh = entity_mesh(world, entity).ok_or("The entity has no mesh")?;
+
+    dxf.write_mesh(mesh, offset, "TOPOGRAPHY")
+}
+
+fn export_mesh(world: &mut World, entity: Entity, path: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let offset = entity_offset(world, entity);
+    let mesh = entity_mesh(world, entity).ok_or("The entity has no mesh")?;
+    let data = mesh_handlers::mesh_to_data(mesh, offset).ok_or("Only triangle meshes can be exported")?;
+
+    MeshFile{ path }.write(&data)
+}
+
+/// World coordinates of the origin the vertices of `entity` are relative to.
+pub fn entity_offset(world: &World, entity: Entity) -> [f64;3] {
+    if let Some(topography) = world.get::<TopographyMesh>(entity) {
+        return [topography.offset_x, topography.offset_y, topography.offset_z];
+    }
+    if let Some(imported) = world.get::<ImportedMesh>(entity) {
+        return [imported.offset_x, imported.offset_y, imported.offset_z];
+    }
+    if let Some(drill_holes) = world.get::<DrillHoleIntervals>(entity) {
+        return drill_holes.offset;
+    }
+    world.resource::<Project>().offset.unwrap_or_default()
+}
+
+/// Loaded mesh of `entity`, the unclipped one while it is clipped.
+pub fn entity_mesh(world: &World, entity: Entity) -> Option<&Mesh> {
+    let handle = match world.get::<ClipOriginal>(entity) {
+        Some(original) => &original.0,
+        None => world.get::<Handle<Mesh>>(entity)?,
+    };
+    world.resource::<Assets<Mesh>>().get(handle)
+}
+
+/// Elevation sampler over the mesh of `entity`, in world coordinates.
+pub fn entity_surface(world: &World, entity: Entity) -> Result<SurfaceSampler, Box<dyn Error + Send + Sync>> {
+    let mesh = entity_mesh(world, entity).ok_or("The surface has no mesh")?;
+    mesh_handlers::surface_sampler(mesh, entity_offset(world, entity)).ok_or_else(|| "The surface is not a triangle mesh".into())
+}
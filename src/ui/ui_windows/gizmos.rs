@@ -37,8 +37,15 @@ impl EditorWindow for GizmoWindow {
 
     const NAME: &'static str = "Gizmos";
 
-    fn ui(_world: &mut World, _cx: EditorWindowContext, ui: &mut egui::Ui) {
-        ui.label("Gizmos can currently not be configured");
+    fn ui(_world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let state = cx.state_mut::<GizmoWindow>().unwrap();
+
+        ui.checkbox(&mut state.camera_gizmo_active, "Show gizmo");
+        ui.horizontal(|ui|{
+            ui.selectable_value(&mut state.gizmo_mode, GizmoMode::Translate, "Translate");
+            ui.selectable_value(&mut state.gizmo_mode, GizmoMode::Rotate, "Rotate");
+            ui.selectable_value(&mut state.gizmo_mode, GizmoMode::Scale, "Scale");
+        });
     }
 
     fn viewport_toolbar_ui(world: &mut World, cx: EditorWindowContext, ui: &mut egui::Ui) {
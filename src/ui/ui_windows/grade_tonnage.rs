@@ -0,0 +1,257 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::plot::{Line, Plot, PlotPoints, Points};
+use egui::RichText;
+
+use crate::custom_meshes::block_model_mesh::BlockModel;
+use crate::custom_meshes::topography_mesh::TopographyMesh;
+use crate::ui::ui_file_loader::files::CsvFile;
+use crate::utilities::math::statistics::{self, GradeTonnage};
+
+use super::export::entity_surface;
+use super::hierarchy::HierarchyWindow;
+
+pub struct GradeTonnageWindowState{
+    entity: Option<Entity>,
+    grade: Option<String>,
+    /// Attribute holding the density, the constant `density` is used when `None`
+    density_attribute: Option<String>,
+    density: f64,
+    cutoffs: [f64;3],
+    below_topography: Option<Entity>,
+    table: Vec<GradeTonnage>,
+    compute_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+    export_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+}
+
+impl Default for GradeTonnageWindowState {
+    fn default() -> Self {
+        Self {
+            entity: None,
+            grade: None,
+            density_attribute: None,
+            density: 2.7,
+            cutoffs: [0.0, 5.0, 0.25],
+            below_topography: None,
+            table: Vec::new(),
+            compute_result: None,
+            export_result: None,
+        }
+    }
+}
+
+pub struct GradeTonnageWindow;
+
+impl EditorWindow for GradeTonnageWindow {
+    type State = GradeTonnageWindowState;
+    const NAME: &'static str = "Grade Tonnage";
+    const DEFAULT_SIZE: (f32, f32) = (700.0, 600.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let selected: Vec<Entity> = cx.state::<HierarchyWindow>().unwrap().selected.iter().collect();
+        let state = cx.state_mut::<GradeTonnageWindow>().unwrap();
+
+        let entity = match selected.as_slice() {
+            [entity] if world.get::<BlockModel>(*entity).is_some() => *entity,
+            _ => {
+                ui.label("Select a block model in the hierarchy");
+                return;
+            }
+        };
+        let topographies: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), With<TopographyMesh>>()
+            .iter(world)
+            .map(|(topography, name)| (topography, name.to_string()))
+            .collect();
+        let block_model = world.get::<BlockModel>(entity).unwrap();
+
+        if state.entity != Some(entity) {
+            state.entity = Some(entity);
+            state.grade = block_model.attributes.first().cloned();
+            state.density_attribute = block_model.attributes.iter().find(|a| a.starts_with("dens")).cloned();
+            state.table.clear();
+            state.compute_result = None;
+        }
+
+        egui::Grid::new("grade tonnage parameters").show(ui, |ui|{
+            ui.label("Grade");
+            egui::ComboBox::from_id_source("grade tonnage grade")
+                .selected_text(state.grade.clone().unwrap_or_default())
+                .show_ui(ui, |ui|{
+                    for attribute in block_model.attributes.iter() {
+                        ui.selectable_value(&mut state.grade, Some(attribute.clone()), attribute);
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Density");
+            ui.horizontal(|ui|{
+                egui::ComboBox::from_id_source("grade tonnage density")
+                    .selected_text(state.density_attribute.clone().unwrap_or_else(|| "Constant".to_string()))
+                    .show_ui(ui, |ui|{
+                        ui.selectable_value(&mut state.density_attribute, None, "Constant");
+                        for attribute in block_model.attributes.iter() {
+                            ui.selectable_value(&mut state.density_attribute, Some(attribute.clone()), attribute);
+                        }
+                    });
+                if state.density_attribute.is_none() {
+                    ui.add(egui::DragValue::new(&mut state.density).clamp_range(0.01..=30.0).speed(0.01));
+                }
+            });
+            ui.end_row();
+
+            ui.label("Cutoffs from / to / step");
+            ui.horizontal(|ui|{
+                ui.add(egui::DragValue::new(&mut state.cutoffs[0]).speed(0.01));
+                ui.add(egui::DragValue::new(&mut state.cutoffs[1]).speed(0.01));
+                ui.add(egui::DragValue::new(&mut state.cutoffs[2]).clamp_range(0.0001..=f64::MAX).speed(0.01));
+            });
+            ui.end_row();
+
+            ui.label("Below topography");
+            ui.horizontal(|ui|{
+                if ui.selectable_label(state.below_topography.is_none(), "None").clicked() {
+                    state.below_topography = None;
+                }
+                for (topography, name) in topographies.iter() {
+                    if ui.selectable_label(state.below_topography == Some(*topography), name).clicked() {
+                        state.below_topography = Some(*topography);
+                    }
+                }
+            });
+            ui.end_row();
+        });
+
+        ui.horizontal(|ui|{
+            if ui.button("Compute").clicked() {
+                state.compute_result = Some(compute(world, entity, state));
+                state.export_result = None;
+            }
+            if ui.add_enabled(!state.table.is_empty(), egui::Button::new("Export CSV")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("Grade tonnage (csv)", &["csv"]).save_file() {
+                    state.export_result = Some(export_table(&state.table, path.with_extension("csv").display().to_string()));
+                }
+            }
+        });
+
+        for status in [&state.compute_result, &state.export_result].into_iter().flatten() {
+            if let Err(error) = status {
+                ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+            }
+        }
+        if let Some(Ok(())) = &state.export_result {
+            ui.label(RichText::new("Export Success!").color(egui::Color32::GREEN));
+        }
+
+        if !state.table.is_empty() {
+            plot_ui(ui, &state.table);
+            table_ui(ui, &state.table);
+        }
+    }
+}
+
+fn compute(
+    world: &mut World,
+    entity: Entity,
+    state: &mut GradeTonnageWindowState
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    state.table.clear();
+    let [from, to, step] = state.cutoffs;
+    if from > to {
+        return Err("The first cutoff is greater than the last".into());
+    }
+
+    let surface = state.below_topography.map(|topography| entity_surface(world, topography)).transpose()?;
+
+    let block_model = world.get::<BlockModel>(entity).ok_or("The entity is not a block model")?;
+    let grade = state.grade.as_ref()
+        .and_then(|g| block_model.attribute_index(g))
+        .ok_or("Select the grade attribute")?;
+    let density = state.density_attribute.as_ref().and_then(|d| block_model.attribute_index(d));
+
+    let blocks: Vec<(f64, f64)> = block_model.blocks.iter()
+        .filter(|block| match &surface {
+            Some(surface) => matches!(
+                surface.elevation(block.center[0], block.center[1]),
+                Some(elevation) if block.center[2] < elevation
+            ),
+            None => true,
+        })
+        .map(|block| {
+            let density = density.map_or(state.density, |d| block.values[d]);
+            let volume: f64 = block.size.iter().product();
+            (block.values[grade], volume * density)
+        })
+        .collect();
+
+    if blocks.is_empty() {
+        return Err("No block below the topography".into());
+    }
+
+    let steps = ((to - from) / step).floor() as usize;
+    let cutoffs: Vec<f64> = (0..=steps).map(|i| from + i as f64 * step).collect();
+    state.table = statistics::grade_tonnage(&blocks, &cutoffs);
+    Ok(())
+}
+
+fn export_table(table: &[GradeTonnage], path: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let csv = CsvFile{
+        path,
+        header: true,
+        sep: b',',
+    };
+    csv.write_table(
+        &["cutoff", "tonnage", "grade", "metal"],
+        table.iter().map(|row| {
+            vec![row.cutoff.to_string(), row.tonnage.to_string(), row.grade.to_string(), row.metal.to_string()]
+        }),
+    )
+}
+
+fn plot_ui(ui: &mut egui::Ui, table: &[GradeTonnage]) {
+    ui.columns(2, |columns|{
+        let tonnage: PlotPoints = table.iter().map(|row| [row.cutoff, row.tonnage]).collect();
+        Plot::new("tonnage curve")
+            .height(250.0)
+            .include_y(0.0)
+            .label_formatter(|_, value| format!("cutoff: {:.3}\ntonnage: {:.0}", value.x, value.y))
+            .show(&mut columns[0], |plot_ui|{
+                plot_ui.line(Line::new(tonnage).name("Tonnage"));
+            });
+
+        let grade: Vec<[f64;2]> = table.iter().map(|row| [row.cutoff, row.grade]).collect();
+        Plot::new("grade curve")
+            .height(250.0)
+            .include_y(0.0)
+            .label_formatter(|_, value| format!("cutoff: {:.3}\ngrade: {:.3}", value.x, value.y))
+            .show(&mut columns[1], |plot_ui|{
+                plot_ui.line(Line::new(PlotPoints::from(grade.clone())).name("Mean grade"));
+                plot_ui.points(Points::new(grade).radius(2.0));
+            });
+    });
+}
+
+fn table_ui(ui: &mut egui::Ui, table: &[GradeTonnage]) {
+    egui::ScrollArea::vertical()
+        .max_height(200.0)
+        .show(ui, |ui|{
+            egui::Grid::new("grade tonnage table").striped(true).show(ui, |ui|{
+                for header in ["Cutoff", "Tonnage", "Grade", "Metal"] {
+                    ui.label(RichText::new(header).strong());
+                }
+                ui.end_row();
+
+                for row in table {
+                    ui.label(format!("{:.3}", row.cutoff));
+                    ui.label(format!("{:.0}", row.tonnage));
+                    ui.label(format!("{:.3}", row.grade));
+                    ui.label(format!("{:.1}", row.metal));
+                    ui.end_row();
+                }
+            });
+        });
+}
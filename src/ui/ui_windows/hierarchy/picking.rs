@@ -1,8 +1,9 @@
-use bevy::{prelude::*, render::render_resource::PrimitiveTopology};
+use bevy::{prelude::*, render::{primitives::Aabb, render_resource::PrimitiveTopology, view::RenderLayers}};
 use bevy_mod_picking::{PickableBundle};
 use bevy_mod_picking::prelude::*;
 use bevy_egui::egui;
 
+use crate::custom_meshes::mesh_handlers;
 use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext};
 use crate::ui::ui_windows::cameras::camera_3d_panorbit::CameraSystem;
 
@@ -50,3 +51,44 @@ fn auto_add_editor_picking_set(
         }
     }
 }
+
+/// Closest visible pickable mesh `ray` goes through and the scene point where it does. Only the
+/// meshes whose bounds the ray enters before the closest hit so far are searched.
+pub fn raycast_surfaces(world: &mut World, ray: Ray) -> Option<(Entity, Vec3)> {
+    let mut targets: Vec<(f32, Entity, Ray, Handle<Mesh>)> = world
+        .query_filtered::<
+            (Entity, &GlobalTransform, &Handle<Mesh>, &ComputedVisibility, Option<&Aabb>),
+            (With<RaycastPickTarget>, Without<RenderLayers>),
+        >()
+        .iter(world)
+        .filter(|(_, _, _, visibility, _)| visibility.is_visible_in_hierarchy())
+        .filter_map(|(entity, transform, handle, _, aabb)| {
+            // Same distances along the ray in the mesh coordinates, its direction is not normalized
+            let inverse = transform.compute_matrix().inverse();
+            let local = Ray {
+                origin: inverse.transform_point3(ray.origin),
+                direction: inverse.transform_vector3(ray.direction),
+            };
+            // The bounds of a clipped mesh are those of the whole one, which contain it
+            let entry = match aabb {
+                Some(aabb) => mesh_handlers::ray_box_intersection(local, aabb.min().into(), aabb.max().into())?,
+                None => 0.0,
+            };
+            Some((entry, entity, local, handle.clone()))
+        })
+        .collect();
+    targets.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let meshes = world.resource::<Assets<Mesh>>();
+
+    let mut closest: Option<(Entity, f32)> = None;
+    for (entry, entity, local, handle) in targets {
+        if closest.map_or(false, |(_, distance)| distance < entry) {
+            break;
+        }
+        let Some(distance) = meshes.get(&handle).and_then(|mesh| mesh_handlers::ray_intersection(mesh, local)) else { continue };
+        if closest.map_or(true, |(_, closest)| distance < closest) {
+            closest = Some((entity, distance));
+        }
+    }
+    closest.map(|(entity, distance)| (entity, ray.get_point(distance)))
+}
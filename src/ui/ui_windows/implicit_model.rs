@@ -0,0 +1,272 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::drill_holes_mesh::{DrillHoleIntervals, DrillHoleLayer};
+use crate::custom_meshes::imported_mesh::ImportedMesh;
+use crate::custom_meshes::topography_mesh::TopographyMesh;
+use crate::project::Project;
+use crate::ui::ui_file_loader::mesh_files::MeshData;
+use crate::utilities::math::estimation::{Anisotropy, BlockGrid, Sample};
+use crate::utilities::math::implicit;
+use crate::utilities::math::rbf::{Kernel, Rbf};
+
+use super::estimation::anisotropy_ui;
+use super::export::entity_surface;
+use super::nodes_creator::spawn_mesh_node;
+use super::widgets::entity_combo;
+
+/// Largest number of samples in the RBF system, which is solved densely
+const MAX_SAMPLES: usize = 1_500;
+/// Largest number of grid nodes the field is evaluated at
+const MAX_NODES: usize = 500_000;
+/// Largest number of grid nodes times samples, every node sums the kernel of every sample on
+/// the UI thread
+const MAX_EVALUATIONS: f64 = 100_000_000.0;
+
+pub struct ImplicitModelWindowState{
+    drill_holes: Option<Entity>,
+    /// Rock codes inside the domain
+    rocks: Vec<String>,
+    /// Spacing of the grid the isosurface is extracted from
+    resolution: f64,
+    /// Distance to the contacts beyond which the samples stop growing
+    max_distance: f64,
+    /// Direction of greatest continuity, the ranges only matter relative to each other
+    trend: Anisotropy,
+    clip_to_topography: bool,
+    topography: Option<Entity>,
+    /// Samples and triangles of the last model
+    summary: Option<(usize, usize)>,
+    /// Holes with lithology that could not be placed without a collar or survey
+    unsurveyed: Vec<String>,
+    build_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+}
+
+impl Default for ImplicitModelWindowState {
+    fn default() -> Self {
+        Self {
+            drill_holes: None,
+            rocks: Vec::new(),
+            resolution: 10.0,
+            max_distance: 50.0,
+            trend: Anisotropy::default(),
+            clip_to_topography: false,
+            topography: None,
+            summary: None,
+            unsurveyed: Vec::new(),
+            build_result: None,
+        }
+    }
+}
+
+pub struct ImplicitModelWindow;
+
+impl EditorWindow for ImplicitModelWindow {
+    type State = ImplicitModelWindowState;
+    const NAME: &'static str = "Implicit model";
+    const DEFAULT_SIZE: (f32, f32) = (450.0, 500.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let state = cx.state_mut::<ImplicitModelWindow>().unwrap();
+
+        let drill_holes: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), (With<DrillHoleIntervals>, With<DrillHoleLayer>)>()
+            .iter(world)
+            .map(|(entity, name)| (entity, name.to_string()))
+            .collect();
+        let topographies: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), With<TopographyMesh>>()
+            .iter(world)
+            .map(|(entity, name)| (entity, name.to_string()))
+            .collect();
+        if drill_holes.is_empty() {
+            ui.label("Load drill holes");
+            return;
+        }
+
+        let rocks = state.drill_holes
+            .and_then(|entity| world.get::<DrillHoleIntervals>(entity))
+            .map(|intervals| intervals.lithology_rocks())
+            .unwrap_or_default();
+        state.rocks.retain(|rock| rocks.contains(rock));
+
+        egui::Grid::new("implicit drill holes").show(ui, |ui|{
+            ui.label("Drill holes");
+            entity_combo(ui, "implicit drill holes", &mut state.drill_holes, &drill_holes);
+            ui.end_row();
+        });
+
+        egui::CollapsingHeader::new("Domain")
+            .default_open(true)
+            .show(ui, |ui|{
+                if rocks.is_empty() {
+                    ui.label(RichText::new("The drill holes have no lithology").weak());
+                }
+                ui.horizontal_wrapped(|ui|{
+                    for rock in &rocks {
+                        let mut inside = state.rocks.contains(rock);
+                        if ui.checkbox(&mut inside, rock).changed() {
+                            if inside {
+                                state.rocks.push(rock.clone());
+                            } else {
+                                state.rocks.retain(|r| r != rock);
+                            }
+                        }
+                    }
+                });
+            });
+
+        egui::CollapsingHeader::new("Parameters")
+            .default_open(true)
+            .show(ui, |ui|{
+                egui::Grid::new("implicit parameters").show(ui, |ui|{
+                    ui.label("Resolution");
+                    ui.add(egui::DragValue::new(&mut state.resolution).clamp_range(0.1..=f64::MAX).speed(0.1).suffix(" m"));
+                    ui.end_row();
+
+                    ui.label("Distance limit");
+                    ui.add(egui::DragValue::new(&mut state.max_distance).clamp_range(0.1..=f64::MAX).speed(0.5).suffix(" m"))
+                        .on_hover_text("Samples further from the contacts along the holes keep this distance");
+                    ui.end_row();
+
+                    ui.label("Clip to topography");
+                    ui.horizontal(|ui|{
+                        ui.add_enabled(!topographies.is_empty(), egui::Checkbox::without_text(&mut state.clip_to_topography));
+                        if state.clip_to_topography {
+                            entity_combo(ui, "implicit topography", &mut state.topography, &topographies);
+                        }
+                    });
+                    ui.end_row();
+                });
+                ui.label("Trend");
+                anisotropy_ui(ui, "implicit trend", &mut state.trend);
+            });
+
+        if ui.button("Build model").clicked() {
+            state.build_result = Some(build_model(world, state));
+        }
+
+        if let Some(Err(error)) = &state.build_result {
+            ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+        }
+        if let (Some(Ok(())), Some((samples, triangles))) = (&state.build_result, state.summary) {
+            ui.label(format!("{} triangles fitted on {} samples", triangles, samples));
+        }
+        if !state.unsurveyed.is_empty() {
+            ui.label(RichText::new(format!(
+                "{} holes have no collar or survey and were skipped: {}",
+                state.unsurveyed.len(),
+                state.unsurveyed.join(", "),
+            )).color(egui::Color32::YELLOW));
+        }
+    }
+}
+
+fn build_model(world: &mut World, state: &mut ImplicitModelWindowState) -> Result<(), Box<dyn Error + Send + Sync>> {
+    state.summary = None;
+    state.unsurveyed.clear();
+    let drill_holes = state.drill_holes.ok_or("Select the drill holes")?;
+    if state.rocks.is_empty() {
+        return Err("Select the rocks inside the domain".into());
+    }
+    let topography = if state.clip_to_topography {
+        let topography = state.topography.ok_or("Select the topography")?;
+        Some(entity_surface(world, topography)?)
+    } else {
+        None
+    };
+
+    let intervals = world.get::<DrillHoleIntervals>(drill_holes).ok_or("The entity has no drill holes")?;
+    state.unsurveyed = intervals.unsurveyed_lithology();
+    let samples = decimate(intervals.domain_samples(&state.rocks, state.max_distance), MAX_SAMPLES);
+    if !samples.iter().any(|sample| sample.value < 0.0) {
+        return Err("No lithology interval is inside the domain".into());
+    }
+    if !samples.iter().any(|sample| sample.value > 0.0) {
+        return Err("Every lithology interval is inside the domain".into());
+    }
+
+    // The field is fitted where the trend ellipsoid becomes a sphere
+    let metric = state.trend.metric();
+    let scaled: Vec<Sample> = samples.iter()
+        .map(|sample| Sample { position: metric.scaled(sample.position), value: sample.value })
+        .collect();
+    let rbf = Rbf::fit(&scaled, Kernel::Cubic, 3, 0.0).ok_or("The samples repeat, the field can not be fitted")?;
+
+    // One node of margin around the samples, outside of the domain
+    let grid = {
+        let first = samples[0].position;
+        let (min, max) = samples.iter().fold((first, first), |(min, max), s| {
+            ([0, 1, 2].map(|i| min[i].min(s.position[i])), [0, 1, 2].map(|i| max[i].max(s.position[i])))
+        });
+        BlockGrid {
+            origin: [0, 1, 2].map(|i| min[i] - 1.5 * state.resolution),
+            block_size: [state.resolution; 3],
+            count: [0, 1, 2].map(|i| ((max[i] - min[i]) / state.resolution).ceil() as usize + 3),
+        }
+    };
+    if grid.block_count() > MAX_NODES {
+        return Err("The resolution is too fine for the drill holes".into());
+    }
+    if grid.block_count() as f64 * samples.len() as f64 > MAX_EVALUATIONS {
+        return Err("The resolution is too fine for this many samples".into());
+    }
+
+    let values: Vec<f64> = (0..grid.block_count())
+        .map(|node| {
+            let position = grid.center(node);
+            let value = rbf.value(metric.scaled(position));
+            // Above the topography is outside
+            match topography.as_ref().and_then(|surface| surface.elevation(position[0], position[1])) {
+                Some(elevation) => value.max(position[2] - elevation),
+                None => value,
+            }
+        })
+        .collect();
+
+    let (positions, triangles) = implicit::isosurface(&grid, &values);
+    if triangles.is_empty() {
+        return Err("The domain is smaller than the resolution".into());
+    }
+
+    let triangle_count = triangles.len();
+    let project_offset = world.resource::<Project>().offset;
+    let (mesh, imported) = ImportedMesh::from_mesh_data(MeshData { positions, triangles, colors: None }, project_offset);
+    let offset = [imported.offset_x, imported.offset_y, imported.offset_z];
+    spawn_mesh_node(
+        world,
+        mesh,
+        offset,
+        StandardMaterial{
+            base_color: Color::rgba(0.9, 0.5, 0.2, 0.6),
+            alpha_mode: AlphaMode::Blend,
+            cull_mode: None,
+            ..Default::default()
+        },
+        (imported, Name::new(format!("Domain ({})", state.rocks.join(", ")))),
+    );
+    state.summary = Some((samples.len(), triangle_count));
+    Ok(())
+}
+
+/// Keeps every contact and evenly spaced other samples, up to `max` samples.
+fn decimate(samples: Vec<Sample>, max: usize) -> Vec<Sample> {
+    if samples.len() <= max {
+        return samples;
+    }
+    let (contacts, others): (Vec<Sample>, Vec<Sample>) = samples.into_iter().partition(|sample| sample.value == 0.0);
+    let every = |samples: Vec<Sample>, count: usize| -> Vec<Sample> {
+        let step = (samples.len() as f64 / count.max(1) as f64).ceil().max(1.0) as usize;
+        samples.into_iter().step_by(step).collect()
+    };
+    if contacts.len() >= max {
+        return every(contacts, max);
+    }
+    let remaining = max - contacts.len();
+    contacts.into_iter().chain(every(others, remaining)).collect()
+}
@@ -0,0 +1,190 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::drill_holes_mesh::{DrillHoleIntervals, DrillHoleLayer};
+use crate::custom_meshes::imported_mesh::ImportedMesh;
+use crate::custom_meshes::topography_mesh::TopographyMesh;
+use crate::ui::ui_file_loader::files::CsvFile;
+
+use super::export::entity_surface;
+use super::nodes_creator::{spawn_mesh_node, topography_material};
+use super::widgets::entity_combo;
+
+/// Point where a hole crosses the surface.
+pub struct PiercePoint{
+    pub hole_id: String,
+    pub depth: f64,
+    /// World coordinates
+    pub position: [f64;3],
+}
+
+#[derive(Default)]
+pub struct IntersectionsWindowState{
+    drill_holes: Option<Entity>,
+    surface: Option<Entity>,
+    pierce_points: Vec<PiercePoint>,
+    /// Holes that do not reach the surface or lie outside of it
+    missed: Vec<String>,
+    compute_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+    export_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+    surface_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+}
+
+pub struct IntersectionsWindow;
+
+impl EditorWindow for IntersectionsWindow {
+    type State = IntersectionsWindowState;
+    const NAME: &'static str = "Hole intersections";
+    const DEFAULT_SIZE: (f32, f32) = (500.0, 500.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let state = cx.state_mut::<IntersectionsWindow>().unwrap();
+
+        let drill_holes: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), (With<DrillHoleIntervals>, With<DrillHoleLayer>)>()
+            .iter(world)
+            .map(|(entity, name)| (entity, name.to_string()))
+            .collect();
+        let surfaces: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), Or<(With<TopographyMesh>, With<ImportedMesh>)>>()
+            .iter(world)
+            .map(|(entity, name)| (entity, name.to_string()))
+            .collect();
+        if drill_holes.is_empty() || surfaces.is_empty() {
+            ui.label("Load drill holes and a surface");
+            return;
+        }
+
+        egui::Grid::new("intersections parameters").show(ui, |ui|{
+            ui.label("Drill holes");
+            entity_combo(ui, "intersections drill holes", &mut state.drill_holes, &drill_holes);
+            ui.end_row();
+
+            ui.label("Surface");
+            entity_combo(ui, "intersections surface", &mut state.surface, &surfaces);
+            ui.end_row();
+        });
+
+        ui.horizontal(|ui|{
+            if ui.button("Intersect").clicked() {
+                state.compute_result = Some(compute(world, state));
+                state.export_result = None;
+                state.surface_result = None;
+            }
+            let has_points = !state.pierce_points.is_empty();
+            if ui.add_enabled(has_points, egui::Button::new("Export CSV")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("Pierce points (csv)", &["csv"]).save_file() {
+                    state.export_result = Some(export_points(&state.pierce_points, path.with_extension("csv").display().to_string()));
+                }
+            }
+            if ui.add_enabled(state.pierce_points.len() >= 3, egui::Button::new("Build surface"))
+                .on_hover_text("Triangulates the pierce points into a new topography surface")
+                .clicked() {
+                state.surface_result = Some(build_surface(world, state));
+            }
+        });
+
+        for status in [&state.compute_result, &state.export_result, &state.surface_result].into_iter().flatten() {
+            if let Err(error) = status {
+                ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+            }
+        }
+        if let Some(Ok(())) = &state.export_result {
+            ui.label(RichText::new("Export Success!").color(egui::Color32::GREEN));
+        }
+
+        if state.pierce_points.is_empty() {
+            return;
+        }
+        ui.separator();
+        ui.label(format!("{} holes intersect the surface", state.pierce_points.len()));
+        if !state.missed.is_empty() {
+            ui.label(RichText::new(format!("{} holes do not: {}", state.missed.len(), state.missed.join(", "))).weak());
+        }
+        egui::ScrollArea::vertical().id_source("pierce points").show(ui, |ui|{
+            egui::Grid::new("pierce points").striped(true).show(ui, |ui|{
+                for header in ["Hole", "X", "Y", "Z", "Depth"] {
+                    ui.label(RichText::new(header).strong());
+                }
+                ui.end_row();
+                for point in &state.pierce_points {
+                    let [x, y, z] = point.position;
+                    ui.label(&point.hole_id);
+                    ui.label(format!("{:.2}", x));
+                    ui.label(format!("{:.2}", y));
+                    ui.label(format!("{:.2}", z));
+                    ui.label(format!("{:.2}", point.depth));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+}
+
+fn compute(world: &mut World, state: &mut IntersectionsWindowState) -> Result<(), Box<dyn Error + Send + Sync>> {
+    state.pierce_points.clear();
+    state.missed.clear();
+    let drill_holes = state.drill_holes.ok_or("Select the drill holes")?;
+    let surface = state.surface.ok_or("Select the surface")?;
+
+    let intervals = world.get::<DrillHoleIntervals>(drill_holes).ok_or("The entity has no drill holes")?;
+    let sampler = entity_surface(world, surface)?;
+    for trace in intervals.traces() {
+        match sampler.pierce(&trace.stations) {
+            Some((depth, position)) => state.pierce_points.push(PiercePoint { hole_id: trace.hole_id, depth, position }),
+            None => state.missed.push(trace.hole_id),
+        }
+    }
+
+    if state.pierce_points.is_empty() {
+        return Err("No hole intersects the surface".into());
+    }
+    Ok(())
+}
+
+fn export_points(points: &[PiercePoint], path: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let csv = CsvFile{
+        path,
+        header: true,
+        sep: b',',
+    };
+    csv.write_table(
+        &["hole-id", "x", "y", "z", "depth"],
+        points.iter().map(|point| {
+            vec![
+                point.hole_id.clone(),
+                point.position[0].to_string(),
+                point.position[1].to_string(),
+                point.position[2].to_string(),
+                point.depth.to_string(),
+            ]
+        }),
+    )
+}
+
+/// Spawns a topography surface triangulating the pierce points, named after the intersected
+/// surface.
+fn build_surface(world: &mut World, state: &IntersectionsWindowState) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (mesh, topography) = TopographyMesh::from_points(state.pierce_points.iter().map(|point| point.position).collect());
+    if !matches!(mesh.indices(), Some(indices) if !indices.is_empty()) {
+        return Err("The pierce points are aligned".into());
+    }
+
+    let name = state.surface
+        .and_then(|surface| world.get::<Name>(surface))
+        .map_or("surface".to_string(), |name| name.to_string());
+    let offset = topography.offset();
+    spawn_mesh_node(
+        world,
+        mesh,
+        offset,
+        topography_material(),
+        (topography, Name::new(format!("Pierce points ({})", name))),
+    );
+    Ok(())
+}
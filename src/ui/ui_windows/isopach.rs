@@ -0,0 +1,248 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use bevy::render::mesh::PrimitiveTopology;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::mesh_handlers;
+use crate::custom_meshes::topography_mesh::TopographyMesh;
+use crate::systems::HiddenBy;
+use crate::ui::ui_file_loader::grid_files::GridFile;
+use crate::utilities::math::analytic_geometry;
+use crate::utilities::math::grid::{Grid, GridGeometry};
+
+use super::export::entity_mesh;
+use super::nodes_creator::spawn_mesh_node;
+use super::widgets::entity_combo;
+
+/// Largest number of grid cells sampled, the cell size has to grow for larger surfaces
+const MAX_CELLS: f64 = 2_000_000.0;
+/// Largest number of contour levels drawn, the interval has to grow for thicker intervals
+const MAX_CONTOURS: f64 = 200.0;
+
+/// Thickness map draped on the top surface, with its contours as children.
+#[derive(Component)]
+pub struct IsopachMap;
+
+pub struct IsopachWindowState{
+    top: Option<Entity>,
+    bottom: Option<Entity>,
+    cell: f64,
+    contour_interval: f64,
+    /// Vertical thickness of the top surface above the bottom one
+    thickness: Option<Grid>,
+    compute_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+    export_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+}
+
+impl Default for IsopachWindowState {
+    fn default() -> Self {
+        Self {
+            top: None,
+            bottom: None,
+            cell: 5.0,
+            contour_interval: 1.0,
+            thickness: None,
+            compute_result: None,
+            export_result: None,
+        }
+    }
+}
+
+pub struct IsopachWindow;
+
+impl EditorWindow for IsopachWindow {
+    type State = IsopachWindowState;
+    const NAME: &'static str = "Isopach";
+    const DEFAULT_SIZE: (f32, f32) = (450.0, 350.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let state = cx.state_mut::<IsopachWindow>().unwrap();
+
+        let topographies: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), With<TopographyMesh>>()
+            .iter(world)
+            .map(|(topography, name)| (topography, name.to_string()))
+            .collect();
+        if topographies.len() < 2 {
+            ui.label("Load two topography surfaces");
+            return;
+        }
+
+        egui::Grid::new("isopach parameters").show(ui, |ui|{
+            ui.label("Top surface");
+            entity_combo(ui, "isopach top", &mut state.top, &topographies);
+            ui.end_row();
+
+            ui.label("Bottom surface");
+            entity_combo(ui, "isopach bottom", &mut state.bottom, &topographies);
+            ui.end_row();
+
+            ui.label("Cell size");
+            ui.add(egui::DragValue::new(&mut state.cell).clamp_range(0.01..=f64::MAX).speed(0.1).suffix(" m"));
+            ui.end_row();
+
+            ui.label("Contour interval");
+            ui.add(egui::DragValue::new(&mut state.contour_interval).clamp_range(0.01..=f64::MAX).speed(0.1).suffix(" m"));
+            ui.end_row();
+        });
+
+        ui.horizontal(|ui|{
+            if ui.button("Compute").clicked() {
+                state.compute_result = Some(compute(world, state));
+                state.export_result = None;
+            }
+            if ui.add_enabled(state.thickness.is_some(), egui::Button::new("Export grid")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("ESRI ASCII grid (asc)", &["asc"]).save_file() {
+                    if let Some(thickness) = &state.thickness {
+                        let file = GridFile { path: path.with_extension("asc").display().to_string() };
+                        state.export_result = Some(file.write(thickness));
+                    }
+                }
+            }
+        });
+
+        for status in [&state.compute_result, &state.export_result].into_iter().flatten() {
+            if let Err(error) = status {
+                ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+            }
+        }
+        if let Some(Ok(())) = &state.export_result {
+            ui.label(RichText::new("Export Success!").color(egui::Color32::GREEN));
+        }
+
+        let Some(thickness) = &state.thickness else { return };
+        let Some((min, max)) = thickness.range() else { return };
+        let defined: Vec<f64> = thickness.values.iter().flatten().copied().collect();
+        let mean = defined.iter().sum::<f64>() / defined.len() as f64;
+        let cell_area = thickness.geometry.cell * thickness.geometry.cell;
+        ui.separator();
+        egui::Grid::new("isopach thickness").striped(true).show(ui, |ui|{
+            ui.label("Minimum thickness");
+            ui.label(format!("{:.2} m", min));
+            ui.end_row();
+            ui.label("Maximum thickness");
+            ui.label(format!("{:.2} m", max));
+            ui.end_row();
+            ui.label("Mean thickness");
+            ui.label(format!("{:.2} m", mean));
+            ui.end_row();
+            ui.label("Area");
+            ui.label(format!("{:.1} m²", defined.len() as f64 * cell_area));
+            ui.end_row();
+        });
+    }
+}
+
+fn compute(world: &mut World, state: &mut IsopachWindowState) -> Result<(), Box<dyn Error + Send + Sync>> {
+    state.thickness = None;
+    let top = state.top.ok_or("Select the top surface")?;
+    let bottom = state.bottom.ok_or("Select the bottom surface")?;
+    if top == bottom {
+        return Err("Select two different surfaces".into());
+    }
+
+    let top_topography = world.get::<TopographyMesh>(top).ok_or("The top is not a topography surface")?;
+    let bottom_topography = world.get::<TopographyMesh>(bottom).ok_or("The bottom is not a topography surface")?;
+    let top_mesh = entity_mesh(world, top).ok_or("The top surface has no mesh")?;
+    let bottom_mesh = entity_mesh(world, bottom).ok_or("The bottom surface has no mesh")?;
+
+    let (top_min, top_max) = top_topography.plan_bounds(top_mesh).ok_or("The top surface is not a triangle mesh")?;
+    let (bottom_min, bottom_max) = bottom_topography.plan_bounds(bottom_mesh).ok_or("The bottom surface is not a triangle mesh")?;
+    let min = [top_min[0].max(bottom_min[0]), top_min[1].max(bottom_min[1])];
+    let max = [top_max[0].min(bottom_max[0]), top_max[1].min(bottom_max[1])];
+    if min[0] >= max[0] || min[1] >= max[1] {
+        return Err("The surfaces do not overlap".into());
+    }
+    if (max[0] - min[0]) * (max[1] - min[1]) / (state.cell * state.cell) > MAX_CELLS {
+        return Err("The cell size is too small for the surfaces".into());
+    }
+
+    let geometry = GridGeometry::covering(min, max, state.cell);
+    let top_grid = top_topography.resample_to_grid(top_mesh, geometry).ok_or("The top surface is not a triangle mesh")?;
+    let bottom_grid = bottom_topography.resample_to_grid(bottom_mesh, geometry).ok_or("The bottom surface is not a triangle mesh")?;
+    let thickness = top_grid.combine(&bottom_grid, |top, bottom| top - bottom).unwrap();
+    if thickness.range().is_none() {
+        return Err("The surfaces do not overlap".into());
+    }
+
+    show_isopach(world, top, &top_grid, &thickness, state.contour_interval)?;
+    state.thickness = Some(thickness);
+    Ok(())
+}
+
+/// Replaces the previous map by the top surface resampled where the thickness is defined and
+/// colored by it, with its contours, hiding the top surface.
+fn show_isopach(
+    world: &mut World,
+    top: Entity,
+    top_grid: &Grid,
+    thickness: &Grid,
+    interval: f64,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (min, max) = thickness.range().ok_or("The surfaces do not overlap")?;
+    if (max / interval).floor() - (min / interval).ceil() > MAX_CONTOURS {
+        return Err("The contour interval is too small for the thickness".into());
+    }
+
+    let draped = top_grid.combine(thickness, |top, _| top).unwrap();
+    let (mut mesh, topography) = TopographyMesh::from_grid(&draped)
+        .ok_or("The surfaces overlap on too few cells, reduce the cell size")?;
+    // One vertex per defined value, in the same order as the thickness
+    let colors: Vec<[f32;4]> = thickness.values.iter()
+        .flatten()
+        .map(|t| mesh_handlers::color_scale(((t - min) / (max - min).max(f64::EPSILON)) as f32))
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+
+    let offset = topography.offset();
+    let contours: Vec<[f32;3]> = ((min / interval).ceil() as i64..=(max / interval).floor() as i64)
+        .flat_map(|level| thickness.contour(level as f64 * interval))
+        .filter_map(|segment| {
+            let scene = |[x, y]: [f64;2]| draped.interpolate(x, y)
+                .map(|z| analytic_geometry::world_to_scene([x, y, z], offset).to_array());
+            Some([scene(segment[0])?, scene(segment[1])?])
+        })
+        .flatten()
+        .collect();
+
+    let previous: Vec<Entity> = world
+        .query_filtered::<Entity, With<IsopachMap>>()
+        .iter(world)
+        .collect();
+    for entity in previous {
+        bevy::hierarchy::despawn_with_children_recursive(world, entity);
+    }
+
+    let name = world.get::<Name>(top).map_or("surface".to_string(), |name| name.to_string());
+    let map = spawn_mesh_node(world, mesh, offset, StandardMaterial {
+        double_sided: true,
+        cull_mode: None,
+        ..Default::default()
+    }, (Name::new(format!("Isopach ({})", name)), IsopachMap));
+    world.entity_mut(top).insert((Visibility::Hidden, HiddenBy(map)));
+
+    if contours.is_empty() {
+        return Ok(());
+    }
+    let mut contour_mesh = Mesh::new(PrimitiveTopology::LineList);
+    contour_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, contours);
+    let contour_mesh = world.resource_mut::<Assets<Mesh>>().add(contour_mesh);
+    let material = world.resource_mut::<Assets<StandardMaterial>>().add(StandardMaterial {
+        base_color: Color::BLACK,
+        unlit: true,
+        ..Default::default()
+    });
+    let contours = world.spawn((PbrBundle {
+        mesh: contour_mesh,
+        material,
+        ..Default::default()
+    },
+    Name::new("Contours"),
+    )).id();
+    world.entity_mut(map).add_child(contours);
+    Ok(())
+}
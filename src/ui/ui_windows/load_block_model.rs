@@ -0,0 +1,150 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::block_model_mesh::{BlockModel, BlockModelDisplay, BlockModelFrame};
+use crate::project::Project;
+use crate::ui::ui_file_loader::files::{CsvFile, FileProperties};
+use crate::ui::ui_windows::nodes_creator::spawn_mesh_node;
+
+/// Overlapping pairs listed in the window, the rest are only counted.
+const LISTED_OVERLAPS: usize = 10;
+
+#[derive(Default)]
+pub struct LoadBlockModelWindowState{
+    pub path: String,
+    headers: bool,
+    rotated: bool,
+    frame: BlockModelFrame,
+    overlaps: Vec<(usize, usize)>,
+    load_files_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+}
+
+pub struct LoadBlockModel;
+
+impl EditorWindow for LoadBlockModel {
+
+    type State = LoadBlockModelWindowState;
+    const NAME: &'static str = "Load Block Model";
+    const RESIZABLE: bool = false;
+    const COLLAPSIBLE: bool = false;
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::File;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui){
+        let state = cx.state_mut::<LoadBlockModel>().unwrap();
+
+        ui.vertical(|ui|{
+
+            ui.horizontal(|ui|{
+                egui::TextEdit::singleline(&mut state.path)
+                    .hint_text("X, Y, Z, DX, DY, DZ, AU")
+                    .show(ui);
+
+                ui.checkbox( &mut state.headers, "Has headers");
+
+                if ui.button("Load Block Model").clicked() {
+                    if let Some(path) = rfd::FileDialog::new().add_filter("Block model", &["csv"]).pick_file() {
+                        state.path = path.display().to_string();
+                        state.headers = true;
+                    }
+                }
+            });
+
+            ui.separator();
+
+            ui.checkbox(&mut state.rotated, "Coordinates in a rotated model frame");
+            ui.add_enabled_ui(state.rotated, |ui|{
+                egui::Grid::new("block model frame").show(ui, |ui|{
+                    ui.label("Origin");
+                    ui.add(egui::DragValue::new(&mut state.frame.origin[0]).prefix("x: "));
+                    ui.add(egui::DragValue::new(&mut state.frame.origin[1]).prefix("y: "));
+                    ui.add(egui::DragValue::new(&mut state.frame.origin[2]).prefix("z: "));
+                    ui.end_row();
+
+                    ui.label("Bearing");
+                    ui.add(egui::DragValue::new(&mut state.frame.bearing).clamp_range(-360.0..=360.0).suffix("\u{B0}"));
+                    ui.end_row();
+                });
+            });
+
+            ui.separator();
+
+            if ui.button("Load").clicked() {
+                state.load_files_result = Some(load_files(world, state));
+            }
+        });
+
+        if let Some(status) = &state.load_files_result {
+            match status {
+                Ok(()) => {
+                    ui.label(RichText::new("Load Success!").color(egui::Color32::GREEN));
+                    if !state.overlaps.is_empty() {
+                        ui.label(RichText::new(format!("{} overlapping block pairs:", state.overlaps.len())).color(egui::Color32::YELLOW));
+                        for (a, b) in state.overlaps.iter().take(LISTED_OVERLAPS) {
+                            ui.label(RichText::new(format!("rows {} and {}", a + 1, b + 1)).color(egui::Color32::YELLOW));
+                        }
+                    }
+                }
+                Err(error) => {
+                    ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+                }
+            }
+        }
+    }
+}
+
+fn load_files(
+    world: &mut World,
+    state: &mut LoadBlockModelWindowState
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    state.overlaps.clear();
+    if state.path.is_empty() {
+        return Err("Select the block model file".into());
+    }
+
+    let csv = CsvFile{
+        path: state.path.clone(),
+        header: state.headers,
+        sep: b',',
+    };
+    let frame = if state.rotated { state.frame } else { BlockModelFrame::default() };
+
+    state.overlaps = generate_block_model_from_csv(csv, &frame, world)?;
+    Ok(())
+}
+
+/// Spawns the block model read from `csv` and returns the pairs of overlapping blocks.
+pub fn generate_block_model_from_csv(
+    csv: CsvFile,
+    frame: &BlockModelFrame,
+    world: &mut World
+) -> Result<Vec<(usize, usize)>, Box<dyn Error + Send + Sync>> {
+    let project_offset = world.resource::<Project>().offset;
+    let block_model = BlockModel::from_csv(&csv, frame, project_offset)?;
+    let overlaps = block_model.overlapping_blocks();
+
+    spawn_block_model(world, block_model, Name::new(csv.name().unwrap()), csv);
+
+    Ok(overlaps)
+}
+
+/// Spawns `block_model` colored by its first attribute.
+pub fn spawn_block_model(world: &mut World, block_model: BlockModel, name: Name, bundle: impl Bundle) -> Entity {
+    let display = BlockModelDisplay{
+        attribute: block_model.attributes.first().cloned(),
+        filter: None,
+    };
+    let mesh = block_model.mesh(&display);
+    let offset = block_model.offset;
+
+    spawn_mesh_node(
+        world,
+        mesh,
+        offset,
+        StandardMaterial::default(),
+        (block_model, display, name, bundle),
+    )
+}
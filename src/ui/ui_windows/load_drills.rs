@@ -11,7 +11,7 @@ use bevy_egui::egui;
 use egui::{RichText};
 
 use crate::custom_meshes::topography_mesh::TopographyMesh;
-use crate::custom_meshes::drill_holes_mesh::DrillHolesMesh;
+use crate::custom_meshes::drill_holes_mesh::{DrillHoleIntervals, DrillHoleLayer, DrillHolesMesh};
 use crate::ui::ui_file_loader::files::CsvFile;
 
 
@@ -192,28 +192,10 @@ fn load_files(
         }
     }
 
-    let final_meshes = DrillHolesMesh::from_csv(drill_holes);
+    let (final_meshes, intervals) = DrillHolesMesh::from_csv(drill_holes);
 
-    for final_mesh in final_meshes{
-        let mut meshes = world.get_resource_mut::<Assets<Mesh>>().unwrap();
-        let mesh = meshes.add(final_mesh);
-
-        let mut materials = world
-            .get_resource_mut::<Assets<StandardMaterial>>()
-            .unwrap();
-        let material = materials.add(
-            StandardMaterial::default()
-        );
-
-        let drill_holes_id = world.spawn((PbrBundle {
-            mesh,
-            material,
-            ..Default::default()
-        },
-        Name::new("Drill Holes")
-        )).id();
-
-        world.entity_mut(state.topography_mesh.unwrap()).add_child(drill_holes_id);
+    for (variable, final_mesh) in final_meshes{
+        spawn_drill_hole_layer(world, state.topography_mesh, final_mesh, &intervals, variable);
     }
 
     //TODO
@@ -221,3 +203,36 @@ fn load_files(
     Ok(())
 }
 
+/// Spawns the mesh of a drill holes layer showing `variable`, as a child of `parent`.
+pub fn spawn_drill_hole_layer(
+    world: &mut World,
+    parent: Option<Entity>,
+    mesh: Mesh,
+    intervals: &DrillHoleIntervals,
+    variable: String,
+) -> Entity {
+    let mut meshes = world.get_resource_mut::<Assets<Mesh>>().unwrap();
+    let mesh = meshes.add(mesh);
+
+    let mut materials = world
+        .get_resource_mut::<Assets<StandardMaterial>>()
+        .unwrap();
+    let material = materials.add(
+        StandardMaterial::default()
+    );
+
+    let drill_holes_id = world.spawn((PbrBundle {
+        mesh,
+        material,
+        ..Default::default()
+    },
+    Name::new(format!("Drill Holes ({})", variable)),
+    DrillHoleLayer{ variable },
+    intervals.clone(),
+    )).id();
+
+    if let Some(parent) = parent {
+        world.entity_mut(parent).add_child(drill_holes_id);
+    }
+    drill_holes_id
+}
@@ -0,0 +1,226 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::RichText;
+
+use crate::custom_meshes::topography_mesh::TopographyMesh;
+use crate::project::DxfLayerSelection;
+use crate::ui::ui_file_loader::files::{DxfFile, FileProperties};
+use crate::ui::ui_windows::nodes_creator::{spawn_mesh_node, topography_material};
+
+struct DxfLayerRow{
+    name: String,
+    entities: usize,
+    points: bool,
+    breaklines: bool,
+}
+
+#[derive(Default)]
+pub struct LoadDxfWindowState{
+    pub path: String,
+    layers_path: String,
+    layers: Vec<DxfLayerRow>,
+    read_layers_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+    skipped: Vec<(String, usize)>,
+    load_files_result: Option<Result<(), Box<dyn Error + Send + Sync>>>,
+}
+
+pub struct LoadDxf;
+
+impl EditorWindow for LoadDxf {
+
+    type State = LoadDxfWindowState;
+    const NAME: &'static str = "Load Dxf";
+    const RESIZABLE: bool = false;
+    const COLLAPSIBLE: bool = false;
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::File;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui){
+        let state = cx.state_mut::<LoadDxf>().unwrap();
+
+        ui.vertical(|ui|{
+
+            let (path_edit, read_clicked) = ui.horizontal(|ui|{
+                let path_edit = egui::TextEdit::singleline(&mut state.path)
+                    .hint_text("Topography dxf")
+                    .show(ui)
+                    .response;
+
+                if ui.button("Load Dxf").clicked() {
+                    if let Some(path) = rfd::FileDialog::new().add_filter("CAD files (dxf)", &["dxf"]).pick_file() {
+                        state.path = path.display().to_string();
+                    }
+                }
+                (path_edit, ui.button("Read layers").clicked())
+            }).inner;
+
+            // A typed path is read once the field loses focus, not on every keystroke
+            if read_clicked || (state.path != state.layers_path && !path_edit.has_focus()) {
+                state.layers_path = state.path.clone();
+                state.load_files_result = None;
+                state.skipped.clear();
+                state.read_layers_result = Some(read_layers(world, state));
+            }
+
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui|{
+                    egui::Grid::new("dxf layers").striped(true).show(ui, |ui|{
+                        ui.label(RichText::new("Layer").strong());
+                        ui.label(RichText::new("Entities").strong());
+                        ui.label(RichText::new("Points").strong());
+                        ui.label(RichText::new("Breaklines").strong());
+                        ui.end_row();
+
+                        for layer in state.layers.iter_mut(){
+                            ui.label(&layer.name);
+                            ui.label(layer.entities.to_string());
+                            if ui.checkbox(&mut layer.points, "").changed() && layer.points {
+                                layer.breaklines = false;
+                            }
+                            if ui.checkbox(&mut layer.breaklines, "").changed() && layer.breaklines {
+                                layer.points = false;
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+
+            ui.horizontal(|ui|{
+                if ui.button("All as points").clicked() {
+                    for layer in state.layers.iter_mut(){
+                        layer.points = true;
+                        layer.breaklines = false;
+                    }
+                }
+                if ui.button("Clear").clicked() {
+                    for layer in state.layers.iter_mut(){
+                        layer.points = false;
+                        layer.breaklines = false;
+                    }
+                }
+            });
+
+            if let Some(Err(error)) = &state.read_layers_result {
+                ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+            }
+
+            ui.separator();
+
+            if ui.button("Load Topography").clicked() {
+                state.load_files_result = Some(load_files(world, state));
+            }
+
+        });
+
+        if let Some(status) = &state.load_files_result {
+            match status {
+                Ok(()) => {
+                    ui.label(RichText::new("Load Success!").color(egui::Color32::GREEN));
+                    if !state.skipped.is_empty() {
+                        ui.label("Skipped entities:");
+                        for (entity_type, count) in state.skipped.iter() {
+                            ui.label(RichText::new(format!("{}: {}", entity_type, count)).color(egui::Color32::YELLOW));
+                        }
+                    }
+                }
+                Err(error) => {
+                    ui.label(RichText::new(error.to_string()).color(egui::Color32::RED));
+                }
+            }
+        }
+    }
+}
+
+fn read_layers(
+    world: &mut World,
+    state: &mut LoadDxfWindowState
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    state.layers.clear();
+    if state.path.is_empty() {
+        return Ok(());
+    }
+
+    let layers = DxfFile::new(state.path.clone()).layers()?;
+    let remembered = world.query::<&DxfLayerSelection>()
+        .iter(world)
+        .filter(|selection| selection.path == state.path && !selection.point_layers.is_empty())
+        .last()
+        .cloned();
+
+    state.layers = layers.into_iter()
+        .map(|(name, entities)| {
+            let (points, breaklines) = match &remembered {
+                Some(selection) => (
+                    selection.point_layers.contains(&name),
+                    selection.breakline_layers.contains(&name),
+                ),
+                None => (entities > 0, false),
+            };
+            DxfLayerRow{ name, entities, points, breaklines }
+        })
+        .collect();
+
+    Ok(())
+}
+
+fn load_files(
+    world: &mut World,
+    state: &mut LoadDxfWindowState
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let dxf = DxfFile{
+        path: state.path.clone(),
+        point_layers: state.layers.iter().filter(|l| l.points).map(|l| l.name.clone()).collect(),
+        breakline_layers: state.layers.iter().filter(|l| l.breaklines).map(|l| l.name.clone()).collect(),
+    };
+
+    if dxf.point_layers.is_empty() {
+        return Err("Select at least one layer for the points".into());
+    }
+
+    state.skipped = generate_topography_mesh_from_dxf(&dxf, world)?;
+    Ok(())
+}
+
+/// Spawns the topography read from `dxf` and returns the entity types that were skipped.
+/// 3DFACE entities are used as the triangulation when present, otherwise the points are triangulated.
+/// The points and breaklines found next to 3DFACE entities are reported as skipped.
+pub fn generate_topography_mesh_from_dxf(dxf: &DxfFile, world: &mut World) -> Result<Vec<(String, usize)>, Box<dyn Error + Send + Sync>> {
+
+    let mut geometry = dxf.get_geometry()?;
+    let (topography_mesh, topography) = if !geometry.faces.is_empty() {
+        let (points, breaklines) = (geometry.points.len(), geometry.breaklines.len());
+        if points > 0 {
+            geometry.skip("Points beside 3DFACE", points);
+        }
+        if breaklines > 0 {
+            geometry.skip("Breaklines beside 3DFACE", breaklines);
+        }
+        TopographyMesh::from_faces(&geometry.faces)
+    } else if !geometry.points.is_empty() {
+        TopographyMesh::from_points_with_breaklines(geometry.points, &geometry.breaklines)
+    } else {
+        return Err("No points found in the selected layers".into());
+    };
+
+    let offset = [topography.offset_x, topography.offset_y, topography.offset_z];
+    let layers = DxfLayerSelection{
+        path: dxf.path.clone(),
+        point_layers: dxf.point_layers.clone(),
+        breakline_layers: dxf.breakline_layers.clone(),
+    };
+
+    spawn_mesh_node(
+        world,
+        topography_mesh,
+        offset,
+        topography_material(),
+        (topography, dxf.clone(), layers, Name::new(dxf.name().unwrap())),
+    );
+
+    Ok(geometry.skipped)
+}
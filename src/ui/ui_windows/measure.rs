@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::{Align2, Color32, FontId, RichText, Stroke};
+
+use crate::project::Project;
+use crate::utilities::math::analytic_geometry;
+
+use super::cameras::{self, ActiveEditorCamera};
+use super::hierarchy::picking::raycast_surfaces;
+
+#[derive(Default)]
+pub struct MeasureWindowState{
+    /// Clicks on the surfaces of the viewport add points while measuring
+    measuring: bool,
+    /// World coordinates of the picked points
+    points: Vec<[f64;3]>,
+    /// Closes the polyline back to its first point
+    closed: bool,
+}
+
+impl MeasureWindowState {
+    /// Consecutive pairs of points, with the closing one when the polyline is closed.
+    fn segments(&self) -> Vec<([f64;3], [f64;3])> {
+        let closing = (self.closed && self.points.len() >= 3)
+            .then(|| (*self.points.last().unwrap(), self.points[0]));
+        self.points.windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .chain(closing)
+            .collect()
+    }
+
+    /// Closed polyline in plan, to be used as a boundary by the other tools.
+    pub fn polygon(&self) -> Option<Vec<[f64;2]>> {
+        (self.closed && self.points.len() >= 3).then(|| self.points.iter().map(|p| [p[0], p[1]]).collect())
+    }
+
+    fn area(&self) -> Option<f64> {
+        (self.closed && self.points.len() >= 3).then(|| analytic_geometry::plan_area(&self.points))
+    }
+}
+
+pub struct MeasureWindow;
+
+impl EditorWindow for MeasureWindow {
+    type State = MeasureWindowState;
+    const NAME: &'static str = "Measure";
+    const DEFAULT_SIZE: (f32, f32) = (500.0, 500.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(_world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let state = cx.state_mut::<MeasureWindow>().unwrap();
+
+        ui.horizontal(|ui|{
+            ui.toggle_value(&mut state.measuring, "Measure");
+            ui.checkbox(&mut state.closed, "Closed");
+            if ui.button("Undo").clicked() {
+                state.points.pop();
+            }
+            if ui.button("Clear").clicked() {
+                state.points.clear();
+            }
+        });
+        ui.label(RichText::new("Click on the surfaces in the viewport to add points").weak());
+        ui.separator();
+
+        let segments = state.segments();
+        if segments.is_empty() {
+            ui.label("Pick at least two points");
+            return;
+        }
+
+        egui::ScrollArea::vertical().id_source("measure segments").max_height(250.0).show(ui, |ui|{
+            egui::Grid::new("measure segments").striped(true).show(ui, |ui|{
+                for header in ["Segment", "Distance", "Horizontal", "Bearing", "Dip"] {
+                    ui.label(RichText::new(header).strong());
+                }
+                ui.end_row();
+
+                let count = state.points.len();
+                for (i, (a, b)) in segments.iter().enumerate() {
+                    let [dx, dy, _] = analytic_geometry::sub(*b, *a);
+                    let (bearing, dip) = analytic_geometry::azimuth_dip(*a, *b);
+                    ui.label(format!("{} – {}", i + 1, (i + 1) % count + 1));
+                    ui.label(format!("{:.2} m", analytic_geometry::norm(analytic_geometry::sub(*b, *a))));
+                    ui.label(format!("{:.2} m", dx.hypot(dy)));
+                    ui.label(format!("{:.1}°", bearing));
+                    ui.label(format!("{:.1}°", dip));
+                    ui.end_row();
+                }
+            });
+        });
+
+        ui.separator();
+        let length: f64 = segments.iter().map(|(a, b)| analytic_geometry::norm(analytic_geometry::sub(*b, *a))).sum();
+        let horizontal_length: f64 = segments.iter().map(|(a, b)| (b[0] - a[0]).hypot(b[1] - a[1])).sum();
+        egui::Grid::new("measure totals").show(ui, |ui|{
+            ui.label("Length");
+            ui.label(format!("{:.2} m", length));
+            ui.end_row();
+            ui.label("Horizontal length");
+            ui.label(format!("{:.2} m", horizontal_length));
+            ui.end_row();
+            if let Some(area) = state.area() {
+                ui.label("Area in plan");
+                ui.label(format!("{:.2} m²", area));
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        let mut removed = None;
+        egui::CollapsingHeader::new("Points").show(ui, |ui|{
+            egui::Grid::new("measure points").striped(true).show(ui, |ui|{
+                for (i, [x, y, z]) in state.points.iter().enumerate() {
+                    ui.label((i + 1).to_string());
+                    ui.label(format!("{:.2}", x));
+                    ui.label(format!("{:.2}", y));
+                    ui.label(format!("{:.2}", z));
+                    if ui.small_button("\u{1F5D1}").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        if let Some(i) = removed {
+            state.points.remove(i);
+        }
+    }
+
+    fn viewport_ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let state = cx.state_mut::<MeasureWindow>().unwrap();
+        let viewport = ui.clip_rect();
+
+        if state.measuring && ui.input(|i| i.pointer.primary_clicked()) {
+            let hit = cameras::viewport_pointer(ui)
+                .and_then(|position| cameras::active_camera_ray(world, viewport, position))
+                .and_then(|ray| raycast_surfaces(world, ray));
+            if let Some((_, point)) = hit {
+                state.points.push(world.resource::<Project>().displayed_to_world(point));
+            }
+        }
+
+        if state.points.is_empty() {
+            return;
+        }
+        draw_measurements(world, state, ui);
+    }
+}
+
+/// Polyline of the picked points, with the length of its segments and its area.
+fn draw_measurements(world: &mut World, state: &MeasureWindowState, ui: &egui::Ui) {
+    let mut active_cameras = world.query_filtered::<(&Camera, &GlobalTransform), With<ActiveEditorCamera>>();
+    let Some((camera, transform)) = active_cameras.iter(world).next() else { return };
+    let project = world.resource::<Project>();
+
+    let viewport = ui.clip_rect();
+    let screen = |point: [f64;3]| {
+        cameras::scene_to_viewport(camera, transform, viewport, project.world_to_displayed(point))
+    };
+    let painter = ui.painter_at(viewport);
+    let stroke = Stroke::new(2.0, Color32::YELLOW);
+    let font = FontId::proportional(13.0);
+
+    for (a, b) in state.segments() {
+        let (Some(screen_a), Some(screen_b)) = (screen(a), screen(b)) else { continue };
+        painter.line_segment([screen_a, screen_b], stroke);
+        painter.text(
+            screen_a + (screen_b - screen_a) * 0.5,
+            Align2::CENTER_BOTTOM,
+            format!("{:.2} m", analytic_geometry::norm(analytic_geometry::sub(b, a))),
+            font.clone(),
+            Color32::WHITE,
+        );
+    }
+    for point in state.points.iter().filter_map(|point| screen(*point)) {
+        painter.circle_filled(point, 4.0, Color32::YELLOW);
+    }
+
+    if let Some(area) = state.area() {
+        let count = state.points.len() as f64;
+        let centroid = state.points.iter()
+            .fold([0.0; 3], |sum, point| [sum[0] + point[0] / count, sum[1] + point[1] / count, sum[2] + point[2] / count]);
+        if let Some(centroid) = screen(centroid) {
+            painter.text(centroid, Align2::CENTER_CENTER, format!("{:.2} m²", area), font, Color32::YELLOW);
+        }
+    }
+}
@@ -3,16 +3,35 @@
 
 pub mod add;
 pub mod assets;
+pub mod block_model;
 pub mod cameras;
+pub mod capping;
+pub mod clipping;
+pub mod contact_surface;
+pub mod cut_fill;
 pub mod debug_settings;
 pub mod diagnostics;
+pub mod estimation;
+pub mod export;
 pub mod gizmos;
+pub mod grade_tonnage;
 pub mod hierarchy;
+pub mod implicit_model;
 pub mod inspector;
+pub mod intersections;
+pub mod isopach;
+pub mod measure;
 pub mod renderer;
 pub mod resources;
+pub mod scatter;
 pub mod scenes;
+pub mod section;
+pub mod statistics;
+pub mod variography;
+pub mod widgets;
 pub mod new_project;
+pub mod load_block_model;
 pub mod load_drills;
+pub mod load_dxf;
 pub mod load_topography;
 pub mod nodes_creator;
\ No newline at end of file
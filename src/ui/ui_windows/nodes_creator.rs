@@ -3,11 +3,17 @@
 use bevy::prelude::*;
 use bevy_egui::egui;
 
+use crate::custom_meshes::imported_mesh::ImportedMesh;
 use crate::custom_meshes::topography_mesh::TopographyMesh;
+use crate::project::Project;
 use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
-use crate::ui::ui_file_loader::files::{CsvFile, DxfFile, FileProperties};
+use crate::ui::ui_file_loader::files::{CsvFile, FileProperties};
+use crate::ui::ui_file_loader::mesh_files::MeshFile;
+use crate::utilities::math::analytic_geometry;
 
+use crate::ui::ui_windows::load_block_model::LoadBlockModel;
 use crate::ui::ui_windows::load_drills::LoadDrills;
+use crate::ui::ui_windows::load_dxf::LoadDxf;
 
 
 #[derive(Default)]
@@ -70,12 +76,9 @@ fn make_ui(world: &mut World,
                                     if ui.selectable_label(false,"\u{1F5B9} From dxf file").clicked(){
 
                                         if let Some(path) = rfd::FileDialog::new().add_filter("CAD files (dxf)", &["dxf"]).pick_file() {
-                                            let dxf = DxfFile{
-                                                path: Some(path.display().to_string()).unwrap()
-                                            };
-                                            result = Option::from(generate_topography_mesh_from_dxf(&dxf, world));
-                                            let state = cx.state_mut::<NodesCreator>().unwrap();
-                                            state.load_node_result=result;
+                                            let state = cx.state_mut::<LoadDxf>().unwrap();
+                                            state.path = path.display().to_string();
+                                            cx.open_floating_window::<LoadDxf>();
                                         }
                                     }
 
@@ -95,6 +98,23 @@ fn make_ui(world: &mut World,
                             if ui.selectable_label(false,"\u{1F4A2} Drill Holes").clicked(){
                                 cx.open_floating_window::<LoadDrills>();
                             }
+                            if ui.selectable_label(false,"\u{1F9CA} Block Model").clicked(){
+                                cx.open_floating_window::<LoadBlockModel>();
+                            }
+                            egui::CollapsingHeader::new("\u{1F537} Mesh")
+                                .default_open(true)
+                                .show(ui, |ui|{
+                                    if ui.selectable_label(false,"\u{1F5B9} From obj, stl or ply file").clicked(){
+                                        if let Some(path) = rfd::FileDialog::new().add_filter("Mesh files (obj, stl, ply)", &["obj", "stl", "ply"]).pick_file() {
+                                            let mesh_file = MeshFile{
+                                                path: path.display().to_string(),
+                                            };
+                                            result = Option::from(generate_mesh_from_file(mesh_file, world));
+                                            let state = cx.state_mut::<NodesCreator>().unwrap();
+                                            state.load_node_result=result;
+                                        }
+                                    }
+                                });
                         });
                 });
         });
@@ -117,57 +137,73 @@ fn make_ui(world: &mut World,
 
 
 
-fn generate_topography_mesh_from_dxf(dxf: &DxfFile, world: &mut World) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-
+fn generate_topography_mesh_from_csv(csv: CsvFile, world: &mut World) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (topography_mesh, topography) = TopographyMesh::from_csv(&csv).unwrap();
+    let offset = [topography.offset_x, topography.offset_y, topography.offset_z];
+
+    spawn_mesh_node(
+        world,
+        topography_mesh,
+        offset,
+        topography_material(),
+        (topography, csv.clone(), Name::new(csv.name().unwrap())),
+    );
 
-    let _points: Vec<[f64;3]> = dxf.get_points();
-    let (topography_mesh, topography) = TopographyMesh::from_points(_points);
+    Ok(())
+}
 
-    let mut meshes = world.get_resource_mut::<Assets<Mesh>>().unwrap();
-    let mesh = meshes.add(topography_mesh);
+fn generate_mesh_from_file(mesh_file: MeshFile, world: &mut World) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let data = mesh_file.read()?;
+    let project_offset = world.resource::<Project>().offset;
+    let (mesh, imported) = ImportedMesh::from_mesh_data(data, project_offset);
+    let offset = [imported.offset_x, imported.offset_y, imported.offset_z];
 
-    let mut materials = world
-        .get_resource_mut::<Assets<StandardMaterial>>()
-        .unwrap();
-    let material = materials.add(
+    spawn_mesh_node(
+        world,
+        mesh,
+        offset,
         StandardMaterial{
-            base_color: Color::rgb(135.0/255.0,135.0/255.0,73.0/255.0),
+            base_color: Color::rgb(160.0/255.0,160.0/255.0,170.0/255.0),
             cull_mode: None,
             ..Default::default()
-        }
+        },
+        (imported, mesh_file.clone(), Name::new(mesh_file.name().unwrap())),
     );
 
-    world.spawn((PbrBundle {
-        mesh,
-        material,
-        ..Default::default()
-    }, topography, dxf.clone(), Name::new(dxf.name().unwrap())));
-
     Ok(())
 }
 
-fn generate_topography_mesh_from_csv(csv: CsvFile, world: &mut World) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let (topography_mesh, topography) = TopographyMesh::from_csv(&csv).unwrap();
+pub fn topography_material() -> StandardMaterial {
+    StandardMaterial{
+        base_color: Color::rgb(135.0/255.0,135.0/255.0,73.0/255.0),
+        cull_mode: None,
+        ..Default::default()
+    }
+}
+
+/// Spawns a mesh node whose vertices had `offset` subtracted. The first node fixes the project
+/// offset, later ones are translated so every node shares the same origin.
+pub fn spawn_mesh_node(
+    world: &mut World,
+    mesh: Mesh,
+    offset: [f64;3],
+    material: StandardMaterial,
+    bundle: impl Bundle,
+) -> Entity {
+    let project_offset = *world.resource_mut::<Project>().offset.get_or_insert(offset);
 
     let mut meshes = world.get_resource_mut::<Assets<Mesh>>().unwrap();
-    let mesh = meshes.add(topography_mesh);
+    let mesh = meshes.add(mesh);
 
     let mut materials = world
         .get_resource_mut::<Assets<StandardMaterial>>()
         .unwrap();
-    let material = materials.add(
-        StandardMaterial{
-            base_color: Color::rgb(135.0/255.0,135.0/255.0,73.0/255.0),
-            cull_mode: None,
-            ..Default::default()
-        }
-    );
+    let material = materials.add(material);
 
     world.spawn((PbrBundle {
         mesh,
         material,
+        transform: Transform::from_translation(analytic_geometry::world_to_scene(offset, project_offset)),
         ..Default::default()
-    }, topography, csv.clone(), Name::new(csv.name().unwrap())));
-
-    Ok(())
-}
\ No newline at end of file
+    }, bundle)).id()
+}
@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::plot::{Legend, Line, Plot, PlotPoints, Points, Polygon};
+use egui::RichText;
+
+use crate::custom_meshes::drill_holes_mesh::{DrillHoleIntervals, DrillHoleSelection, DrillHolesMesh};
+use crate::utilities::math::statistics;
+
+use super::hierarchy::HierarchyWindow;
+
+/// Screen distance in points within which a click picks the closest point of the plot
+const PICK_DISTANCE: f32 = 8.0;
+
+pub struct ScatterWindowState{
+    x: Option<String>,
+    y: Option<String>,
+    log_x: bool,
+    log_y: bool,
+    regression: bool,
+    /// Layer the selected interval indices refer to
+    source: Option<Entity>,
+    selection: Vec<usize>,
+    /// Plot coordinates where the selection rectangle started
+    drag_start: Option<[f64;2]>,
+}
+
+impl Default for ScatterWindowState {
+    fn default() -> Self {
+        Self {
+            x: None,
+            y: None,
+            log_x: false,
+            log_y: false,
+            regression: true,
+            source: None,
+            selection: Vec::new(),
+            drag_start: None,
+        }
+    }
+}
+
+pub struct ScatterWindow;
+
+impl EditorWindow for ScatterWindow {
+    type State = ScatterWindowState;
+    const NAME: &'static str = "Scatter Plot";
+    const DEFAULT_SIZE: (f32, f32) = (700.0, 700.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let selected: Vec<Entity> = cx.state::<HierarchyWindow>().unwrap().selected.iter().collect();
+        let state = cx.state_mut::<ScatterWindow>().unwrap();
+
+        let entity = match selected.as_slice() {
+            [entity] if world.get::<DrillHoleIntervals>(*entity).is_some() => *entity,
+            _ => {
+                ui.label("Select a drill holes layer in the hierarchy");
+                return;
+            }
+        };
+        if state.source != Some(entity) {
+            state.source = Some(entity);
+            state.selection.clear();
+            show_selection(world, entity, &[]);
+        }
+
+        let intervals = world.get::<DrillHoleIntervals>(entity).unwrap();
+        let variables = intervals.variables.clone();
+
+        if !matches!(&state.x, Some(x) if variables.contains(x)) {
+            state.x = variables.first().cloned();
+        }
+        if !matches!(&state.y, Some(y) if variables.contains(y)) {
+            state.y = variables.get(1).or(variables.first()).cloned();
+        }
+
+        ui.horizontal(|ui|{
+            variable_combo(ui, "X", &mut state.x, &variables);
+            ui.checkbox(&mut state.log_x, "Log");
+            ui.separator();
+            variable_combo(ui, "Y", &mut state.y, &variables);
+            ui.checkbox(&mut state.log_y, "Log");
+            ui.separator();
+            ui.checkbox(&mut state.regression, "Regression line");
+        });
+
+        let (Some(x_index), Some(y_index)) = (
+            state.x.as_ref().and_then(|x| intervals.variable_index(x)),
+            state.y.as_ref().and_then(|y| intervals.variable_index(y)),
+        ) else {
+            return;
+        };
+
+        // (interval index, plot coordinates, rock) of the intervals with both values plottable
+        let points: Vec<(usize, [f64;2], Option<String>)> = intervals.intervals.iter()
+            .enumerate()
+            .filter_map(|(i, interval)| {
+                let x = axis_value(interval.values[x_index], state.log_x)?;
+                let y = axis_value(interval.values[y_index], state.log_y)?;
+                Some((i, [x, y], interval.rock.clone()))
+            })
+            .collect();
+
+        let mut groups: BTreeMap<String, Vec<[f64;2]>> = BTreeMap::new();
+        for (_, point, rock) in points.iter() {
+            groups.entry(rock.clone().unwrap_or_else(|| "No lithology".to_string())).or_default().push(*point);
+        }
+        let highlighted: Vec<[f64;2]> = points.iter()
+            .filter(|(i, _, _)| state.selection.contains(i))
+            .map(|(_, point, _)| *point)
+            .collect();
+
+        let x_range = points.iter().fold((f64::MAX, f64::MIN), |(min, max), (_, [x, _], _)| (min.min(*x), max.max(*x)));
+        let regression = statistics::linear_regression(points.iter().map(|(_, [x, y], _)| (*x, *y)));
+        ui.horizontal(|ui|{
+            ui.label(format!("{} intervals", points.len()));
+            if let Some(regression) = regression {
+                ui.separator();
+                ui.label(format!("r = {:.3}", regression.correlation));
+                ui.separator();
+                ui.label(format!("y = {:.4}x {:+.4}", regression.slope, regression.intercept))
+                    .on_hover_text("Fitted on the plotted values, the logarithms on log axes");
+            }
+            ui.separator();
+            ui.label(format!("{} selected", state.selection.len()));
+            if ui.small_button("Clear").clicked() {
+                state.selection.clear();
+                show_selection(world, entity, &[]);
+            }
+        });
+
+        let (log_x, log_y) = (state.log_x, state.log_y);
+        let (x_name, y_name) = (state.x.clone().unwrap_or_default(), state.y.clone().unwrap_or_default());
+        let shift = ui.input(|i| i.modifiers.shift);
+
+        let new_selection = Plot::new("scatter plot")
+            .height(400.0)
+            .legend(Legend::default())
+            .allow_drag(!shift)
+            .x_axis_formatter(move |x, _| axis_label(x, log_x))
+            .y_axis_formatter(move |y, _| axis_label(y, log_y))
+            .label_formatter(move |name, value| {
+                format!("{}\n{}: {}\n{}: {}", name, x_name, axis_label(value.x, log_x), y_name, axis_label(value.y, log_y))
+            })
+            .show(ui, |plot_ui|{
+                for (rock, rock_points) in groups {
+                    plot_ui.points(Points::new(rock_points).radius(2.5).name(rock));
+                }
+                if !highlighted.is_empty() {
+                    plot_ui.points(Points::new(highlighted).radius(4.0).color(egui::Color32::YELLOW).name("Selected"));
+                }
+
+                if let Some(regression) = regression.filter(|_| state.regression) {
+                    let line: PlotPoints = [x_range.0, x_range.1].iter()
+                        .map(|x| [*x, regression.slope * x + regression.intercept])
+                        .collect();
+                    plot_ui.line(Line::new(line).color(egui::Color32::RED).name("Regression"));
+                }
+
+                let (pressed, released) = plot_ui.ctx().input(|i| (i.pointer.primary_pressed(), i.pointer.primary_released()));
+                let pointer = plot_ui.pointer_coordinate().map(|p| [p.x, p.y]);
+
+                if shift && pressed && plot_ui.plot_hovered() {
+                    state.drag_start = pointer;
+                }
+
+                if let Some(start) = state.drag_start {
+                    if let Some(end) = pointer {
+                        let rectangle: PlotPoints = vec![start, [end[0], start[1]], end, [start[0], end[1]]].into();
+                        plot_ui.polygon(Polygon::new(rectangle).color(egui::Color32::YELLOW).fill_alpha(0.1));
+                    }
+                    if released {
+                        state.drag_start = None;
+                        return pointer.map(|end| {
+                            points.iter()
+                                .filter(|(_, [x, y], _)| {
+                                    (start[0].min(end[0])..=start[0].max(end[0])).contains(x)
+                                        && (start[1].min(end[1])..=start[1].max(end[1])).contains(y)
+                                })
+                                .map(|(i, _, _)| *i)
+                                .collect()
+                        });
+                    }
+                } else if plot_ui.plot_clicked() {
+                    let cursor = plot_ui.ctx().input(|i| i.pointer.interact_pos());
+                    let closest = cursor.and_then(|cursor| {
+                        points.iter()
+                            .map(|(i, [x, y], _)| (*i, plot_ui.screen_from_plot([*x, *y].into()).distance(cursor)))
+                            .filter(|(_, distance)| *distance <= PICK_DISTANCE)
+                            .min_by(|a, b| a.1.total_cmp(&b.1))
+                    });
+                    return Some(closest.map(|(i, _)| vec![i]).unwrap_or_default());
+                }
+                None
+            })
+            .inner;
+        ui.label(RichText::new("Click a point or shift + drag a rectangle to highlight the intervals in the viewport").weak());
+
+        if let Some(selection) = new_selection {
+            show_selection(world, entity, &selection);
+            state.selection = selection;
+        }
+
+        ui.separator();
+        egui::CollapsingHeader::new("Correlation matrix").show(ui, |ui|{
+            correlation_matrix(ui, world.get::<DrillHoleIntervals>(entity).unwrap());
+        });
+    }
+}
+
+fn variable_combo(ui: &mut egui::Ui, label: &str, variable: &mut Option<String>, variables: &[String]) {
+    egui::ComboBox::from_label(label)
+        .selected_text(variable.clone().unwrap_or_default())
+        .show_ui(ui, |ui|{
+            for v in variables.iter() {
+                ui.selectable_value(variable, Some(v.clone()), v);
+            }
+        });
+}
+
+/// Plot coordinate of `value`, its logarithm on log axes where only positive values are shown.
+fn axis_value(value: f64, log: bool) -> Option<f64> {
+    match (value.is_finite(), log) {
+        (false, _) => None,
+        (true, true) if value <= 0.0 => None,
+        (true, true) => Some(value.log10()),
+        (true, false) => Some(value),
+    }
+}
+
+fn axis_label(coordinate: f64, log: bool) -> String {
+    if log {
+        format!("{:.3}", 10f64.powf(coordinate))
+    } else {
+        format!("{:.3}", coordinate)
+    }
+}
+
+/// Pearson correlation between every pair of variables, over the intervals where both are
+/// finite. Strong positive correlations are drawn in red and negative ones in blue.
+fn correlation_matrix(ui: &mut egui::Ui, intervals: &DrillHoleIntervals) {
+    egui::ScrollArea::both().max_height(300.0).show(ui, |ui|{
+        egui::Grid::new("scatter correlation matrix").striped(true).show(ui, |ui|{
+            ui.label("");
+            for variable in intervals.variables.iter() {
+                ui.label(RichText::new(variable).strong());
+            }
+            ui.end_row();
+
+            for (i, row) in intervals.variables.iter().enumerate() {
+                ui.label(RichText::new(row).strong());
+                for j in 0..intervals.variables.len() {
+                    let correlation = statistics::linear_regression(intervals.intervals.iter()
+                        .map(|interval| (interval.values[j], interval.values[i])))
+                        .map(|regression| regression.correlation)
+                        .filter(|r| r.is_finite());
+
+                    match correlation {
+                        Some(r) => {
+                            let intensity = (r.abs().min(1.0) * 255.0) as u8;
+                            let color = if r >= 0.0 {
+                                egui::Color32::from_rgb(255, 255 - intensity, 255 - intensity)
+                            } else {
+                                egui::Color32::from_rgb(255 - intensity, 255 - intensity, 255)
+                            };
+                            ui.label(RichText::new(format!("{:.2}", r)).color(color));
+                        }
+                        None => {
+                            ui.label("-");
+                        }
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    });
+}
+
+/// Replaces the highlighted intervals with the `indices` of the intervals of `layer`.
+fn show_selection(world: &mut World, layer: Entity, indices: &[usize]) {
+    let previous: Vec<Entity> = world
+        .query_filtered::<Entity, With<DrillHoleSelection>>()
+        .iter(world)
+        .collect();
+    for entity in previous {
+        bevy::hierarchy::despawn_with_children_recursive(world, entity);
+    }
+
+    let Some(mesh) = world.get::<DrillHoleIntervals>(layer)
+        .and_then(|intervals| DrillHolesMesh::selection_mesh(intervals, indices)) else {
+        return;
+    };
+
+    let mesh = world.resource_mut::<Assets<Mesh>>().add(mesh);
+    let material = world.resource_mut::<Assets<StandardMaterial>>().add(StandardMaterial {
+        base_color: Color::YELLOW,
+        unlit: true,
+        ..Default::default()
+    });
+
+    let selection = world.spawn((PbrBundle {
+        mesh,
+        material,
+        ..Default::default()
+    },
+    Name::new("Scatter selection"),
+    DrillHoleSelection,
+    )).id();
+    world.entity_mut(layer).add_child(selection);
+}
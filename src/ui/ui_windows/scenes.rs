@@ -2,6 +2,9 @@ use bevy::prelude::*;
 use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
 use bevy_egui::egui;
 
+use crate::ui::ui_file_loader::gltf_files::GltfFile;
+use super::hierarchy::{HideInEditor, HierarchyWindow};
+
 const DEFAULT_FILENAME: &str = "scene.scn.ron";
 
 #[derive(Default, Component)]
@@ -11,6 +14,7 @@ pub struct NotInScene;
 pub struct SceneWindowState {
     filename: String,
     scene_save_result: Option<Result<(), Box<dyn std::error::Error + Send + Sync>>>,
+    only_selected: bool,
 }
 
 pub struct SceneWindow;
@@ -21,6 +25,7 @@ impl EditorWindow for SceneWindow {
     const NAME: &'static str = "Scenes";
 
     fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let selected: Vec<Entity> = cx.state::<HierarchyWindow>().unwrap().selected.iter().collect();
         let state = cx.state_mut::<SceneWindow>().unwrap();
 
         ui.horizontal(|ui| {
@@ -47,6 +52,23 @@ impl EditorWindow for SceneWindow {
             }
         });
 
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut state.only_selected, "Only selected");
+
+            if ui.button("Export glTF").clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("glTF binary (glb)", &["glb"]).save_file() {
+                    let roots = if state.only_selected {
+                        selected_roots(world, &selected)
+                    } else {
+                        let mut query = world.query_filtered::<Entity, (Without<Parent>, Without<NotInScene>, Without<HideInEditor>)>();
+                        query.iter(world).collect()
+                    };
+                    let gltf = GltfFile { path: path.with_extension("glb").display().to_string() };
+                    state.scene_save_result = Some(gltf.write_scene(world, &roots));
+                }
+            }
+        });
+
         if let Some(status) = &state.scene_save_result {
             match status {
                 Ok(()) => {
@@ -75,3 +97,21 @@ fn save_world(
     std::fs::write(name, ron)?;
     Ok(())
 }
+
+/// Selected entities that don't have a selected ancestor, so every subtree is exported once.
+fn selected_roots(world: &World, selected: &[Entity]) -> Vec<Entity> {
+    selected
+        .iter()
+        .copied()
+        .filter(|entity| {
+            let mut current = *entity;
+            while let Some(parent) = world.get::<Parent>(current) {
+                current = parent.get();
+                if selected.contains(&current) {
+                    return false;
+                }
+            }
+            true
+        })
+        .collect()
+}
@@ -0,0 +1,393 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::plot::{Legend, Line, Plot, PlotPoints, Polygon};
+use egui::RichText;
+
+use crate::custom_meshes::block_model_mesh::{BlockModel, BlockModelDisplay};
+use crate::custom_meshes::drill_holes_mesh::{DrillHoleIntervals, DrillHoleLayer};
+use crate::custom_meshes::topography_mesh::TopographyMesh;
+use crate::project::Project;
+use crate::utilities::math::analytic_geometry;
+use crate::utilities::math::section::Section;
+
+use super::export::entity_surface;
+use super::nodes_creator::spawn_mesh_node;
+
+/// Points sampled along the section line for the topography profiles
+const PROFILE_SAMPLES: usize = 500;
+
+/// Marks the semi-transparent plane showing the section in the viewport.
+#[derive(Component)]
+pub struct SectionPlane;
+
+/// Geometry of the section, projected when the section or its sources change.
+#[derive(Default)]
+struct SectionContents{
+    /// Drill hole traces in plan, one line per hole
+    traces: Vec<Vec<[f64;2]>>,
+    /// Clipped intervals as (distance, elevation) ends and color
+    intervals: Vec<([[f64;2];2], egui::Color32)>,
+    /// Block outlines on the section and color
+    blocks: Vec<([[f64;2];4], egui::Color32)>,
+    /// Topography name and its profile, split where the line leaves the surface
+    profiles: Vec<(String, Vec<Vec<[f64;2]>>)>,
+    /// Elevation range of everything on the section
+    elevations: Option<[f64;2]>,
+}
+
+pub struct SectionWindowState{
+    section: Section,
+    drill_holes: Option<Entity>,
+    block_model: Option<Entity>,
+    show_topography: bool,
+    show_plane: bool,
+    /// Plan coordinates where the section line being drawn started
+    drag_start: Option<[f64;2]>,
+    /// Section and sources the contents were projected for
+    projected: Option<(Section, Option<Entity>, Option<Entity>, bool)>,
+    contents: SectionContents,
+}
+
+impl Default for SectionWindowState {
+    fn default() -> Self {
+        Self {
+            section: Section::default(),
+            drill_holes: None,
+            block_model: None,
+            show_topography: true,
+            show_plane: true,
+            drag_start: None,
+            projected: None,
+            contents: SectionContents::default(),
+        }
+    }
+}
+
+pub struct SectionWindow;
+
+impl EditorWindow for SectionWindow {
+    type State = SectionWindowState;
+    const NAME: &'static str = "Section";
+    const DEFAULT_SIZE: (f32, f32) = (900.0, 800.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let state = cx.state_mut::<SectionWindow>().unwrap();
+
+        let drill_holes: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), (With<DrillHoleIntervals>, With<DrillHoleLayer>)>()
+            .iter(world)
+            .map(|(entity, name)| (entity, name.to_string()))
+            .collect();
+        let block_models: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), With<BlockModel>>()
+            .iter(world)
+            .map(|(entity, name)| (entity, name.to_string()))
+            .collect();
+        let topographies: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, &Name), With<TopographyMesh>>()
+            .iter(world)
+            .map(|(entity, name)| (entity, name.to_string()))
+            .collect();
+
+        if matches!(state.drill_holes, Some(entity) if !drill_holes.iter().any(|(e, _)| *e == entity)) {
+            state.drill_holes = None;
+        }
+        if matches!(state.block_model, Some(entity) if !block_models.iter().any(|(e, _)| *e == entity)) {
+            state.block_model = None;
+        }
+
+        ui.horizontal(|ui|{
+            entity_combo(ui, "Drill holes", &mut state.drill_holes, &drill_holes);
+            entity_combo(ui, "Block model", &mut state.block_model, &block_models);
+            ui.checkbox(&mut state.show_topography, "Topography");
+        });
+
+        egui::Grid::new("section line").show(ui, |ui|{
+            ui.label("Start");
+            ui.add(egui::DragValue::new(&mut state.section.start[0]).prefix("X: "));
+            ui.add(egui::DragValue::new(&mut state.section.start[1]).prefix("Y: "));
+            ui.end_row();
+
+            ui.label("End");
+            ui.add(egui::DragValue::new(&mut state.section.end[0]).prefix("X: "));
+            ui.add(egui::DragValue::new(&mut state.section.end[1]).prefix("Y: "));
+            ui.end_row();
+
+            ui.label("Width");
+            ui.add(egui::DragValue::new(&mut state.section.width).clamp_range(0.1..=f64::MAX));
+            if ui.button("Fit to drill holes").clicked() {
+                if let Some(section) = state.drill_holes.and_then(|e| fit_section(world, e, state.section.width)) {
+                    state.section = section;
+                }
+            }
+            ui.end_row();
+        });
+        ui.horizontal(|ui|{
+            ui.label(format!("Azimuth {:.1}\u{B0}, length {:.1}", state.section.azimuth(), state.section.length()));
+            if ui.button("Refresh").on_hover_text("Projects the sources again after they changed").clicked() {
+                state.projected = None;
+            }
+        });
+
+        let key = (state.section, state.drill_holes, state.block_model, state.show_topography);
+        if state.projected != Some(key) {
+            state.projected = Some(key);
+            state.contents = project_contents(world, state, &topographies);
+            if state.show_plane {
+                show_plane(world, &state.section, state.contents.elevations);
+            }
+        }
+        if ui.checkbox(&mut state.show_plane, "Show in viewport").changed() {
+            show_plane(world, &state.section, state.contents.elevations.filter(|_| state.show_plane));
+        }
+
+        ui.separator();
+        egui::CollapsingHeader::new("Plan")
+            .default_open(true)
+            .show(ui, |ui|{
+                plan_plot(ui, state);
+                ui.label(RichText::new("Shift + drag to draw the section line").weak());
+            });
+
+        ui.separator();
+        section_plot(ui, &state.contents);
+    }
+}
+
+fn entity_combo(ui: &mut egui::Ui, label: &str, selected: &mut Option<Entity>, entities: &[(Entity, String)]) {
+    let text = entities.iter()
+        .find(|(entity, _)| Some(*entity) == *selected)
+        .map(|(_, name)| name.clone())
+        .unwrap_or_else(|| "None".to_string());
+    egui::ComboBox::from_label(label)
+        .selected_text(text)
+        .show_ui(ui, |ui|{
+            ui.selectable_value(selected, None, "None");
+            for (entity, name) in entities.iter() {
+                ui.selectable_value(selected, Some(*entity), name);
+            }
+        });
+}
+
+/// East–west line through the middle of the drill holes, as long as they are wide.
+fn fit_section(world: &World, entity: Entity, width: f64) -> Option<Section> {
+    let intervals = world.get::<DrillHoleIntervals>(entity)?;
+    let points: Vec<[f64;3]> = intervals.intervals.iter()
+        .flat_map(|interval| [interval.start, interval.end])
+        .map(|point| analytic_geometry::scene_to_world(point, intervals.offset))
+        .collect();
+    if points.is_empty() {
+        return None;
+    }
+    let (min, max) = points.iter().fold(([f64::MAX; 2], [f64::MIN; 2]), |(min, max), p| {
+        ([min[0].min(p[0]), min[1].min(p[1])], [max[0].max(p[0]), max[1].max(p[1])])
+    });
+    let y = (min[1] + max[1]) * 0.5;
+    Some(Section { start: [min[0], y], end: [max[0].max(min[0] + 1.0), y], width })
+}
+
+fn color32(color: [f32;4]) -> egui::Color32 {
+    egui::Rgba::from_rgb(color[0], color[1], color[2]).into()
+}
+
+fn project_contents(world: &World, state: &SectionWindowState, topographies: &[(Entity, String)]) -> SectionContents {
+    let section = &state.section;
+    let mut contents = SectionContents::default();
+
+    if let Some(entity) = state.drill_holes {
+        let intervals = world.get::<DrillHoleIntervals>(entity).unwrap();
+        let colors = world.get::<DrillHoleLayer>(entity)
+            .and_then(|layer| intervals.colors(&layer.variable))
+            .unwrap_or_else(|| vec![[0.8, 0.8, 0.8, 1.0]; intervals.intervals.len()]);
+
+        let mut hole_id = None;
+        for (interval, color) in intervals.intervals.iter().zip(colors) {
+            let start = analytic_geometry::scene_to_world(interval.start, intervals.offset);
+            let end = analytic_geometry::scene_to_world(interval.end, intervals.offset);
+
+            if hole_id != Some(&interval.hole_id) {
+                hole_id = Some(&interval.hole_id);
+                contents.traces.push(vec![[start[0], start[1]]]);
+            }
+            contents.traces.last_mut().unwrap().push([end[0], end[1]]);
+
+            if let Some(ends) = section.clip_segment(start, end) {
+                contents.intervals.push((ends, color32(color)));
+            }
+        }
+    }
+
+    if let Some(entity) = state.block_model {
+        let block_model = world.get::<BlockModel>(entity).unwrap();
+        let display = world.get::<BlockModelDisplay>(entity).cloned().unwrap_or_default();
+
+        // Model axes in plan, the block footprint on the section is the sum of their projections
+        let (sin, cos) = block_model.bearing.to_radians().sin_cos();
+        let axis = section.point(1.0);
+        let axis = [axis[0] - section.start[0], axis[1] - section.start[1]];
+        let along_x = (cos * axis[0] - sin * axis[1]).abs();
+        let along_y = (sin * axis[0] + cos * axis[1]).abs();
+
+        for (block, color) in block_model.blocks.iter().zip(block_model.colors(&display)) {
+            let Some(color) = color else { continue };
+            if !section.contains(block.center) {
+                continue;
+            }
+            let [along, _, z] = section.project(block.center);
+            let half_length = (block.size[0] * along_x + block.size[1] * along_y) * 0.5;
+            let half_height = block.size[2] * 0.5;
+            contents.blocks.push((
+                [
+                    [along - half_length, z - half_height],
+                    [along + half_length, z - half_height],
+                    [along + half_length, z + half_height],
+                    [along - half_length, z + half_height],
+                ],
+                color32(color),
+            ));
+        }
+    }
+
+    if state.show_topography {
+        for (entity, name) in topographies.iter() {
+            let Ok(surface) = entity_surface(world, *entity) else { continue };
+            let mut profile: Vec<Vec<[f64;2]>> = vec![Vec::new()];
+            for i in 0..=PROFILE_SAMPLES {
+                let distance = section.length() * i as f64 / PROFILE_SAMPLES as f64;
+                let [x, y] = section.point(distance);
+                match surface.elevation(x, y) {
+                    Some(z) => profile.last_mut().unwrap().push([distance, z]),
+                    None if !profile.last().unwrap().is_empty() => profile.push(Vec::new()),
+                    None => {}
+                }
+            }
+            profile.retain(|part| part.len() > 1);
+            contents.profiles.push((name.clone(), profile));
+        }
+    }
+
+    let elevations = contents.intervals.iter().flat_map(|(ends, _)| ends.iter().map(|p| p[1]))
+        .chain(contents.blocks.iter().flat_map(|(corners, _)| corners.iter().map(|p| p[1])))
+        .chain(contents.profiles.iter().flat_map(|(_, parts)| parts.iter().flatten().map(|p| p[1])));
+    contents.elevations = elevations.fold(None, |range: Option<[f64;2]>, z| {
+        Some(range.map_or([z, z], |[min, max]| [min.min(z), max.max(z)]))
+    });
+
+    contents
+}
+
+/// Drill hole traces in plan with the section line and its slab. Holding shift and dragging
+/// draws a new section line.
+fn plan_plot(ui: &mut egui::Ui, state: &mut SectionWindowState) {
+    let shift = ui.input(|i| i.modifiers.shift);
+    let section = state.section;
+
+    Plot::new("section plan")
+        .height(250.0)
+        .data_aspect(1.0)
+        .allow_drag(!shift)
+        .label_formatter(|_, value| format!("X: {:.1}\nY: {:.1}", value.x, value.y))
+        .show(ui, |plot_ui|{
+            for trace in state.contents.traces.iter() {
+                plot_ui.line(Line::new(PlotPoints::from(trace.clone())).color(egui::Color32::GRAY));
+            }
+
+            let half_width = section.width * 0.5;
+            let [start, end] = [section.start, section.end];
+            let normal = {
+                let axis = section.point(1.0);
+                [-(axis[1] - start[1]) * half_width, (axis[0] - start[0]) * half_width]
+            };
+            let slab: PlotPoints = vec![
+                [start[0] + normal[0], start[1] + normal[1]],
+                [end[0] + normal[0], end[1] + normal[1]],
+                [end[0] - normal[0], end[1] - normal[1]],
+                [start[0] - normal[0], start[1] - normal[1]],
+            ].into();
+            plot_ui.polygon(Polygon::new(slab).color(egui::Color32::LIGHT_BLUE).fill_alpha(0.1));
+            plot_ui.line(Line::new(PlotPoints::from(vec![start, end])).color(egui::Color32::LIGHT_BLUE).width(2.0));
+
+            let (pressed, released) = plot_ui.ctx().input(|i| (i.pointer.primary_pressed(), i.pointer.primary_released()));
+            let pointer = plot_ui.pointer_coordinate().map(|p| [p.x, p.y]);
+
+            if shift && pressed && plot_ui.plot_hovered() {
+                state.drag_start = pointer;
+            }
+            if let Some(start) = state.drag_start {
+                if let Some(end) = pointer {
+                    plot_ui.line(Line::new(PlotPoints::from(vec![start, end])).color(egui::Color32::YELLOW));
+                    if released && start != end {
+                        state.section.start = start;
+                        state.section.end = end;
+                    }
+                }
+                if released {
+                    state.drag_start = None;
+                }
+            }
+        });
+}
+
+fn section_plot(ui: &mut egui::Ui, contents: &SectionContents) {
+    Plot::new("section view")
+        .data_aspect(1.0)
+        .legend(Legend::default())
+        .label_formatter(|name, value| format!("{}\ndistance: {:.1}\nelevation: {:.1}", name, value.x, value.y))
+        .show(ui, |plot_ui|{
+            for (corners, color) in contents.blocks.iter() {
+                plot_ui.polygon(Polygon::new(PlotPoints::from(corners.to_vec())).color(*color).fill_alpha(0.6));
+            }
+            for (name, parts) in contents.profiles.iter() {
+                for part in parts.iter() {
+                    plot_ui.line(Line::new(PlotPoints::from(part.clone())).color(egui::Color32::BROWN).name(name));
+                }
+            }
+            for (ends, color) in contents.intervals.iter() {
+                plot_ui.line(Line::new(PlotPoints::from(ends.to_vec())).color(*color).width(4.0));
+            }
+        });
+}
+
+/// Replaces the plane showing the section in the viewport, spanning `elevations`. The plane is
+/// only removed when there is nothing to span or no data was loaded yet.
+fn show_plane(world: &mut World, section: &Section, elevations: Option<[f64;2]>) {
+    let previous: Vec<Entity> = world
+        .query_filtered::<Entity, With<SectionPlane>>()
+        .iter(world)
+        .collect();
+    for entity in previous {
+        bevy::hierarchy::despawn_with_children_recursive(world, entity);
+    }
+
+    let (Some([bottom, top]), Some(offset)) = (elevations, world.resource::<Project>().offset) else { return };
+    let corners = [
+        [section.start[0], section.start[1], bottom],
+        [section.end[0], section.end[1], bottom],
+        [section.end[0], section.end[1], top],
+        [section.start[0], section.start[1], top],
+    ];
+    let positions: Vec<[f32;3]> = corners.iter()
+        .map(|corner| analytic_geometry::world_to_scene(*corner, offset).to_array())
+        .collect();
+    let normal = {
+        let [a, b, c] = [positions[0], positions[1], positions[3]].map(Vec3::from);
+        (b - a).cross(c - a).normalize_or_zero().to_array()
+    };
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![normal; 4]);
+    mesh.set_indices(Some(Indices::U32(vec![0, 1, 2, 0, 2, 3])));
+
+    spawn_mesh_node(world, mesh, offset, StandardMaterial {
+        base_color: Color::rgba(0.5, 0.8, 1.0, 0.25),
+        alpha_mode: AlphaMode::Blend,
+        double_sided: true,
+        cull_mode: None,
+        unlit: true,
+        ..Default::default()
+    }, (Name::new("Section plane"), SectionPlane));
+}
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::plot::{Bar, BarChart, Plot, Points, VLine};
+use egui::RichText;
+
+use crate::custom_meshes::block_model_mesh::BlockModel;
+use crate::custom_meshes::drill_holes_mesh::DrillHoleIntervals;
+use crate::utilities::math::statistics::{self, SUMMARY_PERCENTILES};
+
+use super::hierarchy::HierarchyWindow;
+
+pub struct StatisticsWindowState{
+    variable: Option<String>,
+    length_weighted: bool,
+    rock: Option<String>,
+    bins: usize,
+}
+
+impl Default for StatisticsWindowState {
+    fn default() -> Self {
+        Self { variable: None, length_weighted: true, rock: None, bins: 30 }
+    }
+}
+
+pub struct StatisticsWindow;
+
+impl EditorWindow for StatisticsWindow {
+    type State = StatisticsWindowState;
+    const NAME: &'static str = "Statistics";
+    const DEFAULT_SIZE: (f32, f32) = (700.0, 700.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let selected: Vec<Entity> = cx.state::<HierarchyWindow>().unwrap().selected.iter().collect();
+        let state = cx.state_mut::<StatisticsWindow>().unwrap();
+
+        let (variables, rocks) = match selected.as_slice() {
+            [entity] => match (world.get::<DrillHoleIntervals>(*entity), world.get::<BlockModel>(*entity)) {
+                (Some(intervals), _) => (intervals.variables.clone(), intervals.rocks()),
+                (None, Some(block_model)) => (block_model.attributes.clone(), Vec::new()),
+                (None, None) => (Vec::new(), Vec::new()),
+            },
+            _ => (Vec::new(), Vec::new()),
+        };
+        if variables.is_empty() {
+            ui.label("Select a drill holes layer or a block model in the hierarchy");
+            return;
+        }
+        let entity = selected[0];
+
+        if !matches!(&state.variable, Some(variable) if variables.contains(variable)) {
+            state.variable = variables.first().cloned();
+        }
+        if matches!(&state.rock, Some(rock) if !rocks.contains(rock)) {
+            state.rock = None;
+        }
+
+        ui.horizontal(|ui|{
+            egui::ComboBox::from_label("Variable")
+                .selected_text(state.variable.clone().unwrap_or_default())
+                .show_ui(ui, |ui|{
+                    for variable in variables.iter() {
+                        ui.selectable_value(&mut state.variable, Some(variable.clone()), variable);
+                    }
+                });
+
+            if !rocks.is_empty() {
+                egui::ComboBox::from_label("Lithology")
+                    .selected_text(state.rock.clone().unwrap_or_else(|| "All".to_string()))
+                    .show_ui(ui, |ui|{
+                        ui.selectable_value(&mut state.rock, None, "All");
+                        for rock in rocks.iter() {
+                            ui.selectable_value(&mut state.rock, Some(rock.clone()), rock);
+                        }
+                    });
+            }
+
+            ui.checkbox(&mut state.length_weighted, "Length weighted")
+                .on_hover_text("Weights the intervals by length and the blocks by volume");
+        });
+
+        let variable = state.variable.clone().unwrap_or_default();
+        let sorted = weighted_values(world, entity, &variable, state.length_weighted, state.rock.as_deref());
+
+        let Some(summary) = statistics::summary(&sorted) else {
+            ui.label(RichText::new("The variable has no values").color(egui::Color32::YELLOW));
+            return;
+        };
+
+        ui.separator();
+        egui::Grid::new("statistics summary").striped(true).show(ui, |ui|{
+            for (name, value) in [
+                ("Count", summary.count as f64),
+                ("Min", summary.min),
+                ("Max", summary.max),
+                ("Mean", summary.mean),
+                ("Variance", summary.variance),
+                ("Std. deviation", summary.variance.sqrt()),
+                ("CV", summary.cv),
+            ] {
+                ui.label(name);
+                ui.label(format!("{:.4}", value));
+                ui.end_row();
+            }
+            for (q, value) in SUMMARY_PERCENTILES.iter().zip(summary.percentiles.iter()) {
+                ui.label(format!("P{:.0}", q * 100.0));
+                ui.label(format!("{:.4}", value));
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        ui.add(egui::Slider::new(&mut state.bins, 5..=100).text("Bins"));
+        ui.columns(2, |columns|{
+            histogram_plot(&mut columns[0], &sorted, state.bins);
+            log_probability_plot(&mut columns[1], "statistics log probability", &sorted, &[]);
+        });
+    }
+}
+
+/// Sorted (value, weight) pairs of `variable` in a drill holes layer or block model. Drill hole
+/// intervals are weighted by length and blocks by volume when `weighted`, `rock` keeps only the
+/// intervals of one lithology.
+pub fn weighted_values(world: &World, entity: Entity, variable: &str, weighted: bool, rock: Option<&str>) -> Vec<(f64, f64)> {
+    if let Some(intervals) = world.get::<DrillHoleIntervals>(entity) {
+        let Some(index) = intervals.variable_index(variable) else { return Vec::new() };
+        return statistics::sorted_weighted(intervals.intervals.iter()
+            .filter(|interval| rock.is_none() || interval.rock.as_deref() == rock)
+            .map(|interval| {
+                let weight = if weighted { (interval.to - interval.from) as f64 } else { 1.0 };
+                (interval.values[index], weight)
+            }));
+    }
+
+    if let Some(block_model) = world.get::<BlockModel>(entity) {
+        let Some(index) = block_model.attribute_index(variable) else { return Vec::new() };
+        return statistics::sorted_weighted(block_model.blocks.iter().map(|block| {
+            let weight = if weighted { block.size.iter().product() } else { 1.0 };
+            (block.values[index], weight)
+        }));
+    }
+
+    Vec::new()
+}
+
+fn histogram_plot(ui: &mut egui::Ui, sorted: &[(f64, f64)], bins: usize) {
+    let Some((width, frequencies)) = statistics::histogram(sorted, bins) else { return };
+    let min = sorted[0].0;
+
+    let bars: Vec<Bar> = frequencies.iter()
+        .enumerate()
+        .map(|(i, frequency)| Bar::new(min + (i as f64 + 0.5) * width, *frequency).width(width))
+        .collect();
+
+    Plot::new("statistics histogram")
+        .height(250.0)
+        .include_y(0.0)
+        .label_formatter(|_, value| format!("value: {:.3}\nfrequency: {:.2}%", value.x, value.y))
+        .show(ui, |plot_ui|{
+            plot_ui.bar_chart(BarChart::new(bars).name("Frequency %"));
+        });
+}
+
+/// Cumulative probability against the logarithm of the positive values, with the normal
+/// quantile on the vertical axis so a lognormal population plots as a straight line. `markers`
+/// are drawn as vertical lines, such as capping values.
+pub fn log_probability_plot(ui: &mut egui::Ui, id: &str, sorted: &[(f64, f64)], markers: &[f64]) {
+    let positive: Vec<(f64, f64)> = sorted.iter().copied().filter(|(value, _)| *value > 0.0).collect();
+    let probabilities = statistics::cumulative_probabilities(&positive);
+
+    let points: Vec<[f64;2]> = positive.iter()
+        .zip(probabilities.iter())
+        .map(|((value, _), probability)| [value.log10(), statistics::normal_quantile(*probability)])
+        .collect();
+
+    Plot::new(id)
+        .height(250.0)
+        .x_axis_formatter(|x, _| format!("{:.3}", 10f64.powf(x)))
+        .y_axis_formatter(|z, _| format!("{:.1}%", statistics::normal_cdf(z) * 100.0))
+        .label_formatter(|_, value| {
+            format!("value: {:.3}\nprobability: {:.2}%", 10f64.powf(value.x), statistics::normal_cdf(value.y) * 100.0)
+        })
+        .show(ui, |plot_ui|{
+            plot_ui.points(Points::new(points).radius(2.0).name("Cumulative probability"));
+            for marker in markers.iter().filter(|m| **m > 0.0) {
+                plot_ui.vline(VLine::new(marker.log10()).color(egui::Color32::RED));
+            }
+        });
+}
@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use crate::ui::ui_core::editor_window::{EditorWindow, EditorWindowContext, MenuBarWindow};
+use bevy_egui::egui;
+use egui::plot::{Legend, Line, Plot, PlotPoints, Points};
+use egui::RichText;
+
+use crate::custom_meshes::drill_holes_mesh::DrillHoleIntervals;
+use crate::project::Variograms;
+use crate::utilities::math::analytic_geometry;
+use crate::utilities::math::variogram::{self, ExperimentalPoint, VariogramDirection, VariogramModel};
+
+use super::estimation::variogram_ui;
+use super::hierarchy::HierarchyWindow;
+
+/// Points used to draw the model curve.
+const MODEL_CURVE_POINTS: usize = 200;
+
+#[derive(Default)]
+pub struct VariographyWindowState{
+    variable: Option<String>,
+    direction: VariogramDirection,
+    experimental: Vec<ExperimentalPoint>,
+    model: VariogramModel,
+    variance: Option<f64>,
+    saved: bool,
+}
+
+pub struct VariographyWindow;
+
+impl EditorWindow for VariographyWindow {
+    type State = VariographyWindowState;
+    const NAME: &'static str = "Variography";
+    const DEFAULT_SIZE: (f32, f32) = (700.0, 600.0);
+    const MENU_BAR : MenuBarWindow = MenuBarWindow::Edit;
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let selected: Vec<Entity> = cx.state::<HierarchyWindow>().unwrap().selected.iter().collect();
+        let state = cx.state_mut::<VariographyWindow>().unwrap();
+
+        let entity = match selected.as_slice() {
+            [entity] if world.get::<DrillHoleIntervals>(*entity).is_some() => *entity,
+            _ => {
+                ui.label("Select a drill holes layer in the hierarchy");
+                return;
+            }
+        };
+        let intervals = world.get::<DrillHoleIntervals>(entity).unwrap();
+
+        if state.variable.as_ref().and_then(|v| intervals.variable_index(v)).is_none() {
+            state.variable = intervals.variables.first().cloned();
+            state.experimental.clear();
+            state.variance = None;
+        }
+
+        let previous_variable = state.variable.clone();
+        egui::ComboBox::from_label("Variable")
+            .selected_text(state.variable.clone().unwrap_or_default())
+            .show_ui(ui, |ui|{
+                for variable in intervals.variables.iter() {
+                    ui.selectable_value(&mut state.variable, Some(variable.clone()), variable);
+                }
+            });
+        if state.variable != previous_variable {
+            state.experimental.clear();
+            state.variance = None;
+            state.saved = false;
+            if let Some(model) = state.variable.as_ref().and_then(|v| world.get::<Variograms>(entity)?.0.get(v)) {
+                state.model = model.clone();
+            }
+        }
+
+        egui::CollapsingHeader::new("Direction")
+            .default_open(true)
+            .show(ui, |ui|{
+                direction_ui(ui, &mut state.direction);
+                if ui.button("Compute").clicked() {
+                    let samples = state.variable.as_ref().and_then(|v| intervals.samples(v)).unwrap_or_default();
+                    state.experimental = variogram::experimental_variogram(&samples, &state.direction);
+
+                    let values: Vec<f64> = samples.iter().map(|s| s.value).collect();
+                    let mean = values.iter().sum::<f64>() / values.len().max(1) as f64;
+                    state.variance = (values.len() > 1).then(|| {
+                        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64
+                    });
+                }
+            });
+
+        egui::CollapsingHeader::new("Model")
+            .default_open(true)
+            .show(ui, |ui|{
+                if variogram_ui_changed(ui, &mut state.model) {
+                    state.saved = false;
+                }
+                ui.horizontal(|ui|{
+                    if ui.add_enabled(state.variable.is_some(), egui::Button::new("Save to project")).clicked() {
+                        let variable = state.variable.clone().unwrap();
+                        let mut variograms = world.get::<Variograms>(entity).cloned().unwrap_or_default();
+                        variograms.0.insert(variable, state.model.clone());
+                        world.entity_mut(entity).insert(variograms);
+                        state.saved = true;
+                    }
+                    if state.saved {
+                        ui.label(RichText::new("Saved").color(egui::Color32::GREEN));
+                    }
+                });
+            });
+
+        plot_ui(ui, state);
+    }
+}
+
+fn direction_ui(ui: &mut egui::Ui, direction: &mut VariogramDirection) {
+    egui::Grid::new("variogram direction").show(ui, |ui|{
+        ui.label("Azimuth / dip");
+        ui.add(egui::DragValue::new(&mut direction.azimuth).clamp_range(-360.0..=360.0).suffix("\u{B0}"));
+        ui.add(egui::DragValue::new(&mut direction.dip).clamp_range(-90.0..=90.0).suffix("\u{B0}"));
+        ui.end_row();
+
+        ui.label("Angular tolerance");
+        ui.add(egui::DragValue::new(&mut direction.tolerance).clamp_range(0.0..=90.0).suffix("\u{B0}"));
+        ui.end_row();
+
+        ui.label("Bandwidth");
+        ui.add(egui::DragValue::new(&mut direction.bandwidth).clamp_range(0.0..=f64::MAX));
+        ui.end_row();
+
+        ui.label("Lag / lags");
+        ui.add(egui::DragValue::new(&mut direction.lag).clamp_range(0.01..=f64::MAX).speed(0.1));
+        ui.add(egui::DragValue::new(&mut direction.lags).clamp_range(1..=200));
+        ui.end_row();
+    });
+}
+
+/// [`variogram_ui`] reporting whether the model was edited.
+fn variogram_ui_changed(ui: &mut egui::Ui, model: &mut VariogramModel) -> bool {
+    let before = model.clone();
+    variogram_ui(ui, model);
+    before != *model
+}
+
+fn plot_ui(ui: &mut egui::Ui, state: &VariographyWindowState) {
+    let max_distance = state.direction.lag * state.direction.lags as f64;
+    let axis = analytic_geometry::direction(state.direction.azimuth, state.direction.dip);
+    let compiled = state.model.compile();
+
+    let model: PlotPoints = (0..=MODEL_CURVE_POINTS)
+        .map(|i| {
+            let distance = max_distance * i as f64 / MODEL_CURVE_POINTS as f64;
+            [distance, compiled.gamma(axis.map(|c| c * distance))]
+        })
+        .collect();
+    let experimental: PlotPoints = state.experimental.iter().map(|p| [p.distance, p.gamma]).collect();
+
+    Plot::new("variogram plot")
+        .height(300.0)
+        .legend(Legend::default())
+        .include_x(0.0)
+        .include_y(0.0)
+        .label_formatter(|name, value| {
+            if name.is_empty() {
+                format!("h: {:.2}\n\u{3B3}: {:.3}", value.x, value.y)
+            } else {
+                format!("{}\nh: {:.2}\n\u{3B3}: {:.3}", name, value.x, value.y)
+            }
+        })
+        .show(ui, |plot_ui|{
+            plot_ui.points(Points::new(experimental).radius(4.0).name("Experimental"));
+            plot_ui.line(Line::new(model).name("Model"));
+            if let Some(variance) = state.variance {
+                plot_ui.hline(egui::plot::HLine::new(variance).name("Variance"));
+            }
+        });
+
+    if !state.experimental.is_empty() {
+        let pairs: usize = state.experimental.iter().map(|p| p.pairs).sum();
+        ui.label(format!("{} pairs in {} lags", pairs, state.experimental.len()));
+    }
+}
@@ -0,0 +1,18 @@
+//! Small egui widgets shared by the windows.
+
+use bevy::prelude::*;
+use bevy_egui::egui;
+
+/// Combo box picking one of the named `entities`, e.g. the surfaces or drill holes a tool works on.
+pub fn entity_combo(ui: &mut egui::Ui, id: &str, selected: &mut Option<Entity>, entities: &[(Entity, String)]) {
+    let text = entities.iter()
+        .find(|(entity, _)| Some(*entity) == *selected)
+        .map(|(_, name)| name.clone());
+    egui::ComboBox::from_id_source(id)
+        .selected_text(text.unwrap_or_default())
+        .show_ui(ui, |ui|{
+            for (entity, name) in entities {
+                ui.selectable_value(selected, Some(*entity), name);
+            }
+        });
+}
@@ -28,5 +28,132 @@ pub fn interpolate_point_on_the_line(
         origin[2] - delta_z,
     ];
 
-    Vec3::new(point_1[0], point_1[2], point_1[1])
+    world_to_scene(point_1.map(f64::from), [0.0; 3])
+}
+
+/// Restores world coordinates (x, y, elevation) from a scene position, where the east is on the
+/// X axis, the elevation on the Y axis, the north on the -Z axis and `offset` was subtracted when
+/// the mesh was built. A rotation of the world axes, so the triangles keep their winding.
+pub fn scene_to_world(point: Vec3, offset: [f64;3]) -> [f64;3] {
+    [
+        point.x as f64 + offset[0],
+        -point.z as f64 + offset[1],
+        point.y as f64 + offset[2],
+    ]
+}
+
+/// Inverse of [`scene_to_world`].
+pub fn world_to_scene(point: [f64;3], offset: [f64;3]) -> Vec3 {
+    Vec3::new(
+        (point[0] - offset[0]) as f32,
+        (point[2] - offset[2]) as f32,
+        (offset[1] - point[1]) as f32,
+    )
+}
+
+/// Unit vector in world coordinates (x, y, elevation) with `azimuth` clockwise from north and
+/// `dip` in degrees, negative downwards as in the drill hole surveys.
+pub fn direction(azimuth: f64, dip: f64) -> [f64;3] {
+    let (azimuth_sin, azimuth_cos) = azimuth.to_radians().sin_cos();
+    let (dip_sin, dip_cos) = dip.to_radians().sin_cos();
+    [azimuth_sin * dip_cos, azimuth_cos * dip_cos, dip_sin]
+}
+
+/// Azimuth clockwise from north and dip in degrees, negative downwards, of the direction from
+/// `a` to `b`. Inverse of [`direction`].
+pub fn azimuth_dip(a: [f64;3], b: [f64;3]) -> (f64, f64) {
+    let [dx, dy, dz] = sub(b, a);
+    let azimuth = dx.atan2(dy).to_degrees().rem_euclid(360.0);
+    let dip = dz.atan2(dx.hypot(dy)).to_degrees();
+    (azimuth, dip)
+}
+
+/// Area in plan of the polygon going through `points` and back to the first one.
+pub fn plan_area(points: &[[f64;3]]) -> f64 {
+    let Some(first) = points.first() else { return 0.0 };
+    // Shoelace formula relative to the first point, keeping the products small
+    let twice_area: f64 = points.windows(2)
+        .map(|pair| {
+            let (a, b) = (sub(pair[0], *first), sub(pair[1], *first));
+            a[0] * b[1] - b[0] * a[1]
+        })
+        .sum();
+    twice_area.abs() * 0.5
+}
+
+/// Whether `point` is inside `polygon` in plan, by the even-odd rule.
+pub fn point_in_polygon(point: [f64;2], polygon: &[[f64;2]]) -> bool {
+    let mut inside = false;
+    let mut previous = match polygon.last() {
+        Some(last) => *last,
+        None => return false,
+    };
+    for vertex in polygon {
+        if (vertex[1] > point[1]) != (previous[1] > point[1]) {
+            let crossing = vertex[0] + (point[1] - vertex[1]) / (previous[1] - vertex[1]) * (previous[0] - vertex[0]);
+            if point[0] < crossing {
+                inside = !inside;
+            }
+        }
+        previous = *vertex;
+    }
+    inside
+}
+
+pub fn dot(a: [f64;3], b: [f64;3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: [f64;3], b: [f64;3]) -> [f64;3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn sub(a: [f64;3], b: [f64;3]) -> [f64;3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn norm(a: [f64;3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_keeps_the_map_handedness() {
+        let offset = [1000.0, 2000.0, 100.0];
+        // East, north and up, the east crossed with the north giving the up in both spaces
+        assert_eq!(world_to_scene([1001.0, 2000.0, 100.0], offset), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(world_to_scene([1000.0, 2001.0, 100.0], offset), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(world_to_scene([1000.0, 2000.0, 101.0], offset), Vec3::new(0.0, 1.0, 0.0));
+        let point = [1012.5, 1987.0, 130.0];
+        assert_eq!(scene_to_world(world_to_scene(point, offset), offset), point);
+    }
+
+    #[test]
+    fn plan_area_either_way_round() {
+        let square = [[0.0, 0.0, 1.0], [10.0, 0.0, 2.0], [10.0, 10.0, 3.0], [0.0, 10.0, 4.0]];
+        assert!((plan_area(&square) - 100.0).abs() < 1e-9);
+        let mut clockwise = square;
+        clockwise.reverse();
+        assert!((plan_area(&clockwise) - 100.0).abs() < 1e-9);
+        assert_eq!(plan_area(&square[..2]), 0.0);
+    }
+
+    #[test]
+    fn point_in_a_concave_polygon() {
+        // L shape missing its upper right quarter
+        let polygon = [[0.0, 0.0], [10.0, 0.0], [10.0, 5.0], [5.0, 5.0], [5.0, 10.0], [0.0, 10.0]];
+        assert!(point_in_polygon([2.0, 2.0], &polygon));
+        assert!(point_in_polygon([8.0, 2.0], &polygon));
+        assert!(point_in_polygon([2.0, 8.0], &polygon));
+        assert!(!point_in_polygon([8.0, 8.0], &polygon));
+        assert!(!point_in_polygon([-1.0, 2.0], &polygon));
+        assert!(!point_in_polygon([2.0, 2.0], &[]));
+    }
 }
@@ -0,0 +1,84 @@
+use std::f64::consts::TAU;
+
+/// Splits an arc into straight segments. Angles are in degrees, counter-clockwise from +X.
+pub fn tessellate_arc(
+    center: [f64;3],
+    radius: f64,
+    start_angle: f64,
+    end_angle: f64,
+    segments_per_turn: usize,
+) -> Vec<[f64;3]> {
+    let start = start_angle.to_radians();
+    let mut sweep = end_angle.to_radians() - start;
+    if sweep <= 0.0 {
+        sweep += TAU;
+    }
+
+    let segments = ((sweep / TAU) * segments_per_turn as f64).ceil().max(1.0) as usize;
+
+    (0..=segments)
+        .map(|i| {
+            let angle = start + sweep * i as f64 / segments as f64;
+            [
+                center[0] + radius * angle.cos(),
+                center[1] + radius * angle.sin(),
+                center[2],
+            ]
+        })
+        .collect()
+}
+
+/// Evaluates a (rational) b-spline at evenly spaced parameters using de Boor's algorithm.
+/// `weights` may be empty for a non rational spline.
+pub fn tessellate_b_spline(
+    degree: usize,
+    knots: &[f64],
+    control_points: &[[f64;3]],
+    weights: &[f64],
+    segments: usize,
+) -> Vec<[f64;3]> {
+    let n = control_points.len();
+    if degree == 0 || n <= degree || knots.len() != n + degree + 1 {
+        return control_points.to_vec();
+    }
+
+    let homogeneous: Vec<[f64;4]> = control_points.iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let w = if weights.len() == n { weights[i] } else { 1.0 };
+            [p[0] * w, p[1] * w, p[2] * w, w]
+        })
+        .collect();
+
+    let t_start = knots[degree];
+    let t_end = knots[n];
+
+    (0..=segments)
+        .map(|i| {
+            let t = t_start + (t_end - t_start) * i as f64 / segments as f64;
+            let p = de_boor(t, degree, knots, &homogeneous);
+            [p[0] / p[3], p[1] / p[3], p[2] / p[3]]
+        })
+        .collect()
+}
+
+fn de_boor(t: f64, degree: usize, knots: &[f64], points: &[[f64;4]]) -> [f64;4] {
+    let n = points.len();
+    let mut k = degree;
+    while k < n - 1 && knots[k + 1] <= t {
+        k += 1;
+    }
+
+    let mut d: Vec<[f64;4]> = (0..=degree).map(|j| points[j + k - degree]).collect();
+    for r in 1..=degree {
+        for j in (r..=degree).rev() {
+            let left = knots[j + k - degree];
+            let right = knots[j + 1 + k - r];
+            let alpha = if right > left { (t - left) / (right - left) } else { 0.0 };
+            for c in 0..4 {
+                d[j][c] = (1.0 - alpha) * d[j - 1][c] + alpha * d[j][c];
+            }
+        }
+    }
+    d[degree]
+}
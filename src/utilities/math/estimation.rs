@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+
+use bevy::reflect::{FromReflect, Reflect};
+
+use super::analytic_geometry::{self, cross, dot, norm, sub};
+use super::variogram::VariogramModel;
+
+/// Value at a point in world coordinates (x, y, elevation).
+#[derive(Clone, Copy)]
+pub struct Sample{
+    pub position: [f64;3],
+    pub value: f64,
+}
+
+/// Oriented ellipsoid. The major axis points along `azimuth` and `dip` (negative downwards), the
+/// semi-major axis is the horizontal perpendicular rotated by `rake` around the major axis.
+#[derive(Clone, Copy, PartialEq, Reflect, FromReflect)]
+pub struct Anisotropy{
+    pub azimuth: f64,
+    pub dip: f64,
+    pub rake: f64,
+    /// Major, semi-major and minor ranges
+    pub ranges: [f64;3],
+}
+
+impl Default for Anisotropy {
+    fn default() -> Self {
+        Self { azimuth: 0.0, dip: 0.0, rake: 0.0, ranges: [100.0, 100.0, 50.0] }
+    }
+}
+
+/// Axes of an [`Anisotropy`] divided by their ranges, so the ellipsoid becomes the unit sphere.
+pub struct Metric{
+    axes: [[f64;3];3],
+}
+
+impl Metric {
+    /// Components of the separation `h` along the ellipsoid axes, in ranges.
+    pub fn scaled(&self, h: [f64;3]) -> [f64;3] {
+        self.axes.map(|axis| dot(axis, h))
+    }
+}
+
+impl Anisotropy {
+    /// Unit major, semi-major and minor axes in world coordinates.
+    pub fn axes(&self) -> [[f64;3];3] {
+        let major = analytic_geometry::direction(self.azimuth, self.dip);
+        let horizontal = analytic_geometry::direction(self.azimuth + 90.0, 0.0);
+        let normal = cross(major, horizontal);
+
+        let (sin, cos) = self.rake.to_radians().sin_cos();
+        let semi = [0, 1, 2].map(|i| horizontal[i] * cos + normal[i] * sin);
+        let minor = cross(major, semi);
+
+        [major, semi, minor]
+    }
+
+    pub fn metric(&self) -> Metric {
+        let axes = self.axes();
+        Metric {
+            axes: [0, 1, 2].map(|i| axes[i].map(|c| c / self.ranges[i].max(f64::EPSILON))),
+        }
+    }
+
+    pub fn max_range(&self) -> f64 {
+        self.ranges.iter().cloned().fold(f64::EPSILON, f64::max)
+    }
+}
+
+/// Neighbourhood used to pick the samples of each block.
+#[derive(Clone, Copy)]
+pub struct SearchParameters{
+    pub ellipsoid: Anisotropy,
+    pub min_samples: usize,
+    pub max_samples: usize,
+    /// Samples kept from each octant of the ellipsoid, 0 for no limit
+    pub max_per_octant: usize,
+    /// Octants that must hold at least one sample
+    pub min_octants: usize,
+}
+
+impl Default for SearchParameters {
+    fn default() -> Self {
+        Self { ellipsoid: Anisotropy::default(), min_samples: 2, max_samples: 12, max_per_octant: 0, min_octants: 0 }
+    }
+}
+
+/// Regular grid of blocks aligned with the world axes. Blocks are numbered with x varying fastest.
+#[derive(Clone, Copy)]
+pub struct BlockGrid{
+    /// Minimum corner of the first block
+    pub origin: [f64;3],
+    pub block_size: [f64;3],
+    pub count: [usize;3],
+}
+
+impl Default for BlockGrid {
+    fn default() -> Self {
+        Self { origin: [0.0; 3], block_size: [10.0, 10.0, 5.0], count: [1; 3] }
+    }
+}
+
+impl BlockGrid {
+    /// Smallest grid of `block_size` blocks covering every sample.
+    pub fn fit(samples: &[Sample], block_size: [f64;3]) -> Option<Self> {
+        let first = samples.first()?.position;
+        let (min, max) = samples.iter().fold((first, first), |(min, max), s| {
+            (
+                [0, 1, 2].map(|i| min[i].min(s.position[i])),
+                [0, 1, 2].map(|i| max[i].max(s.position[i])),
+            )
+        });
+
+        Some(Self {
+            origin: min,
+            block_size,
+            count: [0, 1, 2].map(|i| ((max[i] - min[i]) / block_size[i]).floor() as usize + 1),
+        })
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.count.iter().product()
+    }
+
+    pub fn center(&self, block: usize) -> [f64;3] {
+        let index = [
+            block % self.count[0],
+            block / self.count[0] % self.count[1],
+            block / (self.count[0] * self.count[1]),
+        ];
+        [0, 1, 2].map(|i| self.origin[i] + (index[i] as f64 + 0.5) * self.block_size[i])
+    }
+}
+
+/// Estimated value of a block and the number of samples it used.
+#[derive(Clone, Copy)]
+pub struct Estimate{
+    pub value: f64,
+    /// Kriging variance, NaN for the estimators without one
+    pub variance: f64,
+    pub samples: usize,
+}
+
+/// Samples bucketed on a grid as large as the search ellipsoid, so a search only visits the
+/// neighbouring cells.
+pub struct SampleIndex<'a>{
+    samples: &'a [Sample],
+    cell: f64,
+    cells: HashMap<[i64;3], Vec<usize>>,
+}
+
+impl<'a> SampleIndex<'a> {
+    pub fn new(samples: &'a [Sample], cell: f64) -> Self {
+        let mut index = Self { samples, cell, cells: HashMap::new() };
+        for (i, sample) in samples.iter().enumerate() {
+            let key = index.key(sample.position);
+            index.cells.entry(key).or_default().push(i);
+        }
+        index
+    }
+
+    fn key(&self, position: [f64;3]) -> [i64;3] {
+        position.map(|c| (c / self.cell).floor() as i64)
+    }
+
+    /// Samples inside the search ellipsoid around `center` that pass the octant rules, nearest
+    /// first, with their distance in ranges. `None` when the minimum samples or octants are not met.
+    pub fn search(&self, center: [f64;3], search: &SearchParameters, metric: &Metric) -> Option<Vec<(usize, f64)>> {
+        let [kx, ky, kz] = self.key(center);
+
+        let mut candidates: Vec<(usize, f64, usize)> = Vec::new();
+        for x in kx - 1..=kx + 1 {
+            for y in ky - 1..=ky + 1 {
+                for z in kz - 1..=kz + 1 {
+                    let Some(cell) = self.cells.get(&[x, y, z]) else { continue };
+                    for &i in cell {
+                        let scaled = metric.scaled(sub(self.samples[i].position, center));
+                        let distance = norm(scaled);
+                        if distance <= 1.0 {
+                            let octant = (scaled[0] >= 0.0) as usize
+                                | ((scaled[1] >= 0.0) as usize) << 1
+                                | ((scaled[2] >= 0.0) as usize) << 2;
+                            candidates.push((i, distance, octant));
+                        }
+                    }
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let mut per_octant = [0usize; 8];
+        let mut selected = Vec::new();
+        for (i, distance, octant) in candidates {
+            if selected.len() >= search.max_samples {
+                break;
+            }
+            if search.max_per_octant > 0 && per_octant[octant] >= search.max_per_octant {
+                continue;
+            }
+            per_octant[octant] += 1;
+            selected.push((i, distance));
+        }
+
+        let octants = per_octant.iter().filter(|count| **count > 0).count();
+        if selected.len() < search.min_samples.max(1) || octants < search.min_octants {
+            return None;
+        }
+        Some(selected)
+    }
+}
+
+/// Inverse distance weighting of every block of `grid`, using the anisotropic distance of the
+/// search ellipsoid. Blocks without enough samples are `None`.
+pub fn inverse_distance(
+    samples: &[Sample],
+    grid: &BlockGrid,
+    search: &SearchParameters,
+    power: f64,
+) -> Vec<Option<Estimate>> {
+    let metric = search.ellipsoid.metric();
+    let index = SampleIndex::new(samples, search.ellipsoid.max_range());
+
+    (0..grid.block_count())
+        .map(|block| {
+            let neighbours = index.search(grid.center(block), search, &metric)?;
+
+            if let Some((i, _)) = neighbours.iter().find(|(_, distance)| *distance < f64::EPSILON) {
+                return Some(Estimate { value: samples[*i].value, variance: f64::NAN, samples: neighbours.len() });
+            }
+
+            let (weights, weighted) = neighbours.iter().fold((0.0, 0.0), |(weights, weighted), (i, distance)| {
+                let weight = distance.powf(-power);
+                (weights + weight, weighted + weight * samples[*i].value)
+            });
+
+            Some(Estimate { value: weighted / weights, variance: f64::NAN, samples: neighbours.len() })
+        })
+        .collect()
+}
+
+/// Ordinary kriging of every block centroid of `grid` with the covariance of `variogram`. Blocks
+/// without enough samples, or whose system is singular, are `None`.
+pub fn ordinary_kriging(
+    samples: &[Sample],
+    grid: &BlockGrid,
+    search: &SearchParameters,
+    variogram: &VariogramModel,
+) -> Vec<Option<Estimate>> {
+    let metric = search.ellipsoid.metric();
+    let index = SampleIndex::new(samples, search.ellipsoid.max_range());
+    let covariance = variogram.compile();
+    let sill = covariance.sill();
+
+    (0..grid.block_count())
+        .map(|block| {
+            let center = grid.center(block);
+            let neighbours = index.search(center, search, &metric)?;
+            let n = neighbours.len();
+
+            // Covariances between the samples bordered by the unbiasedness constraint
+            let mut matrix = vec![0.0; (n + 1) * (n + 1)];
+            let mut rhs = vec![1.0; n + 1];
+            for (row, (i, _)) in neighbours.iter().enumerate() {
+                for (column, (j, _)) in neighbours.iter().enumerate() {
+                    matrix[row * (n + 1) + column] = covariance.covariance(sub(samples[*i].position, samples[*j].position));
+                }
+                matrix[row * (n + 1) + n] = 1.0;
+                matrix[n * (n + 1) + row] = 1.0;
+                rhs[row] = covariance.covariance(sub(samples[*i].position, center));
+            }
+            let point_covariances = rhs.clone();
+
+            let solution = solve(matrix, rhs)?;
+            let value = neighbours.iter().zip(solution.iter()).map(|((i, _), w)| w * samples[*i].value).sum();
+            let variance = sill
+                - solution[..n].iter().zip(point_covariances.iter()).map(|(w, c)| w * c).sum::<f64>()
+                - solution[n];
+
+            Some(Estimate { value, variance, samples: n })
+        })
+        .collect()
+}
+
+/// Solves the square system `matrix x = rhs` (row major) by gaussian elimination with partial
+/// pivoting, `None` when it is singular.
+pub fn solve(mut matrix: Vec<f64>, mut rhs: Vec<f64>) -> Option<Vec<f64>> {
+    let n = rhs.len();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|a, b| {
+            matrix[a * n + column].abs().total_cmp(&matrix[b * n + column].abs())
+        })?;
+        if matrix[pivot * n + column].abs() < 1e-12 {
+            return None;
+        }
+        if pivot != column {
+            for k in 0..n {
+                matrix.swap(pivot * n + k, column * n + k);
+            }
+            rhs.swap(pivot, column);
+        }
+
+        for row in column + 1..n {
+            let factor = matrix[row * n + column] / matrix[column * n + column];
+            if factor == 0.0 {
+                continue;
+            }
+            for k in column..n {
+                matrix[row * n + k] -= factor * matrix[column * n + k];
+            }
+            rhs[row] -= factor * rhs[column];
+        }
+    }
+
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let known: f64 = (row + 1..n).map(|k| matrix[row * n + k] * solution[k]).sum();
+        solution[row] = (rhs[row] - known) / matrix[row * n + row];
+    }
+    Some(solution)
+}
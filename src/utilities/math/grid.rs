@@ -0,0 +1,172 @@
+/// Position and size of a regular grid of square cells in plan, counted from its south-west
+/// corner.
+#[derive(Clone, Copy, PartialEq)]
+pub struct GridGeometry{
+    /// World coordinates of the lower left corner
+    pub origin: [f64;2],
+    pub cell: f64,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl GridGeometry {
+    /// Smallest grid of cells of side `cell` starting at `min` and reaching `max`.
+    pub fn covering(min: [f64;2], max: [f64;2], cell: f64) -> Self {
+        Self {
+            origin: min,
+            cell,
+            columns: ((max[0] - min[0]) / cell).ceil().max(1.0) as usize,
+            rows: ((max[1] - min[1]) / cell).ceil().max(1.0) as usize,
+        }
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    pub fn center(&self, column: usize, row: usize) -> [f64;2] {
+        [
+            self.origin[0] + (column as f64 + 0.5) * self.cell,
+            self.origin[1] + (row as f64 + 0.5) * self.cell,
+        ]
+    }
+}
+
+/// Values at the cell centers of a grid, row by row from the south, `None` where undefined.
+pub struct Grid{
+    pub geometry: GridGeometry,
+    pub values: Vec<Option<f64>>,
+}
+
+impl Grid {
+    pub fn from_fn(geometry: GridGeometry, value: impl Fn(f64, f64) -> Option<f64>) -> Self {
+        let values = (0..geometry.rows)
+            .flat_map(|row| (0..geometry.columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let [x, y] = geometry.center(column, row);
+                value(x, y)
+            })
+            .collect();
+        Self { geometry, values }
+    }
+
+    pub fn value(&self, column: usize, row: usize) -> Option<f64> {
+        self.values[row * self.geometry.columns + column]
+    }
+
+    /// Bilinear interpolation between the four cell centers around (x, y), `None` unless they
+    /// are all defined.
+    pub fn interpolate(&self, x: f64, y: f64) -> Option<f64> {
+        let u = (x - self.geometry.origin[0]) / self.geometry.cell - 0.5;
+        let v = (y - self.geometry.origin[1]) / self.geometry.cell - 0.5;
+        if u < 0.0 || v < 0.0 {
+            return None;
+        }
+        let (column, row) = (u.floor() as usize, v.floor() as usize);
+        if column >= self.geometry.columns || row >= self.geometry.rows {
+            return None;
+        }
+        // Along the last column or row the next center is the same one
+        let next_column = (column + 1).min(self.geometry.columns - 1);
+        let next_row = (row + 1).min(self.geometry.rows - 1);
+        let (s, t) = (u - column as f64, v - row as f64);
+        Some(
+            self.value(column, row)? * (1.0 - s) * (1.0 - t)
+                + self.value(next_column, row)? * s * (1.0 - t)
+                + self.value(next_column, next_row)? * s * t
+                + self.value(column, next_row)? * (1.0 - s) * t
+        )
+    }
+
+    /// Cell by cell combination of two grids with the same geometry, undefined where either is.
+    pub fn combine(&self, other: &Grid, f: impl Fn(f64, f64) -> f64) -> Option<Grid> {
+        if self.geometry != other.geometry {
+            return None;
+        }
+        let values = self.values.iter().zip(other.values.iter())
+            .map(|(a, b)| Some(f((*a)?, (*b)?)))
+            .collect();
+        Some(Self { geometry: self.geometry, values })
+    }
+
+    /// Smallest and largest defined values.
+    pub fn range(&self) -> Option<(f64, f64)> {
+        self.values.iter().flatten().fold(None, |range, value| match range {
+            None => Some((*value, *value)),
+            Some((min, max)) => Some((min.min(*value), max.max(*value))),
+        })
+    }
+
+    /// Segments in plan along which the values cross `level`, by marching squares between the
+    /// cell centers where the four corners are defined.
+    pub fn contour(&self, level: f64) -> Vec<[[f64;2];2]> {
+        let mut segments = Vec::new();
+        for row in 0..self.geometry.rows.saturating_sub(1) {
+            for column in 0..self.geometry.columns.saturating_sub(1) {
+                let corners = [(column, row), (column + 1, row), (column + 1, row + 1), (column, row + 1)];
+                let Some(values) = corners.iter()
+                    .map(|(c, r)| self.value(*c, *r))
+                    .collect::<Option<Vec<f64>>>() else {
+                    continue;
+                };
+
+                // Crossings on the edges in turn around the square, paired in that order
+                let crossings: Vec<[f64;2]> = (0..4)
+                    .filter_map(|edge| {
+                        let next = (edge + 1) % 4;
+                        let (a, b) = (values[edge], values[next]);
+                        if (a < level) == (b < level) {
+                            return None;
+                        }
+                        let t = (level - a) / (b - a);
+                        let start = self.geometry.center(corners[edge].0, corners[edge].1);
+                        let end = self.geometry.center(corners[next].0, corners[next].1);
+                        Some([start[0] + t * (end[0] - start[0]), start[1] + t * (end[1] - start[1])])
+                    })
+                    .collect();
+                for pair in crossings.chunks_exact(2) {
+                    segments.push([pair[0], pair[1]]);
+                }
+            }
+        }
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plane() -> Grid {
+        let geometry = GridGeometry::covering([0.0, 0.0], [10.0, 10.0], 1.0);
+        Grid::from_fn(geometry, |x, y| Some(x + 2.0 * y))
+    }
+
+    #[test]
+    fn interpolates_a_plane() {
+        let grid = plane();
+        let value = grid.interpolate(3.2, 4.7).unwrap();
+        assert!((value - 12.6).abs() < 1e-9);
+        // Before the first cell center
+        assert!(grid.interpolate(0.2, 5.0).is_none());
+    }
+
+    #[test]
+    fn contours_a_plane() {
+        let segments = plane().contour(10.0);
+        assert!(!segments.is_empty());
+        for point in segments.iter().flatten() {
+            assert!((point[0] + 2.0 * point[1] - 10.0).abs() < 1e-9, "{:?} is off the level", point);
+        }
+    }
+
+    #[test]
+    fn combines_where_both_are_defined() {
+        let geometry = GridGeometry::covering([0.0, 0.0], [2.0, 1.0], 1.0);
+        let left = Grid::from_fn(geometry, |x, _| (x < 1.0).then_some(x));
+        let all = Grid::from_fn(geometry, |_, _| Some(1.0));
+        let sum = left.combine(&all, |a, b| a + b).unwrap();
+        assert_eq!(sum.values, vec![Some(1.5), None]);
+        assert_eq!(sum.range(), Some((1.5, 1.5)));
+    }
+}
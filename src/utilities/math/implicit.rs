@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use super::analytic_geometry::{cross, dot, sub};
+use super::estimation::BlockGrid;
+
+/// Corners of a grid cell, x varying fastest then y then z.
+const CORNERS: [[usize;3]; 8] = [
+    [0, 0, 0], [1, 0, 0], [1, 1, 0], [0, 1, 0],
+    [0, 0, 1], [1, 0, 1], [1, 1, 1], [0, 1, 1],
+];
+/// Six tetrahedra around the diagonal from corner 0 to corner 6, which split the faces shared
+/// by neighbouring cells along the same diagonals.
+const TETRAHEDRA: [[usize;4]; 6] = [
+    [0, 5, 1, 6], [0, 1, 2, 6], [0, 2, 3, 6],
+    [0, 3, 7, 6], [0, 7, 4, 6], [0, 4, 5, 6],
+];
+
+/// Closed surface where the field `values` at the block centers of `grid` crosses zero, by
+/// marching tetrahedra. Negative values are inside, the centers on the border of the grid count
+/// as outside so the surface is capped there. Triangles are counter clockwise seen from outside,
+/// in world coordinates, and share the vertices of their common edges.
+///
+/// Marching tetrahedra stands in for marching cubes: it needs no case table and has no ambiguous
+/// faces, so neighbouring cells always agree and the surface has no holes.
+pub fn isosurface(grid: &BlockGrid, values: &[f64]) -> (Vec<[f64;3]>, Vec<[u32;3]>) {
+    let [nx, ny, nz] = grid.count;
+    let node = |[x, y, z]: [usize;3]| x + nx * (y + ny * z);
+    let value = |[x, y, z]: [usize;3]| {
+        let border = x == 0 || y == 0 || z == 0 || x + 1 == nx || y + 1 == ny || z + 1 == nz;
+        let value = values[node([x, y, z])];
+        if border { value.max(f64::EPSILON) } else { value }
+    };
+
+    let mut positions: Vec<[f64;3]> = Vec::new();
+    let mut triangles: Vec<[u32;3]> = Vec::new();
+    let mut edges: HashMap<(usize, usize), u32> = HashMap::new();
+
+    for z in 0..nz.saturating_sub(1) {
+        for y in 0..ny.saturating_sub(1) {
+            for x in 0..nx.saturating_sub(1) {
+                let corners = CORNERS.map(|[dx, dy, dz]| [x + dx, y + dy, z + dz]);
+                let corner_values = corners.map(value);
+                if corner_values.iter().all(|v| *v < 0.0) || corner_values.iter().all(|v| *v >= 0.0) {
+                    continue;
+                }
+
+                for tetrahedron in TETRAHEDRA {
+                    let (inside, outside): (Vec<usize>, Vec<usize>) = tetrahedron.into_iter()
+                        .partition(|corner| corner_values[*corner] < 0.0);
+                    if inside.is_empty() || outside.is_empty() {
+                        continue;
+                    }
+
+                    let mut vertex = |a: usize, b: usize| {
+                        let (node_a, node_b) = (node(corners[a]), node(corners[b]));
+                        *edges.entry((node_a.min(node_b), node_a.max(node_b))).or_insert_with(|| {
+                            let t = corner_values[a] / (corner_values[a] - corner_values[b]);
+                            let (start, end) = (grid.center(node_a), grid.center(node_b));
+                            positions.push([0, 1, 2].map(|i| start[i] + t * (end[i] - start[i])));
+                            (positions.len() - 1) as u32
+                        })
+                    };
+                    let polygon: Vec<u32> = match (inside.as_slice(), outside.as_slice()) {
+                        ([a], [b, c, d]) | ([b, c, d], [a]) => vec![vertex(*a, *b), vertex(*a, *c), vertex(*a, *d)],
+                        ([a, b], [c, d]) => vec![vertex(*a, *c), vertex(*a, *d), vertex(*b, *d), vertex(*b, *c)],
+                        _ => continue,
+                    };
+
+                    // Outwards is from an inside corner towards an outside one
+                    let outwards = sub(grid.center(node(corners[outside[0]])), grid.center(node(corners[inside[0]])));
+                    for pair in polygon[1..].windows(2) {
+                        let mut triangle = [polygon[0], pair[0], pair[1]];
+                        let [a, b, c] = triangle.map(|i| positions[i as usize]);
+                        if dot(cross(sub(b, a), sub(c, a)), outwards) < 0.0 {
+                            triangle.swap(1, 2);
+                        }
+                        triangles.push(triangle);
+                    }
+                }
+            }
+        }
+    }
+    (positions, triangles)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::utilities::math::analytic_geometry::norm;
+
+    const RADIUS: f64 = 6.3;
+
+    fn sphere() -> (Vec<[f64;3]>, Vec<[u32;3]>) {
+        let grid = BlockGrid { origin: [-10.5; 3], block_size: [1.0; 3], count: [21; 3] };
+        let values: Vec<f64> = (0..grid.block_count()).map(|node| norm(grid.center(node)) - RADIUS).collect();
+        isosurface(&grid, &values)
+    }
+
+    #[test]
+    fn sphere_vertices_lie_on_the_sphere() {
+        let (positions, triangles) = sphere();
+        assert!(!triangles.is_empty());
+        for position in positions {
+            assert!((norm(position) - RADIUS).abs() < 0.1, "{:?} is off the sphere", position);
+        }
+    }
+
+    #[test]
+    fn sphere_is_closed() {
+        let (_, triangles) = sphere();
+        // Every edge is walked once in each direction by the two triangles sharing it
+        let mut edges: HashSet<(u32, u32)> = HashSet::new();
+        for [a, b, c] in &triangles {
+            for edge in [(*a, *b), (*b, *c), (*c, *a)] {
+                assert!(edges.insert(edge), "edge {:?} is walked twice the same way", edge);
+            }
+        }
+        for (a, b) in &edges {
+            assert!(edges.contains(&(*b, *a)), "edge ({}, {}) is on the border", a, b);
+        }
+    }
+
+    #[test]
+    fn sphere_winds_outwards() {
+        let (positions, triangles) = sphere();
+        // Divergence theorem, positive when the triangles are counter clockwise seen from outside
+        let volume: f64 = triangles.iter()
+            .map(|triangle| {
+                let [a, b, c] = triangle.map(|i| positions[i as usize]);
+                dot(a, cross(b, c)) / 6.0
+            })
+            .sum();
+        let expected = 4.0 / 3.0 * std::f64::consts::PI * RADIUS.powi(3);
+        assert!((volume - expected).abs() < expected * 0.05, "volume {} instead of {}", volume, expected);
+    }
+}
@@ -1 +1,11 @@
-pub mod analytic_geometry;
\ No newline at end of file
+pub mod analytic_geometry;
+pub mod curves;
+pub mod grid;
+pub mod statistics;
+pub mod estimation;
+pub mod variogram;
+pub mod surface;
+pub mod section;
+pub mod volume;
+pub mod rbf;
+pub mod implicit;
@@ -0,0 +1,137 @@
+use super::analytic_geometry::{norm, sub};
+use super::estimation::{self, Sample};
+
+/// Radial function of the distance between two points.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kernel{
+    /// r² ln r, the smoothest interpolation of a surface in plan
+    ThinPlate,
+    /// r³, for fields in three dimensions
+    Cubic,
+}
+
+impl Kernel {
+    fn value(&self, r: f64) -> f64 {
+        match self {
+            Kernel::ThinPlate => if r > 0.0 { r * r * r.ln() } else { 0.0 },
+            Kernel::Cubic => r * r * r,
+        }
+    }
+}
+
+/// Radial basis function interpolant plus a linear trend. It goes through the samples, or close
+/// to them when smoothed.
+pub struct Rbf{
+    kernel: Kernel,
+    /// Coordinates taking part, 2 to interpolate elevations in plan
+    dimensions: usize,
+    /// Mean of the samples, subtracted so the system stays well conditioned
+    origin: [f64;3],
+    centers: Vec<[f64;3]>,
+    weights: Vec<f64>,
+    /// Constant then one coefficient per coordinate
+    trend: Vec<f64>,
+}
+
+impl Rbf {
+    /// Solves the dense system of every sample, so its cost grows with the cube of their number.
+    /// `None` with too few samples or a singular system, as coincident samples without smoothing.
+    pub fn fit(samples: &[Sample], kernel: Kernel, dimensions: usize, smoothing: f64) -> Option<Self> {
+        let n = samples.len();
+        if n <= dimensions {
+            return None;
+        }
+        let count = n as f64;
+        let origin = samples.iter()
+            .fold([0.0; 3], |sum, s| [0, 1, 2].map(|i| sum[i] + s.position[i] / count));
+        let centers: Vec<[f64;3]> = samples.iter()
+            .map(|s| project(sub(s.position, origin), dimensions))
+            .collect();
+
+        let size = n + dimensions + 1;
+        let mut matrix = vec![0.0; size * size];
+        let mut rhs = vec![0.0; size];
+        for (i, (center, sample)) in centers.iter().zip(samples).enumerate() {
+            for (j, other) in centers.iter().enumerate() {
+                matrix[i * size + j] = kernel.value(norm(sub(*center, *other)));
+            }
+            matrix[i * size + i] += smoothing;
+            matrix[i * size + n] = 1.0;
+            matrix[n * size + i] = 1.0;
+            for (d, coordinate) in center.iter().take(dimensions).enumerate() {
+                matrix[i * size + n + 1 + d] = *coordinate;
+                matrix[(n + 1 + d) * size + i] = *coordinate;
+            }
+            rhs[i] = sample.value;
+        }
+
+        let solution = estimation::solve(matrix, rhs)?;
+        Some(Self {
+            kernel,
+            dimensions,
+            origin,
+            centers,
+            weights: solution[..n].to_vec(),
+            trend: solution[n..].to_vec(),
+        })
+    }
+
+    pub fn value(&self, position: [f64;3]) -> f64 {
+        let point = project(sub(position, self.origin), self.dimensions);
+        let trend = self.trend[0] + point.iter().zip(&self.trend[1..]).map(|(c, t)| c * t).sum::<f64>();
+        let radial: f64 = self.centers.iter().zip(&self.weights)
+            .map(|(center, weight)| weight * self.kernel.value(norm(sub(point, *center))))
+            .sum();
+        trend + radial
+    }
+}
+
+/// Zeroes the coordinates past the first `dimensions`.
+fn project(mut point: [f64;3], dimensions: usize) -> [f64;3] {
+    for coordinate in point.iter_mut().skip(dimensions) {
+        *coordinate = 0.0;
+    }
+    point
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(value: impl Fn(f64, f64, f64) -> f64) -> Vec<Sample> {
+        [
+            [0.0, 0.0, 1.0], [10.0, 0.0, 2.0], [0.0, 10.0, 3.0], [10.0, 10.0, 0.0],
+            [5.0, 5.0, 5.0], [2.0, 7.0, 4.0], [8.0, 3.0, 6.0], [3.0, 1.0, 2.5],
+        ]
+        .map(|[x, y, z]| Sample { position: [x, y, z], value: value(x, y, z) })
+        .to_vec()
+    }
+
+    #[test]
+    fn goes_through_the_samples() {
+        for (kernel, dimensions) in [(Kernel::ThinPlate, 2), (Kernel::Cubic, 3)] {
+            let samples = samples(|x, y, z| (x * 0.3).sin() * 10.0 + y * y * 0.1 - z);
+            let rbf = Rbf::fit(&samples, kernel, dimensions, 0.0).unwrap();
+            for sample in &samples {
+                let value = rbf.value(sample.position);
+                assert!((value - sample.value).abs() < 1e-6, "{} instead of {}", value, sample.value);
+            }
+        }
+    }
+
+    #[test]
+    fn reproduces_a_plane() {
+        let samples = samples(|x, y, _| 2.0 * x - y + 3.0);
+        let rbf = Rbf::fit(&samples, Kernel::ThinPlate, 2, 0.0).unwrap();
+        for [x, y] in [[1.0, 1.0], [4.5, 8.0], [20.0, -5.0]] {
+            let value = rbf.value([x, y, 100.0]);
+            assert!((value - (2.0 * x - y + 3.0)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn needs_more_samples_than_dimensions() {
+        let samples = samples(|x, _, _| x);
+        assert!(Rbf::fit(&samples[..2], Kernel::ThinPlate, 2, 0.0).is_none());
+    }
+}
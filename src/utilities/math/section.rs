@@ -0,0 +1,108 @@
+/// Vertical section through a line in plan, keeping what lies within half its `width` on either
+/// side of the line. Coordinates on the section are the distance from `start` and the elevation.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Section{
+    pub start: [f64;2],
+    pub end: [f64;2],
+    pub width: f64,
+}
+
+impl Default for Section {
+    fn default() -> Self {
+        Self { start: [0.0, 0.0], end: [100.0, 0.0], width: 50.0 }
+    }
+}
+
+impl Section {
+    pub fn length(&self) -> f64 {
+        (self.end[0] - self.start[0]).hypot(self.end[1] - self.start[1])
+    }
+
+    /// Degrees clockwise from north of the direction from `start` to `end`.
+    pub fn azimuth(&self) -> f64 {
+        (self.end[0] - self.start[0]).atan2(self.end[1] - self.start[1]).to_degrees().rem_euclid(360.0)
+    }
+
+    fn axis(&self) -> [f64;2] {
+        let length = self.length().max(f64::EPSILON);
+        [(self.end[0] - self.start[0]) / length, (self.end[1] - self.start[1]) / length]
+    }
+
+    /// Point of the line in plan at `distance` from `start`.
+    pub fn point(&self, distance: f64) -> [f64;2] {
+        let axis = self.axis();
+        [self.start[0] + axis[0] * distance, self.start[1] + axis[1] * distance]
+    }
+
+    /// Distance along the line, signed distance across it (positive to the left) and elevation
+    /// of a world point.
+    pub fn project(&self, point: [f64;3]) -> [f64;3] {
+        let axis = self.axis();
+        let (dx, dy) = (point[0] - self.start[0], point[1] - self.start[1]);
+        [dx * axis[0] + dy * axis[1], dy * axis[0] - dx * axis[1], point[2]]
+    }
+
+    pub fn contains(&self, point: [f64;3]) -> bool {
+        let [along, across, _] = self.project(point);
+        along >= 0.0 && along <= self.length() && across.abs() <= self.width * 0.5
+    }
+
+    /// Part of the world segment from `a` to `b` inside the slab, as (distance, elevation) at
+    /// both ends.
+    pub fn clip_segment(&self, a: [f64;3], b: [f64;3]) -> Option<[[f64;2];2]> {
+        let pa = self.project(a);
+        let pb = self.project(b);
+        let half_width = self.width * 0.5;
+
+        // Liang–Barsky clipping against the slab sides and the line ends
+        let mut t0: f64 = 0.0;
+        let mut t1: f64 = 1.0;
+        for (start, delta, min, max) in [
+            (pa[0], pb[0] - pa[0], 0.0, self.length()),
+            (pa[1], pb[1] - pa[1], -half_width, half_width),
+        ] {
+            if delta.abs() < f64::EPSILON {
+                if start < min || start > max {
+                    return None;
+                }
+                continue;
+            }
+            let (enter, exit) = ((min - start) / delta, (max - start) / delta);
+            t0 = t0.max(enter.min(exit));
+            t1 = t1.min(enter.max(exit));
+            if t0 > t1 {
+                return None;
+            }
+        }
+
+        let at = |t: f64| [pa[0] + (pb[0] - pa[0]) * t, pa[2] + (pb[2] - pa[2]) * t];
+        Some([at(t0), at(t1)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECTION: Section = Section { start: [0.0, 0.0], end: [100.0, 0.0], width: 10.0 };
+
+    #[test]
+    fn clips_a_segment_across() {
+        let [a, b] = SECTION.clip_segment([50.0, -20.0, 0.0], [50.0, 20.0, 10.0]).unwrap();
+        assert!((a[0] - 50.0).abs() < 1e-9 && (a[1] - 3.75).abs() < 1e-9);
+        assert!((b[0] - 50.0).abs() < 1e-9 && (b[1] - 6.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn clips_a_segment_at_the_line_ends() {
+        let [a, b] = SECTION.clip_segment([-50.0, 0.0, 0.0], [50.0, 0.0, 0.0]).unwrap();
+        assert!(a[0].abs() < 1e-9);
+        assert!((b[0] - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn drops_a_segment_outside() {
+        assert!(SECTION.clip_segment([50.0, 20.0, 0.0], [60.0, 30.0, 0.0]).is_none());
+        assert!(SECTION.clip_segment([110.0, 0.0, 0.0], [120.0, 0.0, 0.0]).is_none());
+    }
+}
@@ -0,0 +1,255 @@
+/// Quantile of already sorted values using linear interpolation between the closest ranks.
+pub fn quantile(sorted: &[f64], q: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let position = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    let t = position - lower as f64;
+    Some(sorted[lower] + t * (sorted[upper] - sorted[lower]))
+}
+
+/// Sorts the finite values, dropping NaNs and infinities.
+pub fn sorted_finite(values: impl IntoIterator<Item = f64>) -> Vec<f64> {
+    let mut values: Vec<f64> = values.into_iter().filter(|v| v.is_finite()).collect();
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values
+}
+
+/// Tonnage, mean grade and contained metal of the blocks at or above a cutoff.
+#[derive(Clone, Copy)]
+pub struct GradeTonnage {
+    pub cutoff: f64,
+    pub tonnage: f64,
+    pub grade: f64,
+    pub metal: f64,
+}
+
+/// Grade–tonnage table of `blocks`, given as (grade, tonnage) pairs. Blocks without a finite
+/// grade are ignored.
+pub fn grade_tonnage(blocks: &[(f64, f64)], cutoffs: &[f64]) -> Vec<GradeTonnage> {
+    cutoffs.iter()
+        .map(|cutoff| {
+            let (tonnage, metal) = blocks.iter()
+                .filter(|(grade, _)| grade.is_finite() && grade >= cutoff)
+                .fold((0.0, 0.0), |(tonnage, metal), (grade, tonnes)| {
+                    (tonnage + tonnes, metal + grade * tonnes)
+                });
+            GradeTonnage {
+                cutoff: *cutoff,
+                tonnage,
+                grade: if tonnage > 0.0 { metal / tonnage } else { 0.0 },
+                metal,
+            }
+        })
+        .collect()
+}
+
+/// Percentiles listed by [`Summary`].
+pub const SUMMARY_PERCENTILES: [f64; 7] = [0.05, 0.10, 0.25, 0.50, 0.75, 0.90, 0.95];
+
+/// Univariate statistics of weighted values. The variance is the weighted population variance.
+pub struct Summary {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub variance: f64,
+    pub cv: f64,
+    /// Values at [`SUMMARY_PERCENTILES`]
+    pub percentiles: Vec<f64>,
+}
+
+/// Sorts the (value, weight) pairs with a finite value and a positive weight by value.
+pub fn sorted_weighted(values: impl IntoIterator<Item = (f64, f64)>) -> Vec<(f64, f64)> {
+    let mut values: Vec<(f64, f64)> = values.into_iter()
+        .filter(|(value, weight)| value.is_finite() && *weight > 0.0)
+        .collect();
+    values.sort_by(|a, b| a.0.total_cmp(&b.0));
+    values
+}
+
+/// Cumulative probability of each sorted weighted value, at the middle of its weight.
+pub fn cumulative_probabilities(sorted: &[(f64, f64)]) -> Vec<f64> {
+    let total: f64 = sorted.iter().map(|(_, weight)| weight).sum();
+    let mut cumulative = 0.0;
+    sorted.iter()
+        .map(|(_, weight)| {
+            let probability = (cumulative + weight * 0.5) / total;
+            cumulative += weight;
+            probability
+        })
+        .collect()
+}
+
+/// Quantile of sorted weighted values, interpolating between their cumulative probabilities.
+pub fn weighted_quantile(sorted: &[(f64, f64)], q: f64) -> Option<f64> {
+    let probabilities = cumulative_probabilities(sorted);
+    let upper = probabilities.iter().position(|p| *p >= q);
+    match upper {
+        None => sorted.last().map(|(value, _)| *value),
+        Some(0) => sorted.first().map(|(value, _)| *value),
+        Some(upper) => {
+            let (p0, p1) = (probabilities[upper - 1], probabilities[upper]);
+            let (v0, v1) = (sorted[upper - 1].0, sorted[upper].0);
+            Some(v0 + (q - p0) / (p1 - p0) * (v1 - v0))
+        }
+    }
+}
+
+pub fn summary(sorted: &[(f64, f64)]) -> Option<Summary> {
+    let total: f64 = sorted.iter().map(|(_, weight)| weight).sum();
+    if sorted.is_empty() || total <= 0.0 {
+        return None;
+    }
+
+    let mean = sorted.iter().map(|(value, weight)| value * weight).sum::<f64>() / total;
+    let variance = sorted.iter().map(|(value, weight)| weight * (value - mean).powi(2)).sum::<f64>() / total;
+
+    Some(Summary {
+        count: sorted.len(),
+        min: sorted[0].0,
+        max: sorted[sorted.len() - 1].0,
+        mean,
+        variance,
+        cv: variance.sqrt() / mean,
+        percentiles: SUMMARY_PERCENTILES.iter()
+            .filter_map(|q| weighted_quantile(sorted, *q))
+            .collect(),
+    })
+}
+
+/// Weighted frequency, in percent, of `bins` equal classes between the first and last values.
+/// Returns the class width and the frequency of each class.
+pub fn histogram(sorted: &[(f64, f64)], bins: usize) -> Option<(f64, Vec<f64>)> {
+    let (min, max) = (sorted.first()?.0, sorted.last()?.0);
+    let bins = bins.max(1);
+    let width = ((max - min) / bins as f64).max(f64::EPSILON);
+    let total: f64 = sorted.iter().map(|(_, weight)| weight).sum();
+
+    let mut frequencies = vec![0.0; bins];
+    for (value, weight) in sorted {
+        let bin = (((value - min) / width) as usize).min(bins - 1);
+        frequencies[bin] += weight / total * 100.0;
+    }
+    Some((width, frequencies))
+}
+
+/// Standard normal cumulative distribution, Abramowitz and Stegun 7.1.26.
+pub fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let polynomial = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - polynomial * (-x * x).exp();
+    if z >= 0.0 { 0.5 * (1.0 + erf) } else { 0.5 * (1.0 - erf) }
+}
+
+/// Inverse of the standard normal cumulative distribution, Acklam's rational approximation.
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [-3.96968302866538e+01, 2.20946098424520e+02, -2.75928510446969e+02, 1.38357751867269e+02, -3.06647980661472e+01, 2.50662827745924e+00];
+    const B: [f64; 5] = [-5.44760987982241e+01, 1.61585836858041e+02, -1.55698979859887e+02, 6.68013118877197e+01, -1.32806815528857e+01];
+    const C: [f64; 6] = [-7.78489400243029e-03, -3.22396458041136e-01, -2.40075827716184e+00, -2.54973253934373e+00, 4.37466414146497e+00, 2.93816398269878e+00];
+    const D: [f64; 4] = [7.78469570904146e-03, 3.22467129070040e-01, 2.44513413714300e+00, 3.75440866190742e+00];
+    const LOW: f64 = 0.02425;
+
+    let p = p.clamp(1e-12, 1.0 - 1e-12);
+    if p < LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -normal_quantile(1.0 - p)
+    }
+}
+
+/// Row of a decile analysis: a class of the weighted values and its share of the metal content.
+pub struct DecileRow {
+    pub label: String,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub metal_percent: f64,
+}
+
+/// Decile analysis of sorted weighted values: the ten deciles by cumulative weight followed by
+/// the percentiles of the top decile.
+pub fn decile_analysis(sorted: &[(f64, f64)]) -> Vec<DecileRow> {
+    let total_weight: f64 = sorted.iter().map(|(_, weight)| weight).sum();
+    let total_metal: f64 = sorted.iter().map(|(value, weight)| value * weight).sum();
+    if sorted.is_empty() || total_weight <= 0.0 {
+        return Vec::new();
+    }
+
+    let class = |from: f64, to: f64, label: String| -> Option<DecileRow> {
+        let mut cumulative = 0.0;
+        let members: Vec<(f64, f64)> = sorted.iter()
+            .filter(|(_, weight)| {
+                let middle = (cumulative + weight * 0.5) / total_weight;
+                cumulative += weight;
+                middle >= from && middle < to
+            })
+            .copied()
+            .collect();
+
+        let weight: f64 = members.iter().map(|(_, weight)| weight).sum();
+        let metal: f64 = members.iter().map(|(value, weight)| value * weight).sum();
+        Some(DecileRow {
+            label,
+            min: members.first()?.0,
+            max: members.last()?.0,
+            mean: metal / weight,
+            metal_percent: if total_metal != 0.0 { metal / total_metal * 100.0 } else { 0.0 },
+        })
+    };
+
+    let deciles = (0..10).filter_map(|i| {
+        let to = if i == 9 { f64::MAX } else { (i + 1) as f64 / 10.0 };
+        class(i as f64 / 10.0, to, format!("{}-{}%", i * 10, (i + 1) * 10))
+    });
+    let top = (90..100).filter_map(|i| {
+        let to = if i == 99 { f64::MAX } else { (i + 1) as f64 / 100.0 };
+        class(i as f64 / 100.0, to, format!("{}-{}%", i, i + 1))
+    });
+    deciles.chain(top).collect()
+}
+
+/// Least squares line through (x, y) pairs and the Pearson correlation of the pairs.
+#[derive(Clone, Copy)]
+pub struct Regression {
+    pub slope: f64,
+    pub intercept: f64,
+    pub correlation: f64,
+}
+
+/// Fits y = slope·x + intercept to the pairs with both values finite. `None` with fewer than two
+/// pairs or when every x is the same.
+pub fn linear_regression(pairs: impl IntoIterator<Item = (f64, f64)>) -> Option<Regression> {
+    let pairs: Vec<(f64, f64)> = pairs.into_iter().filter(|(x, y)| x.is_finite() && y.is_finite()).collect();
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let (sxx, syy, sxy) = pairs.iter().fold((0.0, 0.0, 0.0), |(sxx, syy, sxy), (x, y)| {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        (sxx + dx * dx, syy + dy * dy, sxy + dx * dy)
+    });
+    if sxx <= 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    Some(Regression {
+        slope,
+        intercept: mean_y - slope * mean_x,
+        correlation: if syy > 0.0 { sxy / (sxx * syy).sqrt() } else { f64::NAN },
+    })
+}
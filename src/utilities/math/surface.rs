@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+/// Elevation lookup on a triangulated surface in world coordinates (x, y, elevation). Triangles
+/// are bucketed on a regular plan grid so a lookup only tests the triangles of one cell.
+pub struct SurfaceSampler{
+    positions: Vec<[f64;3]>,
+    triangles: Vec<[u32;3]>,
+    cell: f64,
+    cells: HashMap<[i64;2], Vec<usize>>,
+}
+
+impl SurfaceSampler {
+    pub fn new(positions: Vec<[f64;3]>, triangles: Vec<[u32;3]>) -> Self {
+        let (min, max) = plan_bounds(&positions);
+        // Around one triangle per cell on average
+        let area = ((max[0] - min[0]) * (max[1] - min[1])).max(f64::EPSILON);
+        let cell = (area / triangles.len().max(1) as f64).sqrt().max(f64::EPSILON);
+
+        let mut sampler = Self { positions, triangles, cell, cells: HashMap::new() };
+        for (i, triangle) in sampler.triangles.iter().enumerate() {
+            let corners = triangle.map(|v| sampler.positions[v as usize]);
+            let low = sampler.key(
+                corners.iter().map(|c| c[0]).fold(f64::MAX, f64::min),
+                corners.iter().map(|c| c[1]).fold(f64::MAX, f64::min),
+            );
+            let high = sampler.key(
+                corners.iter().map(|c| c[0]).fold(f64::MIN, f64::max),
+                corners.iter().map(|c| c[1]).fold(f64::MIN, f64::max),
+            );
+            for x in low[0]..=high[0] {
+                for y in low[1]..=high[1] {
+                    sampler.cells.entry([x, y]).or_default().push(i);
+                }
+            }
+        }
+        sampler
+    }
+
+    /// Lower and upper corners of the surface in plan.
+    pub fn bounds(&self) -> ([f64;2], [f64;2]) {
+        plan_bounds(&self.positions)
+    }
+
+    fn key(&self, x: f64, y: f64) -> [i64;2] {
+        [(x / self.cell).floor() as i64, (y / self.cell).floor() as i64]
+    }
+
+    /// Elevation of the surface above (x, y), `None` outside of it. Where triangles overlap in
+    /// plan the highest one is used.
+    pub fn elevation(&self, x: f64, y: f64) -> Option<f64> {
+        self.cells.get(&self.key(x, y))?
+            .iter()
+            .filter_map(|i| {
+                let [a, b, c] = self.triangles[*i].map(|v| self.positions[v as usize]);
+                let determinant = (b[1] - c[1]) * (a[0] - c[0]) + (c[0] - b[0]) * (a[1] - c[1]);
+                if determinant.abs() < f64::EPSILON {
+                    return None;
+                }
+                let u = ((b[1] - c[1]) * (x - c[0]) + (c[0] - b[0]) * (y - c[1])) / determinant;
+                let v = ((c[1] - a[1]) * (x - c[0]) + (a[0] - c[0]) * (y - c[1])) / determinant;
+                let w = 1.0 - u - v;
+                let tolerance = -1e-9;
+                (u >= tolerance && v >= tolerance && w >= tolerance).then(|| u * a[2] + v * b[2] + w * c[2])
+            })
+            .reduce(f64::max)
+    }
+
+    /// First point where a downhole path of (depth, position) stations crosses the surface,
+    /// with its depth. Each segment is sampled at the spacing of the triangles and the crossing
+    /// is interpolated linearly between the samples either side of the surface.
+    pub fn pierce(&self, stations: &[(f64, [f64;3])]) -> Option<(f64, [f64;3])> {
+        let height = |point: [f64;3]| self.elevation(point[0], point[1]).map(|elevation| point[2] - elevation);
+        let mut previous: Option<(f64, [f64;3], f64)> = None;
+        for pair in stations.windows(2) {
+            let ((from, a), (to, b)) = (pair[0], pair[1]);
+            let length = (b[0] - a[0]).hypot(b[1] - a[1]).hypot(b[2] - a[2]);
+            let steps = (length / self.cell).ceil().clamp(1.0, 10_000.0) as usize;
+            // The first point of a segment is the last one of the previous
+            let first = if previous.is_some() { 1 } else { 0 };
+            for step in first..=steps {
+                let t = step as f64 / steps as f64;
+                let point = [a[0] + t * (b[0] - a[0]), a[1] + t * (b[1] - a[1]), a[2] + t * (b[2] - a[2])];
+                let depth = from + t * (to - from);
+                let Some(current) = height(point) else {
+                    previous = None;
+                    continue;
+                };
+                if let Some((previous_depth, previous_point, previous_height)) = previous {
+                    if (previous_height >= 0.0) != (current >= 0.0) {
+                        let f = previous_height / (previous_height - current);
+                        return Some((
+                            previous_depth + f * (depth - previous_depth),
+                            [0, 1, 2].map(|i| previous_point[i] + f * (point[i] - previous_point[i])),
+                        ));
+                    }
+                }
+                previous = Some((depth, point, current));
+            }
+        }
+        None
+    }
+}
+
+fn plan_bounds(positions: &[[f64;3]]) -> ([f64;2], [f64;2]) {
+    positions.iter().fold(([f64::MAX; 2], [f64::MIN; 2]), |(min, max), p| {
+        ([min[0].min(p[0]), min[1].min(p[1])], [max[0].max(p[0]), max[1].max(p[1])])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plate() -> SurfaceSampler {
+        SurfaceSampler::new(
+            vec![[0.0, 0.0, 5.0], [10.0, 0.0, 5.0], [10.0, 10.0, 5.0], [0.0, 10.0, 5.0]],
+            vec![[0, 1, 2], [0, 2, 3]],
+        )
+    }
+
+    #[test]
+    fn elevation_inside_only() {
+        let surface = plate();
+        assert_eq!(surface.elevation(3.0, 7.0), Some(5.0));
+        assert_eq!(surface.elevation(11.0, 7.0), None);
+    }
+
+    #[test]
+    fn pierces_a_vertical_hole() {
+        let stations = [(0.0, [3.0, 3.0, 10.0]), (4.0, [3.0, 3.0, 6.0]), (10.0, [3.0, 3.0, 0.0])];
+        let (depth, position) = plate().pierce(&stations).unwrap();
+        assert!((depth - 5.0).abs() < 1e-9);
+        assert!((position[2] - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn misses_a_hole_beside_it() {
+        let stations = [(0.0, [20.0, 3.0, 10.0]), (10.0, [20.0, 3.0, 0.0])];
+        assert!(plate().pierce(&stations).is_none());
+        let short = [(0.0, [3.0, 3.0, 10.0]), (2.0, [3.0, 3.0, 8.0])];
+        assert!(plate().pierce(&short).is_none());
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::reflect::{FromReflect, Reflect};
+
+use super::estimation::{Anisotropy, Metric, Sample};
+use super::analytic_geometry::{self, dot, norm, sub};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect, FromReflect)]
+pub enum StructureKind{
+    Spherical,
+    Exponential,
+    Gaussian,
+}
+
+impl StructureKind {
+    pub fn all() -> [StructureKind; 3] {
+        [StructureKind::Spherical, StructureKind::Exponential, StructureKind::Gaussian]
+    }
+
+    /// Normalized variogram at `r` ranges. Exponential and gaussian use the practical range,
+    /// reaching 95% of the sill at `r = 1`.
+    pub fn value(&self, r: f64) -> f64 {
+        match self {
+            StructureKind::Spherical => if r < 1.0 { 1.5 * r - 0.5 * r.powi(3) } else { 1.0 },
+            StructureKind::Exponential => 1.0 - (-3.0 * r).exp(),
+            StructureKind::Gaussian => 1.0 - (-3.0 * r * r).exp(),
+        }
+    }
+}
+
+/// Nested structure with its own sill contribution, ranges and orientation.
+#[derive(Clone, Copy, PartialEq, Reflect, FromReflect)]
+pub struct VariogramStructure{
+    pub kind: StructureKind,
+    pub sill: f64,
+    pub anisotropy: Anisotropy,
+}
+
+#[derive(Clone, PartialEq, Reflect, FromReflect)]
+pub struct VariogramModel{
+    pub nugget: f64,
+    pub structures: Vec<VariogramStructure>,
+}
+
+impl Default for VariogramModel {
+    fn default() -> Self {
+        Self {
+            nugget: 0.1,
+            structures: vec![VariogramStructure{
+                kind: StructureKind::Spherical,
+                sill: 0.9,
+                anisotropy: Anisotropy::default(),
+            }],
+        }
+    }
+}
+
+/// [`VariogramModel`] with the anisotropy metrics computed once, for repeated evaluation.
+pub struct CompiledVariogram{
+    nugget: f64,
+    structures: Vec<(StructureKind, f64, Metric)>,
+}
+
+impl VariogramModel {
+    pub fn compile(&self) -> CompiledVariogram {
+        CompiledVariogram {
+            nugget: self.nugget,
+            structures: self.structures.iter()
+                .map(|s| (s.kind, s.sill, s.anisotropy.metric()))
+                .collect(),
+        }
+    }
+}
+
+impl CompiledVariogram {
+    pub fn sill(&self) -> f64 {
+        self.nugget + self.structures.iter().map(|s| s.1).sum::<f64>()
+    }
+
+    /// Semivariance for the separation `h` in world coordinates.
+    pub fn gamma(&self, h: [f64;3]) -> f64 {
+        if norm(h) < f64::EPSILON {
+            return 0.0;
+        }
+        self.nugget + self.structures.iter()
+            .map(|(kind, sill, metric)| sill * kind.value(norm(metric.scaled(h))))
+            .sum::<f64>()
+    }
+
+    pub fn covariance(&self, h: [f64;3]) -> f64 {
+        self.sill() - self.gamma(h)
+    }
+}
+
+/// Direction and binning of an experimental variogram. Pairs are kept when their separation is
+/// within `tolerance` degrees of the direction and `bandwidth` of its axis.
+#[derive(Clone, Copy)]
+pub struct VariogramDirection{
+    pub azimuth: f64,
+    pub dip: f64,
+    pub tolerance: f64,
+    pub bandwidth: f64,
+    pub lag: f64,
+    pub lags: usize,
+}
+
+impl Default for VariogramDirection {
+    fn default() -> Self {
+        Self { azimuth: 0.0, dip: 0.0, tolerance: 22.5, bandwidth: 50.0, lag: 10.0, lags: 15 }
+    }
+}
+
+/// Lag of an experimental variogram, at the mean separation of its pairs.
+#[derive(Clone, Copy)]
+pub struct ExperimentalPoint{
+    pub distance: f64,
+    pub gamma: f64,
+    pub pairs: usize,
+}
+
+/// Semivariance of the sample pairs along `direction`, binned every lag with a tolerance of half
+/// a lag. Lags without pairs are left out.
+pub fn experimental_variogram(samples: &[Sample], direction: &VariogramDirection) -> Vec<ExperimentalPoint> {
+    let axis = analytic_geometry::direction(direction.azimuth, direction.dip);
+    let min_cos = direction.tolerance.clamp(0.0, 90.0).to_radians().cos();
+
+    let mut distances = vec![0.0; direction.lags];
+    let mut gammas = vec![0.0; direction.lags];
+    let mut pairs = vec![0usize; direction.lags];
+
+    for (i, a) in samples.iter().enumerate() {
+        for b in samples[i + 1..].iter() {
+            let h = sub(b.position, a.position);
+            let distance = norm(h);
+            if distance < f64::EPSILON {
+                continue;
+            }
+
+            let along = dot(h, axis).abs();
+            if along / distance < min_cos || (distance * distance - along * along).max(0.0).sqrt() > direction.bandwidth {
+                continue;
+            }
+
+            let bin = (distance / direction.lag + 0.5).floor() as usize;
+            if bin == 0 || bin > direction.lags {
+                continue;
+            }
+
+            distances[bin - 1] += distance;
+            gammas[bin - 1] += 0.5 * (b.value - a.value).powi(2);
+            pairs[bin - 1] += 1;
+        }
+    }
+
+    (0..direction.lags)
+        .filter(|lag| pairs[*lag] > 0)
+        .map(|lag| ExperimentalPoint{
+            distance: distances[lag] / pairs[lag] as f64,
+            gamma: gammas[lag] / pairs[lag] as f64,
+            pairs: pairs[lag],
+        })
+        .collect()
+}
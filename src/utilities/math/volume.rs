@@ -0,0 +1,133 @@
+use super::analytic_geometry;
+use super::surface::SurfaceSampler;
+
+/// Elevations of the two surfaces at the center of a grid cell.
+pub struct CutFillCell{
+    pub x: f64,
+    pub y: f64,
+    pub reference: f64,
+    pub compared: f64,
+}
+
+/// Volumes between a reference surface and a compared one, fill where the compared surface is
+/// above the reference and cut where it is below.
+pub struct CutFill{
+    pub cut: f64,
+    pub fill: f64,
+    /// Plan area where both surfaces are defined
+    pub area: f64,
+    pub cells: Vec<CutFillCell>,
+}
+
+impl CutFill {
+    pub fn net(&self) -> f64 {
+        self.fill - self.cut
+    }
+}
+
+/// Plan extent common to both surfaces, within the extent of `boundary` when given. `None` when
+/// it is empty.
+pub fn overlap(
+    reference: &SurfaceSampler,
+    compared: &SurfaceSampler,
+    boundary: Option<&[[f64;2]]>,
+) -> Option<([f64;2], [f64;2])> {
+    let (reference_min, reference_max) = reference.bounds();
+    let (compared_min, compared_max) = compared.bounds();
+    let mut min = [reference_min[0].max(compared_min[0]), reference_min[1].max(compared_min[1])];
+    let mut max = [reference_max[0].min(compared_max[0]), reference_max[1].min(compared_max[1])];
+    if let Some(boundary) = boundary {
+        let low = boundary.iter().fold([f64::MAX; 2], |low, v| [low[0].min(v[0]), low[1].min(v[1])]);
+        let high = boundary.iter().fold([f64::MIN; 2], |high, v| [high[0].max(v[0]), high[1].max(v[1])]);
+        min = [min[0].max(low[0]), min[1].max(low[1])];
+        max = [max[0].min(high[0]), max[1].min(high[1])];
+    }
+    (min[0] < max[0] && min[1] < max[1]).then_some((min, max))
+}
+
+/// Samples both surfaces at the center of square cells of side `cell` covering their
+/// [`overlap`], within `boundary` in plan when given. `None` when the surfaces do not overlap.
+pub fn cut_fill(
+    reference: &SurfaceSampler,
+    compared: &SurfaceSampler,
+    cell: f64,
+    boundary: Option<&[[f64;2]]>,
+) -> Option<CutFill> {
+    let (min, max) = overlap(reference, compared, boundary)?;
+
+    let columns = ((max[0] - min[0]) / cell).ceil().max(0.0) as usize;
+    let rows = ((max[1] - min[1]) / cell).ceil().max(0.0) as usize;
+    let cell_area = cell * cell;
+
+    let mut result = CutFill { cut: 0.0, fill: 0.0, area: 0.0, cells: Vec::new() };
+    for column in 0..columns {
+        for row in 0..rows {
+            let x = min[0] + (column as f64 + 0.5) * cell;
+            let y = min[1] + (row as f64 + 0.5) * cell;
+            if matches!(boundary, Some(boundary) if !analytic_geometry::point_in_polygon([x, y], boundary)) {
+                continue;
+            }
+            let (Some(reference), Some(compared)) = (reference.elevation(x, y), compared.elevation(x, y)) else {
+                continue;
+            };
+
+            let thickness = compared - reference;
+            if thickness > 0.0 {
+                result.fill += thickness * cell_area;
+            } else {
+                result.cut -= thickness * cell_area;
+            }
+            result.area += cell_area;
+            result.cells.push(CutFillCell { x, y, reference, compared });
+        }
+    }
+
+    (!result.cells.is_empty()).then_some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Square plate from (0, 0) to (10, 10) at `elevation`.
+    fn plate(elevation: f64) -> SurfaceSampler {
+        SurfaceSampler::new(
+            vec![[0.0, 0.0, elevation], [10.0, 0.0, elevation], [10.0, 10.0, elevation], [0.0, 10.0, elevation]],
+            vec![[0, 1, 2], [0, 2, 3]],
+        )
+    }
+
+    #[test]
+    fn fill_between_flat_plates() {
+        let result = cut_fill(&plate(0.0), &plate(2.0), 1.0, None).unwrap();
+        assert!((result.fill - 200.0).abs() < 1e-9);
+        assert_eq!(result.cut, 0.0);
+        assert!((result.area - 100.0).abs() < 1e-9);
+        assert_eq!(result.cells.len(), 100);
+    }
+
+    #[test]
+    fn cut_between_flat_plates() {
+        let result = cut_fill(&plate(5.0), &plate(2.0), 0.5, None).unwrap();
+        assert!((result.cut - 300.0).abs() < 1e-9);
+        assert!((result.net() + 300.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn within_a_boundary() {
+        let boundary = [[2.0, 2.0], [6.0, 2.0], [6.0, 6.0], [2.0, 6.0]];
+        let result = cut_fill(&plate(0.0), &plate(2.0), 1.0, Some(&boundary)).unwrap();
+        assert!((result.area - 16.0).abs() < 1e-9);
+        assert!((result.fill - 32.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn apart_plates_do_not_overlap() {
+        let apart = SurfaceSampler::new(
+            vec![[20.0, 0.0, 0.0], [30.0, 0.0, 0.0], [30.0, 10.0, 0.0]],
+            vec![[0, 1, 2]],
+        );
+        assert!(overlap(&plate(0.0), &apart, None).is_none());
+        assert!(cut_fill(&plate(0.0), &apart, 1.0, None).is_none());
+    }
+}